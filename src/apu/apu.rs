@@ -5,11 +5,16 @@
 //! 5-step), and [APU Mixer](https://www.nesdev.org/wiki/APU_Mixer) (non-linear). Registers $4000–$4013,
 //! $4015, $4017. See [APU registers](https://www.nesdev.org/wiki/APU_registers).
 //!
+//! Samples pass through the console's output filters (two high-passes, one low-pass) unless
+//! `APU::set_filters_enabled` turns them off.
+//!
 //! ## Timing
 //!
 //! - Pulse and noise: timer clocked every 2 CPU cycles (APU "half cycle").
 //! - Triangle: timer at CPU rate. Length/envelope/sweep clocked by frame counter (~240 Hz).
 //! - DMC: rate from lookup table; when sample buffer empty, CPU is stalled 4 cycles for PRG read.
+//! - PAL (2A07): its own noise and DMC period tables and a longer frame counter sequence; see
+//!   `ApuTiming`.
 //!
 //! ## References
 //!
@@ -17,9 +22,12 @@
 //! - [APU Noise](https://www.nesdev.org/wiki/APU_Noise), [APU DMC](https://www.nesdev.org/wiki/APU_DMC)
 //! - [APU Length Counter](https://www.nesdev.org/wiki/APU_Length_Counter), [APU Envelope](https://www.nesdev.org/wiki/APU_Envelope)
 
-/// NTSC CPU clock ~ 1.789773 MHz. We generate one sample every CYCLES_PER_SAMPLE CPU cycles (~40.56)
-/// to get 44.1 kHz output. See Cycle_reference_chart.
-const CYCLES_PER_SAMPLE: f64 = 1_789_773.0 / 44_100.0;
+use crate::region::Region;
+
+/// Default output sample rate; change it with `APU::set_sample_rate`. Each sample averages the
+/// mixer over `cpu_clock_hz / rate` CPU cycles (~40.58 NTSC, ~37.70 PAL at 44.1 kHz). See
+/// Cycle_reference_chart.
+const SAMPLE_RATE: f64 = 44_100.0;
 
 /// Length counter lookup table: 5-bit index from register → count. APU_Length_Counter.
 const LENGTH_TABLE: [u8; 32] = [
@@ -32,6 +40,11 @@ const NOISE_PERIOD_TABLE: [u16; 16] = [
     4, 8, 16, 32, 64, 96, 128, 160, 202, 254, 380, 508, 762, 1016, 2034, 4068,
 ];
 
+/// Noise channel period table (PAL). APU_Noise.
+const NOISE_PERIOD_TABLE_PAL: [u16; 16] = [
+    4, 8, 14, 30, 60, 88, 118, 148, 188, 236, 354, 472, 708, 944, 1890, 3778,
+];
+
 /// Pulse channel duty cycles (8 steps). Duty 0=12.5%, 1=25%, 2=50%, 3=25% negated. Sequencer steps
 /// 0→7→6→…→1. APU_Pulse. Output is volume when step is 1, else 0.
 const PULSE_DUTY: [[u8; 8]; 4] = [
@@ -47,12 +60,100 @@ const TRIANGLE_SEQUENCE: [u8; 32] = [
     13, 14, 15,
 ];
 
-/// 4-step frame counter: resets every 29830 CPU cycles. Quarter/half frame at 7457, 14913, 22371;
-/// IRQ (if not inhibited) at 29829. APU_Frame_Counter.
-const FRAME_4STEP_RESET: u32 = 29830;
+/// Region-dependent APU constants. See APU_Frame_Counter, APU_Noise, APU_DMC.
+#[derive(Clone, Copy)]
+struct ApuTiming {
+    /// Frame counter steps 1–4 in CPU cycles (NTSC 7457, 14913, 22371, 29829). The 4-step
+    /// sequence raises the IRQ (if not inhibited) one cycle before step 4 and restarts one cycle
+    /// after it.
+    frame_steps: [u32; 4],
+    /// Last step of the 5-step sequence (no IRQ); it restarts one cycle later.
+    frame_step5: u32,
+    noise_periods: &'static [u16; 16],
+    dmc_rates: &'static [u16; 16],
+    cpu_clock_hz: f64,
+}
+
+impl ApuTiming {
+    fn new(region: Region) -> Self {
+        let (frame_steps, frame_step5, noise_periods, dmc_rates) = match region {
+            Region::Ntsc => (
+                [7457, 14913, 22371, 29829],
+                37281,
+                &NOISE_PERIOD_TABLE,
+                &DMC_RATE_TABLE,
+            ),
+            Region::Pal => (
+                [8313, 16627, 24939, 33253],
+                41565,
+                &NOISE_PERIOD_TABLE_PAL,
+                &DMC_RATE_TABLE_PAL,
+            ),
+        };
+        Self {
+            frame_steps,
+            frame_step5,
+            noise_periods,
+            dmc_rates,
+            cpu_clock_hz: region.cpu_clock_hz(),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Length counter: shared by pulse, triangle and noise
+// -----------------------------------------------------------------------------
+
+/// Length counter with the hardware's write/clock ordering (APU_Length_Counter). Register writes
+/// are staged and applied by `commit` after the frame counter has run for the cycle, so when a
+/// reload lands on the same cycle as a half-frame clock, the reload is ignored if the clock
+/// decremented the counter (it was nonzero and not halted); a halt flag change likewise takes
+/// effect only after that cycle's clock.
+#[derive(Clone, Default)]
+struct LengthCounter {
+    counter: u8,
+    /// Halt flag (also envelope loop / triangle linear control).
+    halt: bool,
+    /// Halt flag written this cycle, applied on `commit`.
+    new_halt: bool,
+    /// Reload value written this cycle and the counter value at the time of the write.
+    reload: Option<(u8, u8)>,
+}
+
+impl LengthCounter {
+    /// Stage a reload from a 5-bit `LENGTH_TABLE` index (bits 3–7 of $4003/$4007/$400B/$400F).
+    fn load(&mut self, index: u8) {
+        self.reload = Some((LENGTH_TABLE[index as usize & 0x1F], self.counter));
+    }
+
+    /// Stage a halt flag change.
+    fn set_halt(&mut self, halt: bool) {
+        self.new_halt = halt;
+    }
+
+    /// Half-frame clock.
+    fn clock(&mut self) {
+        if !self.halt && self.counter > 0 {
+            self.counter -= 1;
+        }
+    }
 
-/// 5-step frame counter: no IRQ; resets every 37282 cycles. Extra half-frame at 37281.
-const FRAME_5STEP_RESET: u32 = 37282;
+    /// Apply this cycle's writes (after any frame counter clock).
+    fn commit(&mut self) {
+        if let Some((value, before)) = self.reload.take()
+            && self.counter == before
+        {
+            self.counter = value;
+        }
+        self.halt = self.new_halt;
+    }
+
+    /// Channel disabled via $4015: counter forced to 0, pending reload dropped.
+    fn clear(&mut self) {
+        self.counter = 0;
+        self.reload = None;
+    }
+}
 
 // -----------------------------------------------------------------------------
 // Pulse channel ($4000–$4003 = pulse 1, $4004–$4007 = pulse 2)
@@ -60,11 +161,10 @@ const FRAME_5STEP_RESET: u32 = 37282;
 // -----------------------------------------------------------------------------
 
 /// Pulse channel: square wave with configurable duty, volume/envelope, frequency sweep, length counter.
-#[derive(Default)]
+#[derive(Clone, Default)]
 struct Pulse {
     enabled: bool,
     duty: u8,
-    length_halt: bool,
     constant_volume: bool,
     volume: u8,
     sweep_enable: bool,
@@ -74,7 +174,7 @@ struct Pulse {
     timer_period: u16,
     timer: u16,
     sequencer_step: u8,
-    length_counter: u8,
+    length: LengthCounter,
     envelope_start: bool,
     envelope_divider: u8,
     envelope_decay: u8,
@@ -86,7 +186,7 @@ impl Pulse {
     /// $4000/$4004: duty, length halt, constant volume, volume/envelope period.
     fn write_4000(&mut self, data: u8) {
         self.duty = (data >> 6) & 3;
-        self.length_halt = data & 0x20 != 0;
+        self.length.set_halt(data & 0x20 != 0);
         self.constant_volume = data & 0x10 != 0;
         self.volume = data & 0x0F;
         self.envelope_start = true;
@@ -110,18 +210,12 @@ impl Pulse {
     fn write_4003(&mut self, data: u8) {
         self.timer_period = (self.timer_period & 0x00FF) | ((data & 7) as u16) << 8;
         if self.enabled {
-            self.length_counter = LENGTH_TABLE[(data >> 3) as usize & 0x1F];
+            self.length.load(data >> 3);
         }
         self.envelope_start = true;
         self.sequencer_step = 0;
     }
 
-    fn clock_length(&mut self) {
-        if !self.length_halt && self.length_counter > 0 {
-            self.length_counter -= 1;
-        }
-    }
-
     fn clock_envelope(&mut self) {
         if self.envelope_start {
             self.envelope_decay = 15;
@@ -133,7 +227,7 @@ impl Pulse {
             self.envelope_divider = self.volume;
             if self.envelope_decay > 0 {
                 self.envelope_decay -= 1;
-            } else if self.length_halt {
+            } else if self.length.halt {
                 self.envelope_decay = 15;
             }
         }
@@ -194,7 +288,7 @@ impl Pulse {
 
     fn output(&self, sweep_silence: bool) -> u8 {
         if !self.enabled
-            || self.length_counter == 0
+            || self.length.counter == 0
             || sweep_silence
             || PULSE_DUTY[self.duty as usize][self.sequencer_step as usize] == 0
         {
@@ -224,14 +318,13 @@ impl Pulse {
 // -----------------------------------------------------------------------------
 
 /// Triangle channel: 32-step triangle wave, linear counter + length counter, no volume control.
-#[derive(Default)]
+#[derive(Clone, Default)]
 struct Triangle {
     enabled: bool,
-    length_halt: bool,
     linear_load: u8,
     timer_period: u16,
     timer: u16,
-    length_counter: u8,
+    length: LengthCounter,
     linear_counter: u8,
     linear_reload: bool,
     sequencer_step: u8,
@@ -240,7 +333,7 @@ struct Triangle {
 impl Triangle {
     /// $4008: length halt, linear counter load value.
     fn write_4008(&mut self, data: u8) {
-        self.length_halt = data & 0x80 != 0;
+        self.length.set_halt(data & 0x80 != 0);
         self.linear_load = data & 0x7F;
     }
 
@@ -253,31 +346,25 @@ impl Triangle {
     fn write_400b(&mut self, data: u8) {
         self.timer_period = (self.timer_period & 0x00FF) | ((data & 7) as u16) << 8;
         if self.enabled {
-            self.length_counter = LENGTH_TABLE[(data >> 3) as usize & 0x1F];
+            self.length.load(data >> 3);
         }
         self.linear_reload = true;
     }
 
-    fn clock_length(&mut self) {
-        if !self.length_halt && self.length_counter > 0 {
-            self.length_counter -= 1;
-        }
-    }
-
     fn clock_linear(&mut self) {
         if self.linear_reload {
             self.linear_counter = self.linear_load;
         } else if self.linear_counter > 0 {
             self.linear_counter -= 1;
         }
-        if !self.length_halt {
+        if !self.length.halt {
             self.linear_reload = false;
         }
     }
 
     fn output(&self) -> u8 {
         if !self.enabled
-            || self.length_counter == 0
+            || self.length.counter == 0
             || self.linear_counter == 0
             || self.timer_period < 2
         {
@@ -292,7 +379,7 @@ impl Triangle {
             return;
         }
         self.timer = self.timer_period;
-        if self.length_counter > 0 && self.linear_counter > 0 {
+        if self.length.counter > 0 && self.linear_counter > 0 {
             self.sequencer_step = (self.sequencer_step + 1) & 31;
         }
     }
@@ -303,15 +390,14 @@ impl Triangle {
 // -----------------------------------------------------------------------------
 
 /// Noise channel: pseudo-random output from 15-bit LFSR; mode bit shortens period (metallic tone).
-#[derive(Default)]
+#[derive(Clone, Default)]
 struct Noise {
     enabled: bool,
-    length_halt: bool,
     constant_volume: bool,
     volume: u8,
     mode: bool,
     period_index: u8,
-    length_counter: u8,
+    length: LengthCounter,
     envelope_start: bool,
     envelope_divider: u8,
     envelope_decay: u8,
@@ -322,7 +408,7 @@ struct Noise {
 impl Noise {
     /// $400C: length halt, constant volume, volume/envelope.
     fn write_400c(&mut self, data: u8) {
-        self.length_halt = data & 0x20 != 0;
+        self.length.set_halt(data & 0x20 != 0);
         self.constant_volume = data & 0x10 != 0;
         self.volume = data & 0x0F;
         self.envelope_start = true;
@@ -337,17 +423,11 @@ impl Noise {
     /// $400F: length counter load; restarts envelope.
     fn write_400f(&mut self, data: u8) {
         if self.enabled {
-            self.length_counter = LENGTH_TABLE[(data >> 3) as usize & 0x1F];
+            self.length.load(data >> 3);
         }
         self.envelope_start = true;
     }
 
-    fn clock_length(&mut self) {
-        if !self.length_halt && self.length_counter > 0 {
-            self.length_counter -= 1;
-        }
-    }
-
     fn clock_envelope(&mut self) {
         if self.envelope_start {
             self.envelope_decay = 15;
@@ -359,14 +439,14 @@ impl Noise {
             self.envelope_divider = self.volume;
             if self.envelope_decay > 0 {
                 self.envelope_decay -= 1;
-            } else if self.length_halt {
+            } else if self.length.halt {
                 self.envelope_decay = 15;
             }
         }
     }
 
     fn output(&self) -> u8 {
-        if !self.enabled || self.length_counter == 0 || (self.shift & 1) != 0 {
+        if !self.enabled || self.length.counter == 0 || (self.shift & 1) != 0 {
             return 0;
         }
         if self.constant_volume {
@@ -376,12 +456,12 @@ impl Noise {
         }
     }
 
-    fn tick_cpu_cycle(&mut self) {
+    fn tick_cpu_cycle(&mut self, periods: &[u16; 16]) {
         if self.timer > 0 {
             self.timer -= 1;
             return;
         }
-        let period = NOISE_PERIOD_TABLE[self.period_index as usize];
+        let period = periods[self.period_index as usize];
         self.timer = period;
         let feedback = if self.mode {
             (self.shift & 1) ^ ((self.shift >> 6) & 1)
@@ -402,6 +482,12 @@ const DMC_RATE_TABLE: [u16; 16] = [
     428, 380, 340, 320, 286, 254, 226, 214, 190, 160, 142, 128, 106, 84, 72, 54,
 ];
 
+/// DMC rate table (PAL). APU_DMC.
+const DMC_RATE_TABLE_PAL: [u16; 16] = [
+    398, 354, 316, 298, 276, 236, 210, 198, 176, 148, 132, 118, 98, 78, 66, 50,
+];
+
+#[derive(Clone)]
 struct Dmc {
     irq_enable: bool,
     loop_flag: bool,
@@ -418,7 +504,6 @@ struct Dmc {
     bits_remaining: u8,
     /// True when buffer is empty (no sample byte to output). Power-on and when waiting for fetch: output 0.
     silence: bool,
-    enabled: bool,
     /// When true, the bus must stall CPU 4 cycles, read from fetch_address, and call dmc_feed_byte.
     fetch_pending: bool,
     fetch_address: u16,
@@ -440,7 +525,6 @@ impl Default for Dmc {
             shift_register: 0,
             bits_remaining: 0,
             silence: true, // power-on: output 0 until we have sample data
-            enabled: false,
             fetch_pending: false,
             fetch_address: 0,
         }
@@ -473,14 +557,15 @@ impl Dmc {
         self.sample_length = (data as u16) * 16 + 1;
     }
 
-    /// Enable/disable from $4015 bit 4. When enabled and bytes_remaining == 0, (re)start sample.
+    /// $4015 bit 4. Clear: bytes_remaining = 0, so the sample ends once the byte already in the
+    /// buffer/shifter has played out. Set: restart the sample only if it has finished
+    /// (bytes_remaining == 0); a running sample continues untouched. See APU_DMC.
     fn set_enabled(&mut self, enabled: bool) {
         if !enabled {
-            self.enabled = false;
+            self.bytes_remaining = 0;
             self.fetch_pending = false;
             return;
         }
-        self.enabled = true;
         if self.bytes_remaining == 0 {
             self.current_address = self.start_address;
             self.bytes_remaining = self.sample_length;
@@ -513,15 +598,13 @@ impl Dmc {
     }
 
     /// Run one CPU cycle: count down rate timer; when it hits 0, output one bit (or silence) and possibly start new cycle / request fetch.
-    fn tick(&mut self) {
-        if !self.enabled {
-            return;
-        }
+    /// The output unit runs whether or not the channel is enabled; only the memory reader stops.
+    fn tick(&mut self, rates: &[u16; 16]) {
         if self.rate_timer > 0 {
             self.rate_timer -= 1;
             return;
         }
-        let period = DMC_RATE_TABLE[self.rate_index as usize];
+        let period = rates[self.rate_index as usize];
         self.rate_timer = period.saturating_sub(1);
 
         if !self.silence {
@@ -559,11 +642,31 @@ impl Dmc {
         if self.silence { 0 } else { self.output_level }
     }
 
+    /// $4015 bit 4: the memory reader still has bytes to fetch. A byte sitting in the sample
+    /// buffer doesn't count.
     fn has_bytes_remaining(&self) -> bool {
-        self.bytes_remaining > 0 || self.sample_buffer.is_some()
+        self.bytes_remaining > 0
     }
 }
 
+/// DMC internal state, for debuggers and save-state tooling. See [`APU::dmc_state`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DmcState {
+    /// 7-bit output level (0–127).
+    pub output_level: u8,
+    /// Next address the memory reader fetches.
+    pub current_address: u16,
+    pub bytes_remaining: u16,
+    /// Byte waiting to be moved into the shift register.
+    pub sample_buffer: Option<u8>,
+    pub shift_register: u8,
+    pub bits_remaining: u8,
+    /// Output unit is silent (buffer was empty when the last output cycle began).
+    pub silence: bool,
+    /// CPU cycles until the next output bit.
+    pub rate_timer: u16,
+}
+
 // -----------------------------------------------------------------------------
 // Mixer: NES non-linear combination (APU_Mixer). Pulse group and TND group combined.
 // -----------------------------------------------------------------------------
@@ -584,12 +687,110 @@ fn tnd_table(n: usize) -> f32 {
     163.67 / (24329.0 / (n as f32) + 100.0)
 }
 
+// -----------------------------------------------------------------------------
+// Output filters: the console's analog path (APU_Mixer "Filtering")
+// -----------------------------------------------------------------------------
+
+/// First-order high-pass at `cutoff` Hz: y[n] = a · (y[n-1] + x[n] - x[n-1]).
+#[derive(Clone, Copy, Default)]
+struct HighPass {
+    a: f32,
+    prev_in: f32,
+    prev_out: f32,
+}
+
+impl HighPass {
+    fn new(cutoff: f64, sample_rate: f64) -> Self {
+        let rc = 1.0 / (2.0 * std::f64::consts::PI * cutoff);
+        let dt = 1.0 / sample_rate;
+        Self {
+            a: (rc / (rc + dt)) as f32,
+            ..Self::default()
+        }
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        self.prev_out = self.a * (self.prev_out + x - self.prev_in);
+        self.prev_in = x;
+        self.prev_out
+    }
+}
+
+/// First-order low-pass at `cutoff` Hz: y[n] = y[n-1] + b · (x[n] - y[n-1]).
+#[derive(Clone, Copy, Default)]
+struct LowPass {
+    b: f32,
+    prev_out: f32,
+}
+
+impl LowPass {
+    fn new(cutoff: f64, sample_rate: f64) -> Self {
+        let rc = 1.0 / (2.0 * std::f64::consts::PI * cutoff);
+        let dt = 1.0 / sample_rate;
+        Self {
+            b: (dt / (rc + dt)) as f32,
+            ..Self::default()
+        }
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        self.prev_out += self.b * (x - self.prev_out);
+        self.prev_out
+    }
+}
+
+/// The NES output chain: high-pass at 90 Hz and 440 Hz (removing the mixer's DC offset), then
+/// low-pass at 14 kHz.
+#[derive(Clone, Copy)]
+struct OutputFilter {
+    high_pass_90: HighPass,
+    high_pass_440: HighPass,
+    low_pass_14k: LowPass,
+}
+
+impl OutputFilter {
+    fn new(sample_rate: f64) -> Self {
+        Self {
+            high_pass_90: HighPass::new(90.0, sample_rate),
+            high_pass_440: HighPass::new(440.0, sample_rate),
+            low_pass_14k: LowPass::new(14_000.0, sample_rate),
+        }
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        let x = self.high_pass_90.process(x);
+        let x = self.high_pass_440.process(x);
+        self.low_pass_14k.process(x)
+    }
+}
+
 // -----------------------------------------------------------------------------
 // APU: register dispatch, frame counter, tick, sample buffer
 // -----------------------------------------------------------------------------
 
+/// One of the five APU channels, for muting in the mix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channel {
+    Pulse1,
+    Pulse2,
+    Triangle,
+    Noise,
+    Dmc,
+}
+
+impl Channel {
+    /// Bit in `APU::muted`.
+    fn mask(self) -> u8 {
+        1 << self as u8
+    }
+}
+
 /// APU state: pulse×2, triangle, noise, DMC; frame counter; status ($4015); sample buffer for
 /// 44.1 kHz output. tick(cycles) advances frame counter and channels, pushes samples when due.
+///
+/// A clone is a complete save state (including the noise LFSR, DMC shifter and resampler
+/// phase): restoring it and replaying the same writes reproduces identical samples.
+#[derive(Clone)]
 pub struct APU {
     pulse1: Pulse,
     pulse2: Pulse,
@@ -601,17 +802,30 @@ pub struct APU {
     frame_4step: bool,
     frame_cycle: u32,
     sample_phase: f64,
+    /// Mixer output summed over the current output sample's cycles, and how many there were.
+    sample_sum: f32,
+    sample_cycles: u32,
+    sample_rate: f64,
+    cycles_per_sample: f64,
+    timing: ApuTiming,
+    /// Cartridge expansion audio (e.g. Sunsoft 5B) in mixer units, added to the channel mix.
+    expansion: f32,
+    /// Channels left out of the mix, one bit per `Channel`. They keep running.
+    muted: u8,
+    filter: OutputFilter,
+    filters_enabled: bool,
     pub sample_buffer: Vec<f32>,
 }
 
 impl Default for APU {
     fn default() -> Self {
-        Self::new()
+        Self::new(Region::default())
     }
 }
 
 impl APU {
-    pub fn new() -> Self {
+    /// Power-on APU with `region`'s frame counter, noise and DMC timing.
+    pub fn new(region: Region) -> Self {
         Self {
             pulse1: Pulse::default(),
             pulse2: Pulse::default(),
@@ -626,10 +840,30 @@ impl APU {
             frame_4step: true,
             frame_cycle: 0,
             sample_phase: 0.0,
-            sample_buffer: Vec::new(),
+            sample_sum: 0.0,
+            sample_cycles: 0,
+            sample_rate: SAMPLE_RATE,
+            cycles_per_sample: region.cpu_clock_hz() / SAMPLE_RATE,
+            timing: ApuTiming::new(region),
+            expansion: 0.0,
+            muted: 0,
+            filter: OutputFilter::new(SAMPLE_RATE),
+            filters_enabled: true,
+            sample_buffer: Vec::with_capacity(SAMPLE_RATE as usize),
         }
     }
 
+    /// Reset button: as if $4015 were written with 0 (all channels disabled, length counters and
+    /// DMC bytes cleared, DMC IRQ acknowledged), the frame IRQ cleared and the frame counter
+    /// restarted in its current mode. The triangle sequencer returns to step 0; the DMC output
+    /// level is left as is. See [APU](https://www.nesdev.org/wiki/APU) and CPU power up state.
+    pub fn reset(&mut self) {
+        self.write(0x4015, 0);
+        self.status &= !0x40;
+        self.frame_cycle = 0;
+        self.triangle.sequencer_step = 0;
+    }
+
     /// Write to APU registers. $4000–$4013 = channel regs; $4015 = enable + length status;
     /// $4017 = frame counter (mode 4/5-step, IRQ inhibit). Writing $4017 resets frame counter.
     pub fn write(&mut self, addr: u16, data: u8) {
@@ -659,16 +893,16 @@ impl APU {
                 self.triangle.enabled = data & 4 != 0;
                 self.noise.enabled = data & 8 != 0;
                 if !self.pulse1.enabled {
-                    self.pulse1.length_counter = 0;
+                    self.pulse1.length.clear();
                 }
                 if !self.pulse2.enabled {
-                    self.pulse2.length_counter = 0;
+                    self.pulse2.length.clear();
                 }
                 if !self.triangle.enabled {
-                    self.triangle.length_counter = 0;
+                    self.triangle.length.clear();
                 }
                 if !self.noise.enabled {
-                    self.noise.length_counter = 0;
+                    self.noise.length.clear();
                 }
                 self.dmc.set_enabled(data & 0x10 != 0);
             }
@@ -686,7 +920,8 @@ impl APU {
                     self.clock_half_frame();
                 }
             }
-            _ => {}
+            // $4009 and $400D are unused; anything else isn't an APU register.
+            _ => log::trace!("ignored APU write ${addr:04X} = ${data:02X}"),
         }
     }
 
@@ -694,16 +929,16 @@ impl APU {
     /// has bytes remaining; bit 6 = frame IRQ; bit 7 = DMC IRQ. Reading clears only the frame IRQ (bit 6).
     pub fn read_status(&mut self) -> u8 {
         let mut r = self.status & 0xC0;
-        if self.pulse1.length_counter > 0 {
+        if self.pulse1.length.counter > 0 {
             r |= 0x01;
         }
-        if self.pulse2.length_counter > 0 {
+        if self.pulse2.length.counter > 0 {
             r |= 0x02;
         }
-        if self.triangle.length_counter > 0 {
+        if self.triangle.length.counter > 0 {
             r |= 0x04;
         }
-        if self.noise.length_counter > 0 {
+        if self.noise.length.counter > 0 {
             r |= 0x08;
         }
         if self.dmc.has_bytes_remaining() {
@@ -713,8 +948,15 @@ impl APU {
         r
     }
 
+    /// APU IRQ output: frame IRQ ($4015 bit 6) or DMC IRQ (bit 7) is set. Acknowledged by
+    /// reading $4015 (frame), writing $4015 (DMC) or disabling the source in $4017 / $4010.
+    pub fn irq_pending(&self) -> bool {
+        self.status & 0xC0 != 0
+    }
+
     /// DMC memory reader: when sample buffer is empty and bytes_remaining > 0, returns Some(addr)
     /// for the bus to read from PRG. Bus must stall CPU 4 cycles, read byte, call dmc_feed_byte.
+    /// `NesBus::tick` does this; custom buses driving the APU need to do it themselves.
     /// See APU_DMC "Memory reader".
     pub fn dmc_wants_fetch(&self) -> Option<u16> {
         if self.dmc.fetch_pending {
@@ -729,6 +971,108 @@ impl APU {
         self.dmc.feed_byte(byte, &mut self.status);
     }
 
+    /// Set the cartridge's expansion audio level (see `Mapper::audio_output`), mixed into every
+    /// sample until changed. The bus updates it as the cartridge is clocked.
+    pub fn set_expansion_audio(&mut self, level: f32) {
+        self.expansion = level;
+    }
+
+    /// Mute or unmute `channel` in the mix. A muted channel keeps running (timers, length
+    /// counters, DMC fetches and IRQs are unaffected); it just contributes 0 to the output.
+    pub fn set_channel_enabled(&mut self, channel: Channel, on: bool) {
+        if on {
+            self.muted &= !channel.mask();
+        } else {
+            self.muted |= channel.mask();
+        }
+    }
+
+    /// True unless `channel` is muted.
+    pub fn channel_enabled(&self, channel: Channel) -> bool {
+        self.muted & channel.mask() == 0
+    }
+
+    /// Mute every channel except `channel`, or unmute all with `None`.
+    pub fn set_solo(&mut self, channel: Option<Channel>) {
+        self.muted = match channel {
+            Some(channel) => 0x1F & !channel.mask(),
+            None => 0,
+        };
+    }
+
+    /// Output sample rate in Hz (44 100 until changed).
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate as u32
+    }
+
+    /// Most samples `sample_buffer` holds: one second at the current sample rate. If the
+    /// frontend stops draining (paused or too slow), newer samples are dropped instead of growing
+    /// the buffer without bound.
+    pub fn max_buffered_samples(&self) -> usize {
+        self.sample_rate as usize
+    }
+
+    /// Generate samples at `hz` from now on. The filters are rebuilt for the new rate; samples
+    /// already buffered are kept as they are.
+    pub fn set_sample_rate(&mut self, hz: u32) {
+        assert!(hz > 0, "sample rate must be positive");
+        self.sample_rate = hz as f64;
+        self.cycles_per_sample = self.timing.cpu_clock_hz / self.sample_rate;
+        self.filter = OutputFilter::new(self.sample_rate);
+    }
+
+    /// Turn the console's output filters on (the default) or off. Filtered samples have no DC
+    /// offset and are centred on 0.5, so they stay within the 0..1 range of raw mixer output.
+    pub fn set_filters_enabled(&mut self, enabled: bool) {
+        if enabled && !self.filters_enabled {
+            self.filter = OutputFilter::new(self.sample_rate);
+        }
+        self.filters_enabled = enabled;
+    }
+
+    /// Noise channel's 15-bit LFSR (power-on value 1).
+    pub fn noise_lfsr(&self) -> u16 {
+        self.noise.shift
+    }
+
+    /// Seed the noise LFSR for reproducible audio. Only the low 15 bits are used; 0 becomes 1,
+    /// since an all-zero LFSR never changes.
+    pub fn set_noise_lfsr(&mut self, seed: u16) {
+        self.noise.shift = match seed & 0x7FFF {
+            0 => 1,
+            seed => seed,
+        };
+    }
+
+    /// Snapshot of the DMC's output unit and memory reader.
+    pub fn dmc_state(&self) -> DmcState {
+        DmcState {
+            output_level: self.dmc.output_level,
+            current_address: self.dmc.current_address,
+            bytes_remaining: self.dmc.bytes_remaining,
+            sample_buffer: self.dmc.sample_buffer,
+            shift_register: self.dmc.shift_register,
+            bits_remaining: self.dmc.bits_remaining,
+            silence: self.dmc.silence,
+            rate_timer: self.dmc.rate_timer,
+        }
+    }
+
+    /// Restore DMC state captured by [`APU::dmc_state`]. Register values ($4010–$4013) are not
+    /// part of it; write those first.
+    pub fn set_dmc_state(&mut self, state: DmcState) {
+        self.dmc.output_level = state.output_level & 0x7F;
+        self.dmc.current_address = state.current_address;
+        self.dmc.bytes_remaining = state.bytes_remaining;
+        self.dmc.sample_buffer = state.sample_buffer;
+        self.dmc.shift_register = state.shift_register;
+        self.dmc.bits_remaining = state.bits_remaining;
+        self.dmc.silence = state.silence;
+        self.dmc.rate_timer = state.rate_timer;
+        self.dmc.fetch_pending = self.dmc.sample_buffer.is_none() && self.dmc.bytes_remaining > 0;
+        self.dmc.fetch_address = self.dmc.current_address;
+    }
+
     /// Quarter-frame: clock envelope (pulse, noise) and triangle linear counter.
     fn clock_quarter_frame(&mut self) {
         self.pulse1.clock_envelope();
@@ -739,91 +1083,100 @@ impl APU {
 
     /// Half-frame: clock length counters and sweep units.
     fn clock_half_frame(&mut self) {
-        self.pulse1.clock_length();
-        self.pulse2.clock_length();
-        self.triangle.clock_length();
-        self.noise.clock_length();
+        self.pulse1.length.clock();
+        self.pulse2.length.clock();
+        self.triangle.length.clock();
+        self.noise.length.clock();
         self.pulse1.clock_sweep(true); // Pulse 1: one's complement when negate
         self.pulse2.clock_sweep(false); // Pulse 2: two's complement when negate
     }
 
     fn mix(&self) -> f32 {
+        let level = |channel: Channel, output: u8| {
+            if self.channel_enabled(channel) { output } else { 0 }
+        };
         let sweep_silence1 = self.pulse1.sweep_silence(true);
         let sweep_silence2 = self.pulse2.sweep_silence(false);
-        let p1 = self.pulse1.output(sweep_silence1);
-        let p2 = self.pulse2.output(sweep_silence2);
+        let p1 = level(Channel::Pulse1, self.pulse1.output(sweep_silence1));
+        let p2 = level(Channel::Pulse2, self.pulse2.output(sweep_silence2));
         let pulse_sum = (p1 + p2) as usize;
-        let tri = self.triangle.output();
-        let noi = self.noise.output();
-        let dmc = self.dmc.output() as usize;
+        let tri = level(Channel::Triangle, self.triangle.output());
+        let noi = level(Channel::Noise, self.noise.output());
+        let dmc = level(Channel::Dmc, self.dmc.output()) as usize;
         let tnd = 3 * (tri as usize) + 2 * (noi as usize) + dmc;
         let pulse_out = pulse_table(pulse_sum.min(31));
         let tnd_out = tnd_table(tnd.min(203));
-        let out = pulse_out + tnd_out;
+        let out = pulse_out + tnd_out + self.expansion;
         // Scale to 0..1 and apply moderate gain
         (out / 255.0).min(1.0)
     }
 
     /// Advance APU by `cycles` CPU cycles: frame counter (quarter/half frame, IRQ), pulse/noise/triangle/DMC
-    /// timers, and mixer. One sample pushed every CYCLES_PER_SAMPLE cycles (~44.1 kHz).
+    /// timers, and mixer. One sample, the average of the mixer over its cycles, is pushed every
+    /// `cycles_per_sample` cycles (44.1 kHz unless `set_sample_rate` changed it).
     pub fn tick(&mut self, cycles: usize) {
         let cycles = cycles as u32;
+        let [step1, step2, step3, step4] = self.timing.frame_steps;
+        let step5 = self.timing.frame_step5;
         for _ in 0..cycles {
             self.frame_cycle += 1;
-            let apu_half_cycle = self.frame_cycle % 2 == 0;
-
+            let apu_half_cycle = self.frame_cycle.is_multiple_of(2);
+
+            let c = self.frame_cycle;
+            if c == step1 || c == step3 {
+                self.clock_quarter_frame();
+            } else if c == step2 {
+                self.clock_quarter_frame();
+                self.clock_half_frame();
+            }
             if self.frame_4step {
-                match self.frame_cycle {
-                    7457 => self.clock_quarter_frame(),
-                    14913 => {
-                        self.clock_quarter_frame();
-                        self.clock_half_frame();
-                    }
-                    22371 => self.clock_quarter_frame(),
-                    29828 => {
-                        if !self.frame_irq_inhibit {
-                            self.status |= 0x40;
-                        }
-                    }
-                    29829 => self.clock_half_frame(),
-                    _ => {}
-                }
-                if self.frame_cycle >= FRAME_4STEP_RESET {
+                if c == step4 - 1 && !self.frame_irq_inhibit {
+                    self.status |= 0x40;
+                } else if c == step4 {
+                    self.clock_half_frame();
+                } else if c > step4 {
                     self.frame_cycle = 0;
                 }
             } else {
-                // 5-step mode (used by Donkey Kong and many other games)
-                match self.frame_cycle {
-                    7457 => self.clock_quarter_frame(),
-                    14913 => {
-                        self.clock_quarter_frame();
-                        self.clock_half_frame();
-                    }
-                    22371 => self.clock_quarter_frame(),
-                    29829 => {}
-                    37281 => {
-                        self.clock_quarter_frame();
-                        self.clock_half_frame();
-                    }
-                    _ => {}
-                }
-                if self.frame_cycle >= FRAME_5STEP_RESET {
+                // 5-step mode (used by Donkey Kong and many other games); step 4 does nothing.
+                if c == step5 {
+                    self.clock_quarter_frame();
+                    self.clock_half_frame();
+                } else if c > step5 {
                     self.frame_cycle = 0;
                 }
             }
 
+            // Register writes made before this cycle land after its frame counter clocks.
+            self.pulse1.length.commit();
+            self.pulse2.length.commit();
+            self.triangle.length.commit();
+            self.noise.length.commit();
+
             if apu_half_cycle {
                 self.pulse1.tick_apu_cycle();
                 self.pulse2.tick_apu_cycle();
             }
             self.triangle.tick_cpu_cycle();
-            self.noise.tick_cpu_cycle();
-            self.dmc.tick();
+            self.noise.tick_cpu_cycle(self.timing.noise_periods);
+            self.dmc.tick(self.timing.dmc_rates);
 
+            // Box-filter the mixer down to the output rate: every cycle counts towards the
+            // sample, so edges between samples aren't lost (or aliased) as with point sampling.
+            self.sample_sum += self.mix();
+            self.sample_cycles += 1;
             self.sample_phase += 1.0;
-            if self.sample_phase >= CYCLES_PER_SAMPLE {
-                self.sample_phase -= CYCLES_PER_SAMPLE;
-                self.sample_buffer.push(self.mix());
+            if self.sample_phase >= self.cycles_per_sample {
+                self.sample_phase -= self.cycles_per_sample;
+                let mut sample = self.sample_sum / self.sample_cycles as f32;
+                self.sample_sum = 0.0;
+                self.sample_cycles = 0;
+                if self.filters_enabled {
+                    sample = (self.filter.process(sample) + 0.5).clamp(0.0, 1.0);
+                }
+                if self.sample_buffer.len() < self.max_buffered_samples() {
+                    self.sample_buffer.push(sample);
+                }
             }
         }
     }
@@ -835,4 +1188,253 @@ impl APU {
         self.sample_buffer.drain(..n);
         n
     }
+
+    /// Like `drain_samples`, but as signed 16-bit PCM centred on zero: mixer output 0..1 maps to
+    /// -32767..=32767 (the frontend's `s * 2 - 1` conversion, clamped).
+    pub fn drain_samples_i16(&mut self, out: &mut [i16]) -> usize {
+        let n = out.len().min(self.sample_buffer.len());
+        for (dst, &s) in out.iter_mut().zip(&self.sample_buffer[..n]) {
+            *dst = ((s * 2.0 - 1.0).clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        }
+        self.sample_buffer.drain(..n);
+        n
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// APU 1000 cycles into playing pulse 1, the triangle and noise at full volume.
+    fn playing_apu() -> APU {
+        let mut apu = APU::new(Region::Ntsc);
+        apu.write(0x4015, 0x0F);
+        for (addr, data) in [
+            (0x4000, 0xBF), // pulse 1: duty 2, length halt, constant volume 15
+            (0x4002, 0x80),
+            (0x4003, 0x01),
+            (0x4008, 0xFF), // triangle: linear counter 127, halt
+            (0x400A, 0x80),
+            (0x400B, 0x01),
+            (0x400C, 0x3F), // noise: length halt, constant volume 15
+            (0x400F, 0x08),
+        ] {
+            apu.write(addr, data);
+        }
+        apu.tick(1_000);
+        apu
+    }
+
+    #[test]
+    fn length_reload_on_a_clocking_cycle_is_ignored_only_if_clocked() {
+        let mut length = LengthCounter {
+            counter: 5,
+            ..LengthCounter::default()
+        };
+        length.load(0); // 10
+        length.clock();
+        length.commit();
+        assert_eq!(length.counter, 4);
+
+        // A zero counter isn't decremented, so the same-cycle reload applies.
+        length.counter = 0;
+        length.load(0);
+        length.clock();
+        length.commit();
+        assert_eq!(length.counter, 10);
+
+        // Halt written on the clocking cycle only takes effect afterwards.
+        length.set_halt(true);
+        length.clock();
+        length.commit();
+        assert_eq!(length.counter, 9);
+        length.clock();
+        assert_eq!(length.counter, 9);
+    }
+
+    #[test]
+    fn restoring_noise_and_dmc_state_reproduces_samples() {
+        let mut apu = APU::new(Region::Ntsc);
+        apu.set_noise_lfsr(0x1ACE);
+        apu.write(0x400C, 0x3F); // length halt, constant volume 15
+        apu.write(0x400E, 0x03);
+        apu.write(0x400F, 0x08);
+        apu.write(0x4011, 0x40); // DMC output level
+        apu.write(0x4015, 0x08);
+        apu.tick(10_000);
+        apu.sample_buffer.clear();
+        let (lfsr, dmc) = (apu.noise_lfsr(), apu.dmc_state());
+
+        let mut restored = apu.clone();
+        restored.set_noise_lfsr(0x7FFF);
+        restored.write(0x4011, 0x00);
+        restored.set_noise_lfsr(lfsr);
+        restored.set_dmc_state(dmc);
+
+        apu.tick(20_000);
+        restored.tick(20_000);
+        assert!(!apu.sample_buffer.is_empty());
+        assert_eq!(apu.sample_buffer, restored.sample_buffer);
+    }
+
+    #[test]
+    fn i16_samples_are_centred_on_zero() {
+        let mut apu = APU::new(Region::Ntsc);
+        apu.sample_buffer.extend([0.0, 0.5, 1.0, 1.5]);
+        let mut out = [0i16; 8];
+        assert_eq!(apu.drain_samples_i16(&mut out), 4);
+        assert_eq!(out[..4], [-32767, 0, 32767, 32767]);
+        assert!(apu.sample_buffer.is_empty());
+    }
+
+    #[test]
+    fn dmc_enable_restarts_only_a_finished_sample() {
+        let mut apu = APU::new(Region::Ntsc);
+        apu.write(0x4012, 0x01); // start $C040
+        apu.write(0x4013, 0x01); // 17 bytes
+
+        // Idle -> enabled: the sample starts and its first byte is requested.
+        apu.write(0x4015, 0x10);
+        assert_eq!(apu.dmc_wants_fetch(), Some(0xC040));
+        assert_eq!(apu.read_status() & 0x10, 0x10);
+        apu.dmc_feed_byte(0xAA);
+        assert_eq!(apu.dmc_state().bytes_remaining, 16);
+
+        // Playing -> enabled again: nothing restarts.
+        apu.write(0x4015, 0x10);
+        assert_eq!(apu.dmc_state().current_address, 0xC041);
+        assert_eq!(apu.dmc_state().bytes_remaining, 16);
+
+        // Playing -> disabled: the sample ends, the buffered byte stays.
+        apu.write(0x4015, 0x00);
+        assert_eq!(apu.dmc_state().bytes_remaining, 0);
+        assert_eq!(apu.dmc_state().sample_buffer, Some(0xAA));
+        assert_eq!(apu.read_status() & 0x10, 0);
+
+        // Finished -> enabled: restarts from the start address.
+        apu.write(0x4015, 0x10);
+        assert_eq!(apu.dmc_state().current_address, 0xC040);
+        assert_eq!(apu.dmc_state().bytes_remaining, 17);
+    }
+
+    #[test]
+    fn pulse1_sweep_negates_one_lower_than_pulse2() {
+        let (mut p1, mut p2) = (Pulse::default(), Pulse::default());
+        for p in [&mut p1, &mut p2] {
+            p.write_4001(0x89); // enabled, period 0, negate, shift 1
+            p.write_4002(0x00);
+            p.write_4003(0x01); // timer period $100
+        }
+        p1.clock_sweep(true);
+        p2.clock_sweep(false);
+        assert_eq!(p2.timer_period, 0x80);
+        assert_eq!(p1.timer_period, p2.timer_period - 1);
+    }
+
+    #[test]
+    fn sample_buffer_holds_at_most_one_second() {
+        let mut apu = APU::new(Region::Ntsc);
+        apu.set_sample_rate(8_000);
+        apu.tick(2 * 1_789_773);
+        assert_eq!(apu.sample_buffer.len(), 8_000);
+    }
+
+    #[test]
+    fn reset_clears_length_counters_and_silences_the_mix() {
+        let mut apu = playing_apu();
+        assert_eq!(apu.read_status() & 0x0F, 0x0D);
+        assert!(apu.mix() > 0.0);
+
+        apu.reset();
+        let lengths = [
+            apu.pulse1.length.counter,
+            apu.pulse2.length.counter,
+            apu.triangle.length.counter,
+            apu.noise.length.counter,
+        ];
+        assert_eq!(lengths, [0; 4]);
+        assert_eq!(apu.mix(), 0.0);
+    }
+
+    #[test]
+    fn muting_triangle_and_noise_leaves_the_pulse_contribution() {
+        let mut apu = playing_apu();
+        let mut pulse_only = apu.clone();
+        pulse_only.write(0x4015, 0x03); // silence the triangle and noise outright
+        let full = apu.mix();
+
+        apu.set_channel_enabled(Channel::Triangle, false);
+        apu.set_channel_enabled(Channel::Noise, false);
+        assert_ne!(apu.mix(), full);
+        assert_eq!(apu.mix(), pulse_only.mix());
+
+        apu.set_solo(Some(Channel::Triangle));
+        assert!(!apu.channel_enabled(Channel::Pulse1) && apu.channel_enabled(Channel::Triangle));
+        apu.set_solo(None);
+        assert_eq!(apu.mix(), full);
+    }
+
+    #[test]
+    fn high_pass_removes_a_constant_level_unless_filters_are_off() {
+        let mut apu = APU::new(Region::Ntsc);
+        apu.set_expansion_audio(64.0); // a constant mixer input
+        let mut filtered = apu.clone();
+        apu.set_filters_enabled(false);
+        apu.tick(200_000);
+        filtered.tick(200_000);
+
+        let raw = *apu.sample_buffer.last().unwrap();
+        assert_eq!(raw, 64.0 / 255.0);
+        assert!(apu.sample_buffer.iter().all(|&s| s == raw));
+        // Filtered samples start at the step and decay to the 0.5 centre.
+        assert!(filtered.sample_buffer[0] > 0.5 + raw / 2.0);
+        assert!((filtered.sample_buffer.last().unwrap() - 0.5).abs() < raw / 100.0);
+    }
+
+    /// Energy of `samples` left after removing the mean and every harmonic of `f0` below
+    /// Nyquist: what aliasing folded in between the harmonics.
+    fn inharmonic_energy(samples: &[f32], f0: f64, rate: f64) -> f64 {
+        let n = samples.len() as f64;
+        let mean = samples.iter().map(|&x| x as f64).sum::<f64>() / n;
+        let mut energy: f64 = samples.iter().map(|&x| (x as f64 - mean).powi(2)).sum();
+        let mut k = 1.0;
+        while k * f0 < rate / 2.0 {
+            let w = 2.0 * std::f64::consts::PI * k * f0 / rate;
+            let (mut c, mut s) = (0.0, 0.0);
+            for (i, &x) in samples.iter().enumerate() {
+                c += (x as f64 - mean) * (w * i as f64).cos();
+                s += (x as f64 - mean) * (w * i as f64).sin();
+            }
+            energy -= 2.0 * (c * c + s * s) / n;
+            k += 1.0;
+        }
+        energy
+    }
+
+    #[test]
+    fn averaged_samples_alias_less_than_point_samples() {
+        let rate = 22_050;
+        let mut apu = APU::new(Region::Ntsc);
+        apu.set_sample_rate(rate);
+        apu.set_filters_enabled(false);
+        apu.write(0x4015, 0x01);
+        apu.write(0x4000, 0xBF); // pulse 1: 50% duty, length halt, constant volume 15
+        apu.write(0x4002, 0x6F); // timer 111: ~999 Hz
+        apu.write(0x4003, 0x00);
+        let f0 = apu.timing.cpu_clock_hz / (16.0 * 112.0);
+
+        // One second of output, plus the mixer level at the cycle each sample is taken.
+        let mut point = Vec::new();
+        while apu.sample_buffer.len() < rate as usize {
+            let before = apu.sample_buffer.len();
+            apu.tick(1);
+            if apu.sample_buffer.len() > before {
+                point.push(apu.mix());
+            }
+        }
+
+        let averaged = inharmonic_energy(&apu.sample_buffer, f0, rate as f64);
+        let point = inharmonic_energy(&point, f0, rate as f64);
+        assert!(averaged < point / 2.0, "averaged {averaged}, point {point}");
+    }
 }
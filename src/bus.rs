@@ -11,19 +11,43 @@
 //! | $4018–$7FFF |        | Unmapped / cartridge (e.g. PRG RAM at $6000–$7FFF) |
 //! | $8000–$FFFF |        | Cartridge PRG ROM and mapper registers |
 //!
-//! PPU runs at 3× CPU clock; each `tick(cycles)` advances PPU by `cycles*3` and APU by `cycles`.
+//! PPU runs at 3× CPU clock (3.2× on PAL); each `tick(cycles)` advances the PPU by that many dots
+//! and the APU by `cycles`.
 
 use crate::apu::apu::APU;
+use crate::controller::InputDevice;
+use crate::region::Region;
 use crate::{cartridge::cartridge::Cartridge, controller::Controller, ppu::ppu::PPU};
 
 /// Trait for memory-mapped I/O and bus access used by the CPU.
 /// See NESdev "CPU memory map" for read/write behavior and open bus.
 pub trait Bus {
     fn read(&mut self, addr: u16) -> u8;
+    /// Read without side effects (for debuggers and the disassembler). Defaults to `read`; buses
+    /// with read-sensitive registers should override it.
+    fn peek(&mut self, addr: u16) -> u8 {
+        self.read(addr)
+    }
     fn write(&mut self, addr: u16, data: u8);
-    fn tick(&mut self, cycles: usize);
-    fn poll_nmi(&mut self) -> bool;
-    /// Poll cartridge IRQ (e.g. MMC3 scanline IRQ). Returns true if IRQ was pending and clears it.
+    /// Advance the rest of the system by `cycles` CPU cycles. Default: no-op, for plain memory
+    /// buses (tests, CPU-only embedders).
+    fn tick(&mut self, _cycles: usize) {}
+    /// Returns true if an NMI edge is pending and clears it. Default: false.
+    fn poll_nmi(&mut self) -> bool {
+        false
+    }
+    /// CPU cycles the bus stole for DMA during `tick` since the last call (already ticked). The
+    /// CPU adds them to its cycle count. Default: 0.
+    fn take_stall_cycles(&mut self) -> usize {
+        0
+    }
+    /// PPU (scanline, dot) for trace output, with the pre-render line as 261 as in nestest.log.
+    /// Default: `None` (the column is left out).
+    fn ppu_position(&self) -> Option<(u16, u16)> {
+        None
+    }
+    /// Level of the shared IRQ line: true while any source (cartridge, APU) asserts it. Sources
+    /// keep it asserted until the game acknowledges them. Default: false.
     fn poll_irq(&mut self) -> bool {
         false
     }
@@ -31,29 +55,121 @@ pub trait Bus {
 
 /// Main NES bus: 2 KiB internal RAM, PPU, APU, cartridge, controller.
 /// Decoding matches the NES 2A03 address map; unmapped reads return open bus ($40 typical).
+#[derive(Clone)]
 pub struct NesBus {
     /// Internal RAM; $0000–$07FF is the only physical RAM; $0800–$1FFF mirror it (addr & $07FF).
     pub ram: [u8; 2048],
     pub cart: Cartridge,
     pub ppu: PPU,
     pub apu: APU,
-    /// Controller port 1 ($4016). Port 2 ($4017) not implemented. See Controller_reading.
+    /// Controller port 1 ($4016). See Controller_reading.
     pub controller: Controller,
+    /// Device on port 2 / the expansion port ($4017), e.g. the Family BASIC keyboard. `None`
+    /// reads as open bus.
+    pub port2: Option<Box<dyn InputDevice>>,
+    /// Famicom controller 2 microphone: while true (the player is blowing into it), $4016 reads
+    /// have bit 2 set. See [Controller port registers](https://www.nesdev.org/wiki/Controller_port_registers).
+    pub microphone: bool,
+    /// Cycles the CPU was halted for DMC fetches and OAM DMA, not yet reported via
+    /// `take_stall_cycles`.
+    stall_cycles: usize,
+    /// Page written to $4014; the DMA runs at the next `tick`.
+    oam_dma_page: Option<u8>,
+    /// CPU cycles since power-on, for the OAM DMA get/put alignment.
+    cpu_cycle: u64,
+    region: Region,
+    /// PPU dots owed to the PPU, in units of 1/`ppu_dots_per_cpu_cycle().1` dot (PAL runs a
+    /// fractional 3.2 dots per CPU cycle).
+    ppu_dot_phase: u32,
 }
 
 impl NesBus {
-    /// Create a new bus with the given cartridge.
-    pub fn new(cart: Cartridge) -> Self {
+    /// Create a new bus with the given cartridge, timed for `region`.
+    pub fn new(cart: Cartridge, region: Region) -> Self {
         Self {
             ram: [0; 2048],
             cart,
-            ppu: PPU::new(),
-            apu: APU::new(),
+            ppu: PPU::new(region),
+            apu: APU::new(region),
             controller: Controller { state: 0, shift: 0 },
+            port2: None,
+            microphone: false,
+            stall_cycles: 0,
+            oam_dma_page: None,
+            cpu_cycle: 0,
+            region,
+            ppu_dot_phase: 0,
+        }
+    }
+
+    /// The port 2 device as its concrete type, if one of type `T` is plugged in (for frontends
+    /// that feed it host input).
+    pub fn port2_mut<T: InputDevice>(&mut self) -> Option<&mut T> {
+        let device: &mut dyn std::any::Any = self.port2.as_deref_mut()?;
+        device.downcast_mut::<T>()
+    }
+
+    /// Swap in a new cartridge and power-cycle everything else on the bus: RAM is cleared and the
+    /// PPU, APU and controller start fresh, so no pending NMI, frame IRQ or mapper IRQ from the old
+    /// game survives. The caller must then `reset` the CPU to take the new reset vector.
+    pub fn load_cartridge(&mut self, cart: Cartridge) {
+        self.cart = cart;
+        self.ram = [0; 2048];
+        self.ppu = PPU::new(self.region);
+        let sample_rate = self.apu.sample_rate();
+        self.apu = APU::new(self.region);
+        self.apu.set_sample_rate(sample_rate);
+        self.controller = Controller::default();
+        self.stall_cycles = 0;
+        self.oam_dma_page = None;
+        self.cpu_cycle = 0;
+        self.ppu_dot_phase = 0;
+    }
+
+    /// Advance the PPU by 3× cycles (3.2× on PAL) and the APU by cycles, rendering each scanline
+    /// as it completes.
+    fn advance(&mut self, cycles: usize) {
+        self.cpu_cycle += cycles as u64;
+        self.cart.cpu_clock(cycles);
+        self.apu.set_expansion_audio(self.cart.audio_output());
+        self.apu.tick(cycles);
+        let (dots, per_cycles) = self.region.ppu_dots_per_cpu_cycle();
+        self.ppu_dot_phase += cycles as u32 * dots;
+        while self.ppu_dot_phase >= per_cycles {
+            self.ppu_dot_phase -= per_cycles;
+            if let Some(scanline) = self.ppu.tick() {
+                self.ppu.render_scanline(&mut self.cart, scanline);
+            }
         }
     }
 
-    /// True when the PPU has entered vblank; framebuffer is already filled scanline-by-scanline.
+    /// OAM DMA from CPU page `page` ($xx00–$xxFF) through $2004: one halt cycle, one more to align
+    /// to a read ("get") cycle when the halt lands on an odd cycle, then 256 read/write pairs, so
+    /// 513 or 514 cycles. A DMC fetch due during the copy takes over the next read slot and costs
+    /// 2 cycles (a dummy cycle plus the read) instead of its usual 4. See
+    /// [DMA](https://www.nesdev.org/wiki/DMA).
+    fn run_oam_dma(&mut self, page: u8) {
+        let mut stall = 1 + (self.cpu_cycle & 1) as usize;
+        self.advance(stall);
+        for i in 0..=0xFF {
+            if let Some(addr) = self.apu.dmc_wants_fetch() {
+                self.advance(2);
+                let byte = self.read(addr);
+                self.apu.dmc_feed_byte(byte);
+                stall += 2;
+            }
+            self.advance(1);
+            let byte = self.read((page as u16) << 8 | i);
+            self.advance(1);
+            self.ppu.write_oam_data(byte);
+            stall += 2;
+        }
+        self.stall_cycles += stall;
+    }
+
+    /// True once the PPU has entered vblank (scanline 241, dot 1). Every visible line (0–239) was
+    /// rendered by `tick` when it completed, so the framebuffer holds the whole frame; it is safe
+    /// to present until the CPU runs into the next frame's scanline 0.
     pub fn frame_ready(&self) -> bool {
         self.ppu.frame_ready
     }
@@ -70,22 +186,25 @@ impl Bus for NesBus {
             // $0000–$1FFF: Internal RAM; addresses incompletely decoded → 4 mirrors (addr & $07FF).
             0x0000..=0x1FFF => self.ram[(addr & 0x07FF) as usize],
             // $2000–$3FFF: PPU registers; incompletely decoded → repeat every 8 bytes. $2002=PPUSTATUS,
-            // $2004=OAMDATA, $2007=PPUDATA are readable; others return open bus (e.g. $40).
+            // $2004=OAMDATA, $2007=PPUDATA are readable; others return the PPU's open bus.
             0x2000..=0x3FFF => {
                 let r = addr & 0x2007;
                 match r {
                     0x2002 => self.ppu.read_status(),
                     0x2004 => self.ppu.read_oam_data(),
                     0x2007 => self.ppu.read_data(&mut self.cart),
-                    _ => 0x40, // Write-only or unused; open bus (Open_bus_behavior).
+                    _ => self.ppu.read_open_bus(), // Write-only (Open_bus_behavior).
                 }
             }
-            // $4000–$4014, $4017–$401F: APU write-only / unused; open bus. $4015 is internal to CPU.
-            0x4000..=0x4014 | 0x4017..=0x401F => 0x40,
+            // $4000–$4014, $4018–$401F: APU write-only / unused; open bus. $4015 is internal to CPU.
+            0x4000..=0x4014 | 0x4018..=0x401F => 0x40,
+            // $4017: port 2 / expansion port data (D0–D4); upper bits open bus.
+            0x4017 => 0x40 | self.port2.as_mut().map_or(0, |d| d.read() & 0x1F),
             0x4015 => self.apu.read_status(),
-            0x4016 => self.controller.read(),
-            // $4020–$5FFF: Unmapped; open bus.
-            0x4020..=0x5FFF => 0x40,
+            // $4016: port 1 data (D0) and the Famicom microphone (D2).
+            0x4016 => self.controller.read() | if self.microphone { 0x04 } else { 0 },
+            // $4020–$5FFF: expansion area; open bus unless the mapper has registers there.
+            0x4020..=0x5FFF => self.cart.expansion_read(addr).unwrap_or(0x40),
             // $6000–$7FFF: Cartridge PRG RAM (e.g. MMC3 save RAM).
             0x6000..=0x7FFF => self.cart.read(addr),
             // $8000–$FFFF: Cartridge PRG ROM (and fixed last bank for vectors $FFFA–$FFFF).
@@ -93,12 +212,23 @@ impl Bus for NesBus {
         }
     }
 
+    /// Like `read`, but PPU/APU/controller registers are not touched (reading $2002, $2007 or
+    /// $4016 would clear flags or advance latches), so they return open bus ($40).
+    fn peek(&mut self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x1FFF => self.ram[(addr & 0x07FF) as usize],
+            0x2000..=0x5FFF => 0x40,
+            0x6000..=0xFFFF => self.cart.read(addr),
+        }
+    }
+
     fn write(&mut self, addr: u16, data: u8) {
         match addr {
             0x0000..=0x1FFF => self.ram[(addr & 0x07FF) as usize] = data,
             // PPU: $2000=PPUCTRL, $2001=PPUMASK, $2003=OAMADDR, $2004=OAMDATA, $2005=PPUSCROLL,
             // $2006=PPUADDR, $2007=PPUDATA. Writes to $2002 (PPUSTATUS) fill internal latch only.
             0x2000..=0x3FFF => {
+                self.ppu.refresh_open_bus(data, 0xFF);
                 let r = addr & 0x2007;
                 match r {
                     0x2000 => self.ppu.write_ctrl(data),
@@ -113,26 +243,42 @@ impl Bus for NesBus {
             }
             // APU $4000–$4013 (channels), $4015 (enable/status), $4017 (frame counter). $4014=OAMDMA.
             0x4000..=0x4013 => self.apu.write(addr, data),
-            0x4014 => self.ppu.oam_dma(&self.ram, data), // OAMDMA: 256-byte copy from page in data.
+            0x4014 => self.oam_dma_page = Some(data), // OAMDMA: runs at the next tick.
             0x4015 => self.apu.write(0x4015, data),
             0x4017 => self.apu.write(0x4017, data),
-            0x4016 => self.controller.write(data), // Latch (bit 0): 1=strobe, then read $4016 for bits.
+            // Latch (bit 0): 1=strobe, then read $4016 for bits. OUT0–OUT2 also reach port 2.
+            0x4016 => {
+                self.controller.write(data);
+                if let Some(device) = self.port2.as_mut() {
+                    device.write(data);
+                }
+            }
             0x4018..=0x401F => {}
-            0x4020..=0x5FFF => {}
-            0x6000..=0x7FFF => self.cart.write(addr, data),
+            // $4020–$5FFF: expansion area (mapper registers on some boards, e.g. Namco 163).
+            0x4020..=0x7FFF => self.cart.write(addr, data),
             // Cartridge: mapper registers (e.g. MMC1 at $8000–$FFFF by bank).
             0x8000..=0xFFFF => self.cart.write(addr, data),
         }
     }
 
     /// Advance PPU by 3× cycles and APU by cycles. PPU has 341 cycles per scanline; when a visible
-    /// scanline (0–239) completes, we render it. See Cycle_reference_chart.
+    /// scanline (0–239) or the pre-render line completes, we render it (the pre-render line only
+    /// issues its sprite fetches). See Cycle_reference_chart.
+    ///
+    /// A $4014 write halts the CPU for the OAM DMA (513–514 cycles, see `run_oam_dma`). When the
+    /// DMC's sample buffer empties, the CPU is halted 4 cycles while the DMC reads the next byte
+    /// from PRG. Both stalls are ticked here and reported by `take_stall_cycles`. See APU_DMC
+    /// "Memory reader".
     fn tick(&mut self, cycles: usize) {
-        self.apu.tick(cycles);
-        for _ in 0..(cycles * 3) {
-            if let Some(scanline) = self.ppu.tick() {
-                self.ppu.render_scanline(&mut self.cart, scanline);
-            }
+        self.advance(cycles);
+        if let Some(page) = self.oam_dma_page.take() {
+            self.run_oam_dma(page);
+        }
+        while let Some(addr) = self.apu.dmc_wants_fetch() {
+            self.advance(4);
+            let byte = self.read(addr);
+            self.apu.dmc_feed_byte(byte);
+            self.stall_cycles += 4;
         }
     }
 
@@ -147,7 +293,150 @@ impl Bus for NesBus {
         }
     }
 
+    fn ppu_position(&self) -> Option<(u16, u16)> {
+        let scanline = if self.ppu.scanline < 0 { 261 } else { self.ppu.scanline as u16 };
+        Some((scanline, self.ppu.cycle))
+    }
+
+    /// Cartridge IRQ (e.g. MMC3 scanline counter) or APU frame/DMC IRQ.
     fn poll_irq(&mut self) -> bool {
-        self.cart.poll_irq()
+        self.cart.poll_irq() || self.apu.irq_pending()
+    }
+
+    fn take_stall_cycles(&mut self) -> usize {
+        std::mem::take(&mut self.stall_cycles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::mapper::Mirroring;
+
+    /// NTSC bus with a 32 KiB NROM cartridge whose PRG bytes are their offset's low byte.
+    fn nrom_bus() -> NesBus {
+        let prg = (0..32 * 1024).map(|i| i as u8).collect();
+        let cart = Cartridge::from_rom(prg, Vec::new(), 0, Mirroring::Horizontal).unwrap();
+        NesBus::new(cart, Region::Ntsc)
+    }
+
+    #[test]
+    fn prg_rom_built_in_code_is_read_through_the_bus() {
+        let mut bus = nrom_bus();
+        assert_eq!(bus.read(0x8000), 0x00);
+        assert_eq!(bus.read(0x9234), 0x34);
+        assert_eq!(bus.read(0xFFFF), 0xFF);
+    }
+
+    #[test]
+    fn microphone_sets_bit_2_of_4016() {
+        let mut bus = nrom_bus();
+        assert_eq!(bus.read(0x4016) & 0x04, 0);
+        bus.microphone = true;
+        assert_eq!(bus.read(0x4016) & 0x04, 0x04);
+    }
+
+    #[test]
+    fn write_only_ppu_registers_read_back_the_open_bus() {
+        let mut bus = nrom_bus();
+        bus.write(0x2000, 0x5A);
+        assert_eq!(bus.read(0x2000), 0x5A);
+        assert_eq!(bus.read(0x2005), 0x5A);
+        bus.write(0x2003, 0x37);
+        // $2002 puts its three flags over the low 5 bits of the latch.
+        assert_eq!(bus.read(0x2002) & 0x1F, 0x17);
+    }
+
+    #[test]
+    fn frame_is_complete_when_vblank_starts() {
+        let mut bus = nrom_bus();
+        bus.ppu.framebuffer.fill(u32::MAX);
+        while !bus.frame_ready() {
+            bus.tick(1);
+        }
+        assert_eq!(bus.ppu.scanline, 241);
+        assert!(bus.ppu.framebuffer.iter().all(|&px| px != u32::MAX));
+    }
+
+    #[test]
+    fn dmc_fetch_stalls_the_cpu_four_cycles() {
+        let mut bus = nrom_bus();
+        bus.write(0x4013, 0x01);
+        bus.write(0x4015, 0x10);
+        bus.tick(1);
+        assert_eq!(bus.take_stall_cycles(), 4);
+        bus.tick(1);
+        assert_eq!(bus.take_stall_cycles(), 0);
+    }
+
+    #[test]
+    fn dmc_fetch_during_oam_dma_costs_two_cycles() {
+        let mut bus = nrom_bus();
+        bus.write(0x4013, 0x01);
+        bus.write(0x4015, 0x10);
+        bus.write(0x4014, 0x02);
+        // The DMA starts after this one cycle; an odd start adds an alignment cycle.
+        let align = (bus.cpu_cycle + 1) as usize & 1;
+        bus.tick(1);
+        assert_eq!(bus.take_stall_cycles(), 1 + align + 2 + 512);
+    }
+
+    #[test]
+    fn apu_frame_irq_reaches_the_cpu_irq_line() {
+        let mut bus = nrom_bus();
+        bus.write(0x4017, 0x00); // 4-step sequence, IRQ enabled
+        assert!(!bus.poll_irq());
+        bus.tick(30_000);
+        assert!(bus.poll_irq());
+        bus.write(0x4017, 0x40); // inhibit clears the flag
+        assert!(!bus.poll_irq());
+    }
+
+    /// Write `data` to PPU memory from `addr` on through $2006/$2007.
+    fn write_vram(bus: &mut NesBus, addr: u16, data: &[u8]) {
+        bus.write(0x2006, (addr >> 8) as u8);
+        bus.write(0x2006, addr as u8);
+        for &byte in data {
+            bus.write(0x2007, byte);
+        }
+    }
+
+    /// Scroll nametable $2000 to (`x`, 0), as a game does through $2000/$2005.
+    fn set_scroll_x(bus: &mut NesBus, x: u8) {
+        bus.write(0x2000, 0x00);
+        bus.read(0x2002);
+        bus.write(0x2005, x);
+        bus.write(0x2005, 0);
+    }
+
+    #[test]
+    fn scroll_written_mid_frame_only_moves_the_lines_below() {
+        let mut bus = nrom_bus();
+        // Tile 1 solid color 1; nametable columns alternate tile 1 and tile 0 (8 px stripes).
+        write_vram(&mut bus, 0x0010, &[0xFF; 8]);
+        let stripes: Vec<u8> = (0..960).map(|i| (i % 2 == 0) as u8).collect();
+        write_vram(&mut bus, 0x2000, &stripes);
+        write_vram(&mut bus, 0x3F00, &[0x0F, 0x30]);
+        bus.write(0x2001, 0x0A); // background on, including the left 8 px
+        while !bus.frame_ready() {
+            bus.tick(1);
+        }
+        bus.clear_frame_ready();
+        set_scroll_x(&mut bus, 0);
+        // What an IRQ handler at the split would do (cf. an MMC3 IRQ on line 120).
+        while bus.ppu.scanline != 120 {
+            bus.tick(1);
+        }
+        set_scroll_x(&mut bus, 4);
+        while !bus.frame_ready() {
+            bus.tick(1);
+        }
+
+        let row = |y: usize| &bus.ppu.framebuffer[y * 256..y * 256 + 16];
+        let (lit, dark) = (row(50)[0], row(50)[8]);
+        assert_ne!(lit, dark);
+        assert!(row(50)[..8].iter().all(|&px| px == lit));
+        assert!(row(200)[..4].iter().all(|&px| px == lit));
+        assert!(row(200)[4..12].iter().all(|&px| px == dark));
     }
 }
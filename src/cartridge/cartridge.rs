@@ -4,61 +4,369 @@
 //! PRG size in 16 KiB units, CHR size in 8 KiB units, flags 6–7 for mapper, etc.), then PRG ROM,
 //! then CHR ROM. CHR may be ROM or RAM depending on mapper. [Mapper](https://www.nesdev.org/wiki/Mapper)
 //! implements CPU PRG ($8000–$FFFF) and PPU CHR ($0000–$1FFF) address decoding and bank switching.
+//! [NES 2.0](https://www.nesdev.org/wiki/NES_2.0) headers add larger ROM sizes, 12-bit mapper
+//! numbers, submappers and RAM sizes on top of the same layout.
 
+use std::fmt;
 use std::fs::File;
-use std::io::Read;
+use std::io::{self, Read};
 
 use crate::cartridge::mapper::mapper::Mapper;
 use crate::cartridge::mapper::mapper0::Mapper0;
 use crate::cartridge::mapper::mapper1::Mapper1;
+use crate::cartridge::mapper::mapper2::Mapper2;
 use crate::cartridge::mapper::mapper4::Mapper4;
+use crate::cartridge::mapper::mapper7::Mapper7;
+use crate::cartridge::mapper::mapper9::Mapper9;
+use crate::cartridge::mapper::mapper10::Mapper10;
+use crate::cartridge::mapper::mapper11::Mapper11;
+use crate::cartridge::mapper::mapper19::Mapper19;
+use crate::cartridge::mapper::mapper34::Mapper34;
+use crate::cartridge::mapper::mapper69::Mapper69;
 use crate::cartridge::mapper::Mirroring;
+use crate::region::Region;
+
+/// Why a ROM file could not be loaded.
+#[derive(Debug)]
+pub enum CartError {
+    /// The file could not be opened or read.
+    Io(io::Error),
+    /// Shorter than the 16-byte header.
+    InvalidHeader,
+    /// Bytes 0–3 are not the "NES\x1A" magic, so this is not an iNES file at all.
+    BadMagic,
+    /// The file ends before the trainer, PRG and CHR data its header declares.
+    Truncated { expected: usize, actual: usize },
+    /// iNES / NES 2.0 mapper number with no implementation here.
+    UnsupportedMapper(u16),
+}
+
+impl fmt::Display for CartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CartError::Io(err) => write!(f, "{err}"),
+            CartError::InvalidHeader => write!(f, "file is too short for an iNES header"),
+            CartError::BadMagic => write!(f, "not an iNES file (missing NES\\x1A signature)"),
+            CartError::Truncated { expected, actual } => write!(
+                f,
+                "ROM file is truncated: header declares {expected} bytes, file has {actual}"
+            ),
+            CartError::UnsupportedMapper(id) => write!(f, "unsupported mapper {id}"),
+        }
+    }
+}
+
+impl std::error::Error for CartError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CartError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CartError {
+    fn from(err: io::Error) -> Self {
+        CartError::Io(err)
+    }
+}
 
 /// Cartridge: holds PRG/CHR and the mapper that implements read/write and nametable mirroring.
 /// CPU reads PRG via bus at $8000–$FFFF; PPU reads CHR at $0000–$1FFF (pattern tables).
+#[derive(Clone)]
 pub struct Cartridge {
     pub mapper: Box<dyn Mapper>,
+    /// CHR is RAM (no CHR ROM in the image); see `is_chr_ram`.
+    chr_is_ram: bool,
+    /// TV system declared by the header, if any; see `region`.
+    region: Option<Region>,
+    mapper_id: u16,
+    submapper: u8,
+    prg_ram_size: usize,
+    /// 512-byte trainer from the file, if any; see `trainer`.
+    trainer: Option<Vec<u8>>,
+    prg_rom_size: usize,
+    chr_rom_size: usize,
+    has_battery: bool,
+    initial_mirroring: Mirroring,
 }
 
 impl Cartridge {
     /// Load cartridge from iNES file. Header bytes 4–5 = PRG/CHR size; bytes 6–7 = mapper number
     /// (low nibble of 6 | high nibble of 7). See iNES "File format".
-    pub fn load(path: &str) -> Self {
-        let mut file = File::open(path).expect("Failed to open ROM");
+    pub fn load(path: &str) -> Result<Self, CartError> {
+        let mut file = File::open(path)?;
         let mut data = Vec::new();
-        file.read_to_end(&mut data).unwrap();
-
-        let prg_rom_size = data[4] as usize * 16 * 1024; // PRG ROM size in 16 KiB units
-        let chr_rom_size = data[5] as usize * 8 * 1024;  // CHR ROM size in 8 KiB units (0 → 8 KiB RAM)
+        file.read_to_end(&mut data)?;
+        Self::from_bytes(&data)
+    }
 
-        let prg_start = 16;
-        let prg_end = prg_start + prg_rom_size;
-        let chr_start = prg_end;
-        let chr_end = chr_start + chr_rom_size;
+    /// Parse an in-memory iNES image. The data must hold the header, the 512-byte trainer if
+    /// flag 6 bit 2 says there is one, and all declared PRG and CHR ROM; bytes past that are
+    /// ignored with a warning.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, CartError> {
+        if !data.starts_with(b"NES\x1A") {
+            return Err(CartError::BadMagic);
+        }
+        if data.len() < 16 {
+            return Err(CartError::InvalidHeader);
+        }
 
-        let prg_rom = data[prg_start..prg_end].to_vec();
-        let chr_rom = if chr_rom_size > 0 {
-            data[chr_start..chr_end].to_vec()
+        // NES 2.0 (byte 7 bits 2–3 = 10) extends the ROM sizes with byte 9 and the mapper number
+        // with byte 8; everything else is read as plain iNES.
+        let nes2 = data[7] & 0x0C == 0x08;
+        let (prg_rom_size, chr_rom_size) = if nes2 {
+            (
+                nes2_rom_size(data[4], data[9] & 0x0F, 16 * 1024),
+                nes2_rom_size(data[5], data[9] >> 4, 8 * 1024),
+            )
         } else {
-            vec![0; 8 * 1024] // No CHR ROM → 8 KiB CHR RAM (e.g. some NROM, MMC1)
+            // PRG ROM in 16 KiB units, CHR ROM in 8 KiB units (0 → CHR RAM).
+            (data[4] as usize * 16 * 1024, data[5] as usize * 8 * 1024)
         };
 
-        // Mapper number from header bytes 6–7 (iNES). 0 = NROM, 1 = MMC1, 4 = MMC3.
-        let mapper_id = (data[6] >> 4) | (data[7] & 0xF0);
+        // Optional 512-byte trainer between header and PRG (flag 6 bit 2); see `trainer`.
+        let has_trainer = data[6] & 0x04 != 0;
+        let prg_start: usize = if has_trainer { 16 + 512 } else { 16 };
+        let prg_end = prg_start.saturating_add(prg_rom_size);
+        let chr_start = prg_end;
+        let chr_end = chr_start.saturating_add(chr_rom_size);
+        if data.len() < chr_end {
+            return Err(CartError::Truncated {
+                expected: chr_end,
+                actual: data.len(),
+            });
+        }
+        if data.len() > chr_end {
+            log::warn!(
+                "ignoring {} bytes past the end of the declared ROM data",
+                data.len() - chr_end
+            );
+        }
+
+        // Mapper number from header bytes 6–7 (iNES), plus byte 8 bits 0–3 (NES 2.0).
+        let mut mapper_id = ((data[6] >> 4) | (data[7] & 0xF0)) as u16;
+        let mut submapper = 0;
+        // PRG RAM: NES 2.0 byte 10 as 64 << n bytes (low nibble volatile, high nibble
+        // battery-backed); plain iNES boards get the customary 8 KiB.
+        let mut prg_ram_size = 8 * 1024;
+        if nes2 {
+            mapper_id |= ((data[8] & 0x0F) as u16) << 8;
+            submapper = data[8] >> 4;
+            prg_ram_size = [data[10] & 0x0F, data[10] >> 4]
+                .iter()
+                .map(|&shift| if shift == 0 { 0 } else { 64 << shift })
+                .sum();
+        }
         // Mirroring from iNES byte 6 bit 0: 0 = horizontal, 1 = vertical (board solder pads for NROM).
-        let mirroring = if data[6] & 1 != 0 {
+        // Bit 3 overrides it with four-screen VRAM on the cartridge.
+        let mirroring = if data[6] & 0x08 != 0 {
+            Mirroring::FourScreen
+        } else if data[6] & 1 != 0 {
             Mirroring::Vertical
         } else {
             Mirroring::Horizontal
         };
+        // CHR RAM size: NES 2.0 (byte 7 bits 2–3 = 10) declares it in byte 11 as 64 << n bytes
+        // (low nibble volatile, high nibble battery-backed); plain iNES implies 8 KiB.
+        let chr = if chr_rom_size > 0 {
+            data[chr_start..chr_end].to_vec()
+        } else {
+            let shift = if nes2 {
+                (data[11] & 0x0F).max(data[11] >> 4)
+            } else {
+                0
+            };
+            let size = if shift == 0 { 8 * 1024 } else { 64 << shift };
+            vec![0; size]
+        };
+        let mut cart = Self::with_mapper(
+            data[prg_start..prg_end].to_vec(),
+            chr,
+            chr_rom_size == 0,
+            mapper_id,
+            submapper,
+            mirroring,
+        )?;
+        cart.region = header_region(data);
+        cart.prg_ram_size = prg_ram_size;
+        cart.trainer = has_trainer.then(|| data[16..prg_start].to_vec());
+        if let Some(trainer) = &cart.trainer {
+            for (i, &byte) in trainer.iter().enumerate() {
+                cart.mapper.write(0x7000 + i as u16, byte);
+            }
+        }
+        cart.has_battery = data[6] & 0x02 != 0;
+        Ok(cart)
+    }
+
+    /// Build a cartridge from raw PRG and CHR ROM, e.g. for tests without a .nes file. Empty
+    /// `chr_rom` gives 8 KiB CHR RAM. `mirroring` is the header's hard-wired mirroring, used by
+    /// mappers that cannot switch it.
+    pub fn from_rom(
+        prg_rom: Vec<u8>,
+        chr_rom: Vec<u8>,
+        mapper_id: u8,
+        mirroring: Mirroring,
+    ) -> Result<Self, CartError> {
+        let chr_is_ram = chr_rom.is_empty();
+        let chr_rom = if chr_is_ram {
+            vec![0; 8 * 1024] // No CHR ROM → 8 KiB CHR RAM (e.g. some NROM, MMC1)
+        } else {
+            chr_rom
+        };
+        Self::with_mapper(prg_rom, chr_rom, chr_is_ram, mapper_id as u16, 0, mirroring)
+    }
+
+    /// Build the mapper for `mapper_id` (and `submapper`, where it matters) over `chr`, which is
+    /// already sized (CHR RAM included).
+    fn with_mapper(
+        prg_rom: Vec<u8>,
+        chr_rom: Vec<u8>,
+        chr_is_ram: bool,
+        mapper_id: u16,
+        submapper: u8,
+        mirroring: Mirroring,
+    ) -> Result<Self, CartError> {
+        let prg_rom_size = prg_rom.len();
+        let chr_rom_size = if chr_is_ram { 0 } else { chr_rom.len() };
+        // 0 = NROM, 1 = MMC1, 2 = UxROM, 4 = MMC3 (submapper 1 = MMC6), 7 = AxROM, 9 = MMC2,
+        // 10 = MMC4, 11 = Color Dreams, 19 = Namco 163, 34 = BNROM/NINA-001, 69 = FME-7.
+        // Discrete boards with NES 2.0 submapper 2 declare bus conflicts.
         let mapper: Box<dyn Mapper> = match mapper_id {
-            0 => Box::new(Mapper0::new(prg_rom, chr_rom, mirroring)),
-            1 => Box::new(Mapper1::new(prg_rom)),
-            4 => Box::new(Mapper4::new(prg_rom, chr_rom, mirroring)),
-            _ => panic!("unsupported mapper {}", mapper_id),
+            0 => Box::new(Mapper0::new(prg_rom, chr_rom, chr_is_ram, mirroring)),
+            1 => Box::new(Mapper1::new(prg_rom, chr_rom, chr_is_ram)),
+            2 => {
+                let mut uxrom = Mapper2::new(prg_rom, chr_rom, chr_is_ram, mirroring);
+                uxrom.set_bus_conflicts(submapper == 2);
+                Box::new(uxrom)
+            }
+            4 => {
+                let mmc6 = submapper == 1;
+                Box::new(Mapper4::new(prg_rom, chr_rom, chr_is_ram, mirroring, !mmc6))
+            }
+            7 => {
+                let mut axrom = Mapper7::new(prg_rom, chr_rom, chr_is_ram);
+                axrom.set_bus_conflicts(submapper == 2);
+                Box::new(axrom)
+            }
+            9 => Box::new(Mapper9::new(prg_rom, chr_rom, mirroring)),
+            10 => Box::new(Mapper10::new(prg_rom, chr_rom, mirroring)),
+            11 => {
+                let mut color_dreams = Mapper11::new(prg_rom, chr_rom, mirroring);
+                color_dreams.set_bus_conflicts(submapper == 2);
+                Box::new(color_dreams)
+            }
+            19 => Box::new(Mapper19::new(prg_rom, chr_rom, mirroring)),
+            34 => {
+                // Submapper 2 is BNROM; the setting doesn't reach NINA-001's RAM registers.
+                let mut board = Mapper34::new(prg_rom, chr_rom, chr_is_ram, mirroring);
+                board.set_bus_conflicts(submapper == 2);
+                Box::new(board)
+            }
+            69 => Box::new(Mapper69::new(prg_rom, chr_rom, chr_is_ram, mirroring)),
+            _ => return Err(CartError::UnsupportedMapper(mapper_id)),
         };
+        Ok(Self {
+            mapper,
+            chr_is_ram,
+            region: None,
+            mapper_id,
+            submapper,
+            prg_ram_size: 8 * 1024,
+            trainer: None,
+            prg_rom_size,
+            chr_rom_size,
+            has_battery: false,
+            initial_mirroring: mirroring,
+        })
+    }
+
+    /// Wrap an already-built mapper (e.g. one constructed by a test or a custom board). The
+    /// cartridge reports CHR ROM; the mapper itself decides whether CHR writes stick.
+    pub fn from_parts(mapper: Box<dyn Mapper>) -> Self {
+        Self {
+            mapper,
+            chr_is_ram: false,
+            region: None,
+            mapper_id: 0,
+            submapper: 0,
+            prg_ram_size: 8 * 1024,
+            trainer: None,
+            prg_rom_size: 0,
+            chr_rom_size: 0,
+            has_battery: false,
+            initial_mirroring: Mirroring::Horizontal,
+        }
+    }
+
+    /// TV system from the header: NES 2.0 byte 12, or the rarely set iNES byte 9 bit 0. `None`
+    /// when the header doesn't say (or the ROM runs on both); frontends then default to NTSC
+    /// unless the user overrides it.
+    pub fn region(&self) -> Option<Region> {
+        self.region
+    }
+
+    /// Region to run in: the user's `choice` (e.g. `--region`) wins over the header, and NTSC is
+    /// used when neither says.
+    pub fn resolve_region(&self, choice: Option<Region>) -> Region {
+        choice.or(self.region).unwrap_or_default()
+    }
+
+    /// Mapper number (12 bits with NES 2.0). 0 for cartridges built with `from_parts`.
+    pub fn mapper_id(&self) -> u16 {
+        self.mapper_id
+    }
+
+    /// Board or chip name of the mapper, e.g. "MMC3". See `Mapper::name`.
+    pub fn mapper_name(&self) -> &'static str {
+        self.mapper.name()
+    }
+
+    /// PRG ROM size in bytes. 0 for cartridges built with `from_parts`.
+    pub fn prg_rom_size(&self) -> usize {
+        self.prg_rom_size
+    }
 
-        Self { mapper }
+    /// CHR ROM size in bytes; 0 for CHR RAM and for cartridges built with `from_parts`.
+    pub fn chr_rom_size(&self) -> usize {
+        self.chr_rom_size
+    }
+
+    /// The header says PRG RAM is battery-backed (flag 6 bit 1), i.e. the game saves.
+    pub fn has_battery(&self) -> bool {
+        self.has_battery
+    }
+
+    /// Mirroring wired by the header (flag 6 bits 0 and 3). Mappers with a mirroring register
+    /// may since have switched it; `mapper.mirroring()` has the live value.
+    pub fn initial_mirroring(&self) -> Mirroring {
+        self.initial_mirroring
+    }
+
+    /// NES 2.0 submapper (byte 8 bits 4–7); 0 for iNES files.
+    pub fn submapper(&self) -> u8 {
+        self.submapper
+    }
+
+    /// PRG RAM the header declares, volatile plus battery-backed, in bytes. NES 2.0 gives it in
+    /// byte 10; iNES files report 8 KiB.
+    pub fn prg_ram_size(&self) -> usize {
+        self.prg_ram_size
+    }
+
+    /// The 512-byte trainer stored before PRG ROM (flag 6 bit 2), which copiers loaded into PRG
+    /// RAM at $7000–$71FF before starting the game. It is copied into the mapper's PRG RAM there
+    /// on load (mappers without PRG RAM drop it). See
+    /// [iNES trainer](https://www.nesdev.org/wiki/INES#Trainer).
+    pub fn trainer(&self) -> Option<&[u8]> {
+        self.trainer.as_deref()
+    }
+
+    /// True when the pattern tables are CHR RAM (the image has no CHR ROM), so PPU writes to
+    /// $0000–$1FFF are allowed.
+    pub fn is_chr_ram(&self) -> bool {
+        self.chr_is_ram
     }
 
     /// Read: PRG space ($8000–$FFFF) or CHR ($0000–$1FFF) depending on addr. Mapper dispatches.
@@ -71,13 +379,271 @@ impl Cartridge {
         self.mapper.write(addr, data);
     }
 
+    /// CPU read of $4020–$5FFF; `None` means open bus. See `Mapper::expansion_read`.
+    pub fn expansion_read(&mut self, addr: u16) -> Option<u8> {
+        self.mapper.expansion_read(addr)
+    }
+
+    /// Advance mapper CPU-cycle counters by `cycles`.
+    pub fn cpu_clock(&mut self, cycles: usize) {
+        self.mapper.cpu_clock(cycles);
+    }
+
+    /// Expansion audio level to mix with the APU. See `Mapper::audio_output`.
+    pub fn audio_output(&self) -> f32 {
+        self.mapper.audio_output()
+    }
+
     /// Notify mapper of PPU CHR read (e.g. MMC3 IRQ counter on A12 rising edge).
-    pub fn on_chr_access(&mut self, addr: u16) {
-        self.mapper.on_chr_access(addr);
+    pub fn on_chr_access(&mut self, addr: u16, ppu_dot: u64) {
+        self.mapper.on_chr_access(addr, ppu_dot);
+    }
+
+    /// Nametable byte supplied by the cartridge instead of CIRAM, if any. See `Mapper`.
+    pub fn ppu_nametable_read(&mut self, addr: u16) -> Option<u8> {
+        self.mapper.ppu_nametable_read(addr)
     }
 
-    /// Poll and clear mapper IRQ (e.g. MMC3 scanline IRQ). Returns true if IRQ was pending.
+    /// Offer a nametable write to the cartridge; true if it handled it.
+    pub fn ppu_nametable_write(&mut self, addr: u16, data: u8) -> bool {
+        self.mapper.ppu_nametable_write(addr, data)
+    }
+
+    /// PRG bank mapped at CPU `addr`, if the mapper reports it. See `Mapper::prg_bank_at`.
+    pub fn prg_bank_at(&self, addr: u16) -> Option<usize> {
+        self.mapper.prg_bank_at(addr)
+    }
+
+    /// CHR bank mapped at PPU `addr`, if the mapper reports it. See `Mapper::chr_bank_at`.
+    pub fn chr_bank_at(&self, addr: u16) -> Option<usize> {
+        self.mapper.chr_bank_at(addr)
+    }
+
+    /// One-line bank map for debugging, e.g. `PRG 06 07 1E 1F  CHR 00 01 02 03 04 05 06 07`:
+    /// the bank at each 8 KiB of CPU $8000–$FFFF and each 1 KiB of PPU $0000–$1FFF. Banks the
+    /// mapper doesn't report print as `--`.
+    pub fn bank_summary(&self) -> String {
+        let fmt = |bank: Option<usize>| bank.map_or("--".to_string(), |b| format!("{b:02X}"));
+        let prg: Vec<String> = (0..4u16)
+            .map(|i| fmt(self.prg_bank_at(0x8000 + i * 0x2000)))
+            .collect();
+        let chr: Vec<String> = (0..8u16)
+            .map(|i| fmt(self.chr_bank_at(i * 0x400)))
+            .collect();
+        format!("PRG {}  CHR {}", prg.join(" "), chr.join(" "))
+    }
+
+    /// Mapper IRQ output (e.g. MMC3 scanline IRQ); true until the game acknowledges it.
     pub fn poll_irq(&mut self) -> bool {
         self.mapper.poll_irq()
     }
 }
+
+/// NES 2.0 ROM size from the size LSB (byte 4 or 5) and its MSB nibble (byte 9). MSB $F
+/// switches to exponent notation, LSB = EEEEEEMM for 2^E × (MM × 2 + 1) bytes. Saturates, so an
+/// absurd size shows up as a truncated file. See
+/// [NES 2.0 ROM size](https://www.nesdev.org/wiki/NES_2.0#PRG-ROM_Area).
+fn nes2_rom_size(lsb: u8, msb: u8, unit: usize) -> usize {
+    if msb == 0x0F {
+        let multiplier = (lsb & 3) as usize * 2 + 1;
+        1usize
+            .checked_shl((lsb >> 2) as u32)
+            .and_then(|size| size.checked_mul(multiplier))
+            .unwrap_or(usize::MAX)
+    } else {
+        ((msb as usize) << 8 | lsb as usize) * unit
+    }
+}
+
+/// Region declared by an iNES header. NES 2.0 (byte 7 bits 2–3 = 10): byte 12 bits 0–1, with
+/// Dendy run as PAL (50 Hz) and multi-region as unspecified. iNES 1.0: byte 9 bit 0 = PAL, trusted
+/// only when bytes 12–15 are zero (older tools wrote signatures like "DiskDude!" over 7–15). See
+/// [NES 2.0](https://www.nesdev.org/wiki/NES_2.0#CPU/PPU_Timing).
+fn header_region(header: &[u8]) -> Option<Region> {
+    if header[7] & 0x0C == 0x08 {
+        match header[12] & 3 {
+            0 => Some(Region::Ntsc),
+            1 | 3 => Some(Region::Pal),
+            _ => None,
+        }
+    } else if header[12..16].iter().all(|&b| b == 0) && header[9] & 1 != 0 {
+        Some(Region::Pal)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// iNES image with `mapper`, flag 6 bits 0–3 from `flags6`, one 16 KiB PRG bank and
+    /// `chr_banks` 8 KiB CHR banks, plus a trainer of 0, 1, 2… when flag 6 bit 2 is set.
+    fn ines(mapper: u8, flags6: u8, chr_banks: u8) -> Vec<u8> {
+        let mut data = b"NES\x1A".to_vec();
+        data.extend([1, chr_banks, (mapper << 4) | (flags6 & 0x0F), mapper & 0xF0]);
+        data.resize(16, 0);
+        if flags6 & 0x04 != 0 {
+            data.extend((0..512).map(|i| i as u8));
+        }
+        data.resize(data.len() + 16 * 1024 + chr_banks as usize * 8 * 1024, 0);
+        data
+    }
+
+    #[test]
+    fn rejects_short_header_and_accepts_trailing_bytes() {
+        let data = ines(0, 0, 1);
+        assert!(matches!(Cartridge::from_bytes(&data[..10]), Err(CartError::InvalidHeader)));
+        let mut oversized = data;
+        oversized.extend([0xFF; 100]);
+        assert!(Cartridge::from_bytes(&oversized).is_ok());
+    }
+
+    #[test]
+    fn from_bytes_loads_minimal_nrom() {
+        let mut data = ines(0, 0x01, 1);
+        data[16 + 0x3FFC] = 0x34;
+        let cart = Cartridge::from_bytes(&data).unwrap();
+        assert_eq!(cart.mapper_id(), 0);
+        assert_eq!(cart.initial_mirroring(), Mirroring::Vertical);
+        assert_eq!(cart.read(0xFFFC), 0x34);
+    }
+
+    #[test]
+    fn from_bytes_rejects_truncated_and_bad_magic() {
+        assert!(matches!(Cartridge::from_bytes(b"NES\x1A"), Err(CartError::InvalidHeader)));
+        let mut data = ines(0, 0, 1);
+        data.truncate(data.len() - 1);
+        assert!(matches!(Cartridge::from_bytes(&data), Err(CartError::Truncated { .. })));
+        data[0] = b'X';
+        assert!(matches!(Cartridge::from_bytes(&data), Err(CartError::BadMagic)));
+    }
+
+    #[test]
+    fn rejects_a_jpeg_and_reports_how_short_a_signed_file_is() {
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10, b'J', b'F', b'I', b'F', 0];
+        jpeg.resize(4096, 0);
+        assert!(matches!(Cartridge::from_bytes(&jpeg), Err(CartError::BadMagic)));
+
+        let mut data = ines(0, 0, 1);
+        data.truncate(16 + 1000);
+        let expected = 16 + 16 * 1024 + 8 * 1024;
+        assert!(matches!(
+            Cartridge::from_bytes(&data),
+            Err(CartError::Truncated { expected: e, actual: 1016 }) if e == expected
+        ));
+    }
+
+    #[test]
+    fn nes2_header_with_exponent_prg_size() {
+        // PRG 2^15 × 1 bytes (byte 9 low nibble $F), MMC1 submapper 5, 8 KiB PRG RAM, CHR RAM.
+        let mut data = b"NES\x1A".to_vec();
+        data.extend([15 << 2, 0, 0x10, 0x08, 0x50, 0x0F, 0x07, 0x07]);
+        data.resize(16 + 32 * 1024, 0);
+        data[16 + 32 * 1024 - 1] = 0x77;
+        let cart = Cartridge::from_bytes(&data).unwrap();
+        assert_eq!((cart.mapper_id(), cart.submapper()), (1, 5));
+        assert_eq!((cart.prg_rom_size(), cart.prg_ram_size()), (32 * 1024, 8 * 1024));
+        assert_eq!(cart.read(0xFFFF), 0x77);
+    }
+
+    #[test]
+    fn uxrom_submapper_2_turns_on_bus_conflicts() {
+        for (submapper, bank) in [(0, 2), (2, 0)] {
+            // NES 2.0 UxROM, four 16 KiB banks each filled with their number, CHR RAM.
+            let mut data = b"NES\x1A".to_vec();
+            data.extend([4, 0, 0x20, 0x08, submapper << 4]);
+            data.resize(16, 0);
+            data.extend((0..4u8).flat_map(|bank| [bank; 16 * 1024]));
+            let mut cart = Cartridge::from_bytes(&data).unwrap();
+            // The ROM byte under $8000 is 0, which masks the whole bank number off.
+            cart.write(0x8000, 2);
+            assert_eq!(cart.read(0x8000), bank, "submapper {submapper}");
+        }
+    }
+
+    #[test]
+    fn color_dreams_and_bnrom_submapper_2_turn_on_bus_conflicts() {
+        // Mapper number in the flags 6 / 7 high nibbles (flags 7 also marks NES 2.0).
+        for (flags6, flags7) in [(0xB0, 0x08), (0x20, 0x28)] {
+            for (submapper, bank) in [(0, 1), (2, 0)] {
+                // Two 32 KiB banks, each filled with its number, and CHR RAM.
+                let mut data = b"NES\x1A".to_vec();
+                data.extend([4, 0, flags6, flags7, submapper << 4]);
+                data.resize(16, 0);
+                data.extend((0..2u8).flat_map(|bank| [bank; 32 * 1024]));
+                let mut cart = Cartridge::from_bytes(&data).unwrap();
+                // The ROM byte under $8000 is 0, which masks the bank number off.
+                cart.write(0x8000, 1);
+                let mapper = cart.mapper_id();
+                assert_eq!(cart.read(0x8000), bank, "mapper {mapper} submapper {submapper}");
+            }
+        }
+    }
+
+    #[test]
+    fn reports_mmc3_identity_and_rom_geometry() {
+        let cart = Cartridge::from_bytes(&ines(4, 0x02, 2)).unwrap();
+        assert_eq!((cart.mapper_id(), cart.mapper_name()), (4, "MMC3"));
+        assert_eq!((cart.prg_rom_size(), cart.chr_rom_size()), (16 * 1024, 16 * 1024));
+        assert!(cart.has_battery());
+        assert_eq!(cart.initial_mirroring(), Mirroring::Horizontal);
+        assert!(!cart.is_chr_ram());
+    }
+
+    #[test]
+    fn nrom_has_no_irq_and_ignores_chr_access() {
+        let chr = (0..8 * 1024).map(|i| i as u8).collect();
+        let mut cart =
+            Cartridge::from_rom(vec![0; 16 * 1024], chr, 0, Mirroring::Horizontal).unwrap();
+        cart.on_chr_access(0x1000, 0);
+        assert!(!cart.poll_irq());
+        assert_eq!(cart.read(0x1234), 0x34);
+    }
+
+    #[test]
+    fn trainer_is_copied_to_prg_ram_at_7000() {
+        let cart = Cartridge::from_bytes(&ines(4, 0x04, 1)).unwrap();
+        for i in 0..512u16 {
+            assert_eq!(cart.read(0x7000 + i), i as u8);
+        }
+    }
+
+    /// Load an MMC1 CHR RAM image and report whether 4 KiB CHR bank 2 is separate RAM from bank
+    /// 0, i.e. whether more than 8 KiB was allocated.
+    fn mmc1_chr_bank_2_is_distinct(data: &[u8]) -> bool {
+        let mut cart = Cartridge::from_bytes(data).unwrap();
+        let write_register = |cart: &mut Cartridge, addr: u16, value: u8| {
+            for bit in 0..5 {
+                cart.write(addr, value >> bit & 1);
+                cart.cpu_clock(1);
+            }
+        };
+        write_register(&mut cart, 0x8000, 0x10); // 4 KiB CHR mode
+        cart.write(0x0000, 0xAA);
+        write_register(&mut cart, 0xA000, 2);
+        cart.read(0x0000) != 0xAA
+    }
+
+    #[test]
+    fn nes2_header_sizes_chr_ram() {
+        let mut data = ines(1, 0, 0);
+        assert!(!mmc1_chr_bank_2_is_distinct(&data));
+        data[7] |= 0x08; // NES 2.0
+        data[11] = 0x08; // 64 << 8 = 16 KiB CHR RAM
+        assert!(Cartridge::from_bytes(&data).unwrap().is_chr_ram());
+        assert!(mmc1_chr_bank_2_is_distinct(&data));
+    }
+
+    #[test]
+    fn nes2_pal_flag_selects_pal_unless_overridden() {
+        let mut data = ines(0, 0, 1);
+        assert_eq!(Cartridge::from_bytes(&data).unwrap().resolve_region(None), Region::Ntsc);
+        data[7] |= 0x08; // NES 2.0
+        data[12] = 0x01; // PAL
+        let cart = Cartridge::from_bytes(&data).unwrap();
+        assert_eq!(cart.region(), Some(Region::Pal));
+        assert_eq!(cart.resolve_region(None), Region::Pal);
+        assert_eq!(cart.resolve_region(Some(Region::Ntsc)), Region::Ntsc);
+    }
+}
@@ -0,0 +1,62 @@
+//! CHR latches shared by MMC2 and MMC4.
+//!
+//! [MMC2](https://www.nesdev.org/wiki/MMC2) and [MMC4](https://www.nesdev.org/wiki/MMC4) each
+//! have two 4 KiB CHR windows ($0000, $1000) with two bank registers apiece. A latch per window
+//! picks the register: when the PPU fetches the pattern of tile $FD or $FE (address $xFD8 or
+//! $xFE8), the latch flips to that tile, and the new bank is used from the next fetch on.
+
+/// Latch value: which of a window's two bank registers is in use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Latch {
+    Fd,
+    Fe,
+}
+
+/// Both windows' latches and bank registers.
+#[derive(Clone, Debug)]
+pub struct ChrLatches {
+    /// Latch per 4 KiB window ($0000, $1000).
+    pub latch: [Latch; 2],
+    /// `banks[window][0]` is used when the latch is $FD, `[1]` when $FE (4 KiB units).
+    pub banks: [[usize; 2]; 2],
+    /// MMC2 triggers the $0000 window only on exactly $0FD8 / $0FE8; MMC4 (and both chips' $1000
+    /// window) on the whole $xFD8–$xFDF / $xFE8–$xFEF range.
+    exact_low_trigger: bool,
+}
+
+impl ChrLatches {
+    /// Latches start at $FE (the power-on state most emulators use; games set them before use).
+    pub fn new(exact_low_trigger: bool) -> Self {
+        Self {
+            latch: [Latch::Fe; 2],
+            banks: [[0; 2]; 2],
+            exact_low_trigger,
+        }
+    }
+
+    /// 4 KiB bank currently mapped at PPU `addr` ($0000–$1FFF).
+    pub fn bank(&self, addr: u16) -> usize {
+        let window = (addr as usize >> 12) & 1;
+        let reg = match self.latch[window] {
+            Latch::Fd => 0,
+            Latch::Fe => 1,
+        };
+        self.banks[window][reg]
+    }
+
+    /// Update the latches after a PPU pattern fetch at `addr`.
+    pub fn on_fetch(&mut self, addr: u16) {
+        let window = (addr as usize >> 12) & 1;
+        let low = addr & 0x0FFF;
+        let (fd, fe) = if window == 0 && self.exact_low_trigger {
+            (low == 0x0FD8, low == 0x0FE8)
+        } else {
+            (low & 0xFF8 == 0xFD8, low & 0xFF8 == 0xFE8)
+        };
+        if fd {
+            self.latch[window] = Latch::Fd;
+        } else if fe {
+            self.latch[window] = Latch::Fe;
+        }
+    }
+}
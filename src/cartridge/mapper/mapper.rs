@@ -18,10 +18,60 @@ pub trait Mapper {
     /// Current nametable mirroring: horizontal (vertical mirroring), vertical (horizontal
     /// mirroring), or one-screen. PPU uses this to map $2000–$2FFF to 2 KiB. See PPU_nametables.
     fn mirroring(&mut self) -> Mirroring;
-    /// Called when the PPU reads a CHR address (e.g. MMC3 clocks IRQ on A12 rising edge). Default: no-op.
-    fn on_chr_access(&mut self, _addr: u16) {}
-    /// Returns true if mapper has a pending IRQ and clears it. Default: false.
+    /// Called when the PPU reads a CHR address (e.g. MMC3 clocks IRQ on A12 rising edge).
+    /// `ppu_dot` is the PPU clock (dots since power-on) of the fetch. Default: no-op.
+    fn on_chr_access(&mut self, _addr: u16, _ppu_dot: u64) {}
+    /// PPU nametable read ($2000–$2FFF, already folded from the $3000 mirror). `Some(byte)`
+    /// overrides the console's internal nametable RAM, for fill-mode nametables (MMC5) or
+    /// cartridge VRAM (four-screen boards). Default: `None` (use internal RAM per `mirroring`).
+    fn ppu_nametable_read(&mut self, _addr: u16) -> Option<u8> {
+        None
+    }
+    /// PPU nametable write ($2000–$2FFF). Return true if the cartridge stored the byte, so the
+    /// internal RAM is left untouched. Default: false.
+    fn ppu_nametable_write(&mut self, _addr: u16, _data: u8) -> bool {
+        false
+    }
+    /// CPU read of the expansion area $4020–$5FFF. `Some` for mappers with readable registers
+    /// there (e.g. Namco 163 at $4800–$5FFF). Default: `None` (open bus). Writes to the area go
+    /// to `write`.
+    fn expansion_read(&mut self, _addr: u16) -> Option<u8> {
+        None
+    }
+    /// Called as the CPU runs, `cycles` CPU cycles at a time, for CPU-cycle IRQ counters
+    /// (e.g. Namco 163). Default: no-op.
+    fn cpu_clock(&mut self, _cycles: usize) {}
+    /// Expansion audio level in NES mixer units (a full-volume pulse is ~0.15), added to the APU
+    /// mix (e.g. Sunsoft 5B). Default: 0.0 (no expansion audio).
+    fn audio_output(&self) -> f32 {
+        0.0
+    }
+    /// Returns true while the mapper asserts IRQ. The line is level-triggered: it stays asserted
+    /// until the game acknowledges it through a mapper register. Default: false.
     fn poll_irq(&mut self) -> bool {
         false
     }
+    /// PRG ROM bank currently serving CPU `addr` ($8000–$FFFF), in units of the mapper's PRG
+    /// bank size (16 KiB for NROM/MMC1, 8 KiB for MMC3). For debuggers. Default: `None`.
+    fn prg_bank_at(&self, _addr: u16) -> Option<usize> {
+        None
+    }
+    /// CHR bank currently serving PPU `addr` ($0000–$1FFF), in units of the mapper's finest CHR
+    /// bank size (8 KiB for NROM, 1 KiB for MMC3). For debuggers. Default: `None`.
+    fn chr_bank_at(&self, _addr: u16) -> Option<usize> {
+        None
+    }
+    /// Board or chip name for display, e.g. "MMC3". Default: "Unknown".
+    fn name(&self) -> &'static str {
+        "Unknown"
+    }
+    /// Copy of this mapper with all bank registers, RAM and IRQ state, for cloning a
+    /// `Cartridge` (save states, rewind). Implement as `Box::new(self.clone())`.
+    fn clone_box(&self) -> Box<dyn Mapper>;
+}
+
+impl Clone for Box<dyn Mapper> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
 }
@@ -8,26 +8,38 @@ use crate::cartridge::mapper::{Mirroring, mapper::Mapper};
 
 /// NROM: one or two 16 KiB PRG banks, 8 KiB CHR (ROM or RAM). No registers.
 /// Mirroring is fixed by the board; we take it from the iNES header (byte 6 bit 0).
+#[derive(Clone)]
 pub struct Mapper0 {
     prg_rom: Vec<u8>,
     chr_rom: Vec<u8>,
+    /// CHR is RAM (the header declared no CHR ROM), so pattern writes stick.
+    chr_is_ram: bool,
     mirroring: Mirroring,
 }
 
 impl Mapper0 {
-    /// Create NROM with given PRG, CHR, and mirroring. CHR may be ROM or used as 8 KiB RAM (writable) if
-    /// cartridge has no CHR ROM (chr_rom.len() == 8192 and we allow writes).
-    /// Mirroring should come from iNES header byte 6 bit 0 (0 = horizontal, 1 = vertical).
-    pub fn new(prg_rom: Vec<u8>, chr_rom: Vec<u8>, mirroring: Mirroring) -> Self {
+    /// Create NROM with given PRG, CHR, and mirroring. `chr_is_ram` makes CHR writable (boards
+    /// without CHR ROM). Mirroring should come from iNES header byte 6 bit 0 (0 = horizontal,
+    /// 1 = vertical).
+    pub fn new(prg_rom: Vec<u8>, chr_rom: Vec<u8>, chr_is_ram: bool, mirroring: Mirroring) -> Self {
         Self {
             prg_rom,
             chr_rom,
+            chr_is_ram,
             mirroring,
         }
     }
 }
 
 impl Mapper for Mapper0 {
+    fn clone_box(&self) -> Box<dyn Mapper> {
+        Box::new(self.clone())
+    }
+
+    fn name(&self) -> &'static str {
+        "NROM"
+    }
+
     fn read(&self, addr: u16) -> u8 {
         match addr {
             // PRG: $8000–$FFFF. If only 16 KiB, $8000–$BFFF and $C000–$FFFF mirror same bank.
@@ -39,18 +51,17 @@ impl Mapper for Mapper0 {
                 self.prg_rom[addr]
             }
             // CHR: $0000–$1FFF (PPU pattern tables)
-            0x0000..=0x1FFF => self.chr_rom[addr as usize],
+            0x0000..=0x1FFF => self.chr_rom[addr as usize % self.chr_rom.len()],
             _ => 0,
         }
     }
 
     fn write(&mut self, addr: u16, data: u8) {
         match addr {
-            // CHR: only writable if cartridge has CHR RAM (we treat 8 KiB buffer as RAM when size is 8 KiB)
-            0x0000..=0x1FFF => {
-                if self.chr_rom.len() == 8 * 1024 {
-                    self.chr_rom[addr as usize] = data;
-                }
+            // CHR: only writable if the cartridge has CHR RAM
+            0x0000..=0x1FFF if self.chr_is_ram => {
+                let i = addr as usize % self.chr_rom.len();
+                self.chr_rom[i] = data;
             }
             0x8000..=0xFFFF => {} // PRG ROM is read-only
             _ => {}
@@ -61,4 +72,14 @@ impl Mapper for Mapper0 {
     fn mirroring(&mut self) -> Mirroring {
         self.mirroring
     }
+
+    /// 16 KiB bank: 0 for $8000–$BFFF, 1 for $C000–$FFFF (0 for both with 16 KiB PRG).
+    fn prg_bank_at(&self, addr: u16) -> Option<usize> {
+        let banks = (self.prg_rom.len() / 0x4000).max(1);
+        (addr >= 0x8000).then(|| ((addr as usize - 0x8000) / 0x4000) % banks)
+    }
+
+    fn chr_bank_at(&self, addr: u16) -> Option<usize> {
+        (addr < 0x2000).then_some(0)
+    }
 }
@@ -3,29 +3,58 @@
 //! [MMC1](https://www.nesdev.org/wiki/MMC1): writes to $8000–$9FFF (control), $A000–$BFFF (CHR0),
 //! $C000–$DFFF (CHR1), $E000–$FFFF (PRG bank). Any write with bit 7 set resets the shift register.
 //! Otherwise, bit 0 is shifted in (LSB first); after 5 writes, the value is latched to the selected
-//! register. Control (bits 0–1) = mirroring; bits 2–3 = PRG mode; bit 4 = CHR mode. We implement
-//! PRG banking only; CHR banking is omitted (single bank or RAM as needed).
+//! register. Control (bits 0–1) = mirroring; bits 2–3 = PRG mode; bit 4 = CHR mode (0 = one 8 KiB
+//! bank selected by CHR0 with bit 0 ignored, 1 = two 4 KiB banks from CHR0 and CHR1). CHR RAM
+//! (SNROM, SUROM) is banked the same way as ROM. 8 KiB PRG RAM at $6000–$7FFF is always present
+//! (SNROM-style boards; blargg test ROMs report there) and enabled while PRG register bit 4 is
+//! clear (MMC1B and later); disabled, reads are open bus and writes are dropped. A register write on the cycle
+//! right after another is ignored, so the double write of read-modify-write instructions only
+//! counts once (games reset the MMC1 with `INC $FFFF`).
+//!
+//! SUROM (512 KiB PRG, Dragon Warrior III/IV) has only 8 KiB CHR RAM, so it reuses CHR0 bit 4 as
+//! PRG address line 18: the bank modes above then apply within the selected 256 KiB half,
+//! including the fixed last bank. CHR1 bit 4 is not consulted.
 
 use crate::cartridge::mapper::{Mirroring, mapper::Mapper};
 
-/// MMC1 state: 5-bit shift register, control byte (mirroring + PRG/CHR mode), PRG bank select.
+/// MMC1 state: 5-bit shift register, control byte (mirroring + PRG/CHR mode), PRG and CHR bank
+/// selects.
+#[derive(Clone)]
 pub struct Mapper1 {
     prg_rom: Vec<u8>,
+    chr: Vec<u8>,
+    chr_is_ram: bool,
+    prg_ram: Vec<u8>,
     shift_reg: u8,
     shift_count: u8,
     control: u8,
+    chr_bank0: u8,
+    chr_bank1: u8,
     prg_bank: u8,
+    /// PRG register bit 4: PRG RAM disabled.
+    prg_ram_disabled: bool,
+    /// A register write happened and the CPU hasn't been clocked since; the CPU only writes twice
+    /// without a clock in between for read-modify-write instructions.
+    wrote_since_clock: bool,
 }
 
 impl Mapper1 {
-    /// Create MMC1 with PRG ROM. Control defaults to $0C (PRG mode 3: $8000 switchable, $C000 fixed last).
-    pub fn new(prg_rom: Vec<u8>) -> Self {
+    /// Create MMC1 with PRG ROM and CHR (RAM if `chr_is_ram`). Control defaults to $0C (PRG mode 3:
+    /// $8000 switchable, $C000 fixed last).
+    pub fn new(prg_rom: Vec<u8>, chr: Vec<u8>, chr_is_ram: bool) -> Self {
         Self {
             prg_rom,
+            chr,
+            chr_is_ram,
+            prg_ram: vec![0; 8 * 1024],
             shift_reg: 0,
             shift_count: 0,
             control: 0x0C,
+            chr_bank0: 0,
+            chr_bank1: 0,
             prg_bank: 0,
+            prg_ram_disabled: false,
+            wrote_since_clock: false,
         }
     }
 
@@ -37,49 +66,89 @@ impl Mapper1 {
     fn prg_bank_count(&self) -> usize {
         self.prg_rom.len() / 0x4000
     }
+
+    /// 16 KiB PRG bank mapped at `addr` ($8000–$FFFF) for the current bank mode. The bank modes
+    /// work within one 256 KiB half; on 512 KiB boards CHR0 bit 4 picks the half.
+    fn prg_bank(&self, addr: u16) -> usize {
+        let upper = addr >= 0xC000;
+        let half_banks = self.prg_bank_count().clamp(1, 16);
+        let bank = match self.prg_bank_mode() {
+            // 32 KiB mode: low bit of the bank number is ignored.
+            0 | 1 => (self.prg_bank & !1) as usize + upper as usize,
+            2 if upper => self.prg_bank as usize,
+            2 => 0,
+            _ if upper => half_banks - 1,
+            _ => self.prg_bank as usize,
+        };
+        let outer = if self.prg_bank_count() > 16 {
+            (self.chr_bank0 & 0x10) as usize
+        } else {
+            0
+        };
+        (outer + bank % half_banks) % self.prg_bank_count().max(1)
+    }
+
+    /// 4 KiB CHR bank mapped at `addr` ($0000–$1FFF) for the current CHR mode.
+    fn chr_bank(&self, addr: u16) -> usize {
+        let upper = addr >= 0x1000;
+        let bank = if self.control & 0x10 == 0 {
+            // 8 KiB mode: low bit of CHR0 is ignored, CHR1 is unused.
+            (self.chr_bank0 & !1) as usize + upper as usize
+        } else if upper {
+            self.chr_bank1 as usize
+        } else {
+            self.chr_bank0 as usize
+        };
+        bank % (self.chr.len() / 0x1000).max(1)
+    }
+
+    fn chr_offset(&self, addr: u16) -> usize {
+        (self.chr_bank(addr) * 0x1000 + (addr as usize & 0x0FFF)) % self.chr.len()
+    }
 }
 
 impl Mapper for Mapper1 {
+    fn clone_box(&self) -> Box<dyn Mapper> {
+        Box::new(self.clone())
+    }
+
+    fn name(&self) -> &'static str {
+        "MMC1"
+    }
+
     fn read(&self, addr: u16) -> u8 {
         match addr {
+            0x0000..=0x1FFF => self.chr[self.chr_offset(addr)],
+            // Disabled PRG RAM leaves the bus floating at the address high byte.
+            0x6000..=0x7FFF if self.prg_ram_disabled => (addr >> 8) as u8,
+            0x6000..=0x7FFF => self.prg_ram[(addr - 0x6000) as usize],
             // PRG: bank mode and prg_bank select which 16 KiB bank(s) appear at $8000 and $C000.
             0x8000..=0xFFFF => {
-                let bank_mode = self.prg_bank_mode();
-                let bank_count = self.prg_bank_count();
-
-                let addr = addr as usize;
-
-                match bank_mode {
-                    0 | 1 => {
-                        let bank = (self.prg_bank & !1) as usize;
-                        let offset = addr - 0x8000;
-                        self.prg_rom[(bank * 0x4000 * 2) + offset]
-                    }
-                    2 => {
-                        if addr < 0xC000 {
-                            self.prg_rom[addr - 0x8000]
-                        } else {
-                            let bank = self.prg_bank as usize;
-                            self.prg_rom[(bank * 0x4000) + (addr - 0xC000)]
-                        }
-                    }
-                    3 => {
-                        if addr < 0xC000 {
-                            let bank = self.prg_bank as usize;
-                            self.prg_rom[(bank * 0x4000) + (addr - 0x8000)]
-                        } else {
-                            let bank = bank_count - 1;
-                            self.prg_rom[(bank * 0x4000) + (addr - 0xC000)]
-                        }
-                    }
-                    _ => 0,
-                }
+                let bank = self.prg_bank(addr);
+                self.prg_rom[bank * 0x4000 + (addr as usize & 0x3FFF)]
             }
             _ => 0,
         }
     }
 
     fn write(&mut self, addr: u16, data: u8) {
+        if addr < 0x2000 {
+            if self.chr_is_ram {
+                let i = self.chr_offset(addr);
+                self.chr[i] = data;
+            }
+            return;
+        }
+        if (0x6000..=0x7FFF).contains(&addr) {
+            if !self.prg_ram_disabled {
+                self.prg_ram[(addr - 0x6000) as usize] = data;
+            }
+            return;
+        }
+        if addr < 0x8000 || std::mem::replace(&mut self.wrote_since_clock, true) {
+            return;
+        }
+
         // MMC1: write with bit 7 set resets shift register (and often control to $0C).
         if data & 0x80 != 0 {
             self.shift_reg = 0;
@@ -99,8 +168,12 @@ impl Mapper for Mapper1 {
 
         match addr {
             0x8000..=0x9FFF => self.control = self.shift_reg & 0x1F, // Control: mirroring, PRG/CHR mode
-            0xE000..=0xFFFF => self.prg_bank = self.shift_reg & 0x0F, // PRG bank (4-bit)
-            _ => {} // CHR0/CHR1 ($A000–$BFFF, $C000–$DFFF) not implemented
+            0xA000..=0xBFFF => self.chr_bank0 = self.shift_reg & 0x1F, // CHR0 (4 KiB units)
+            0xC000..=0xDFFF => self.chr_bank1 = self.shift_reg & 0x1F, // CHR1 (4 KiB units)
+            _ => {
+                self.prg_bank = self.shift_reg & 0x0F; // PRG bank (4-bit)
+                self.prg_ram_disabled = self.shift_reg & 0x10 != 0;
+            }
         }
 
         self.shift_reg = 0;
@@ -117,4 +190,68 @@ impl Mapper for Mapper1 {
             _ => unreachable!(),
         }
     }
+
+    fn cpu_clock(&mut self, _cycles: usize) {
+        self.wrote_since_clock = false;
+    }
+
+    fn prg_bank_at(&self, addr: u16) -> Option<usize> {
+        (addr >= 0x8000).then(|| self.prg_bank(addr))
+    }
+
+    /// 4 KiB units.
+    fn chr_bank_at(&self, addr: u16) -> Option<usize> {
+        (addr < 0x2000).then(|| self.chr_bank(addr))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `count` banks of `size` bytes, each filled with its bank number.
+    fn banks(count: u8, size: usize) -> Vec<u8> {
+        (0..count).flat_map(|bank| vec![bank; size]).collect()
+    }
+
+    /// Load `value` into the register at `addr` with five serial writes, one per CPU cycle.
+    fn write_register(m: &mut Mapper1, addr: u16, value: u8) {
+        for bit in 0..5 {
+            m.write(addr, value >> bit & 1);
+            m.cpu_clock(1);
+        }
+    }
+
+    #[test]
+    fn chr_banks_switch_in_8k_and_4k_modes() {
+        let mut m = Mapper1::new(banks(8, 0x4000), banks(8, 0x1000), false);
+        write_register(&mut m, 0xA000, 3); // 8 KiB mode ignores bit 0
+        assert_eq!((m.read(0x0000), m.read(0x1000)), (2, 3));
+        write_register(&mut m, 0x8000, 0x1C); // 4 KiB mode
+        write_register(&mut m, 0xA000, 5);
+        write_register(&mut m, 0xC000, 2);
+        assert_eq!((m.read(0x0FFF), m.read(0x1000)), (5, 2));
+        assert_eq!(m.chr_bank_at(0x1FFF), Some(2));
+    }
+
+    #[test]
+    fn surom_chr0_bit_4_selects_the_prg_half() {
+        let mut m = Mapper1::new(banks(32, 0x4000), vec![0; 0x2000], true);
+        write_register(&mut m, 0xE000, 1);
+        assert_eq!((m.read(0x8000), m.read(0xC000)), (1, 15));
+        write_register(&mut m, 0xA000, 0x10);
+        assert_eq!((m.read(0x8000), m.read(0xC000)), (17, 31));
+    }
+
+    #[test]
+    fn prg_ram_survives_bank_switches_and_honours_the_disable_bit() {
+        let mut m = Mapper1::new(banks(8, 0x4000), vec![0; 0x2000], true);
+        m.write(0x6000, 0x42);
+        write_register(&mut m, 0xE000, 3);
+        assert_eq!((m.read(0x8000), m.read(0x6000)), (3, 0x42));
+        write_register(&mut m, 0xE000, 0x13); // bit 4: PRG RAM off
+        m.write(0x6000, 0x99);
+        write_register(&mut m, 0xE000, 0x03);
+        assert_eq!(m.read(0x6000), 0x42);
+    }
 }
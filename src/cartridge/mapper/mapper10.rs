@@ -0,0 +1,140 @@
+//! Mapper 10 (MMC4): 16 KiB PRG switching and latch-driven 4 KiB CHR switching.
+//!
+//! [MMC4](https://www.nesdev.org/wiki/MMC4) (Fire Emblem, Famicom Wars):
+//!
+//! - $6000–$7FFF: 8 KiB PRG RAM.
+//! - $8000–$BFFF: switchable 16 KiB PRG bank ($A000); $C000–$FFFF fixed to the last bank.
+//! - $0000 / $1000: 4 KiB CHR banks chosen by the FD/FE latches (see `chr_latch`); registers
+//!   $B000/$C000 (window 0, FD/FE) and $D000/$E000 (window 1, FD/FE).
+//! - $F000: mirroring (bit 0: 0 = vertical, 1 = horizontal).
+
+use crate::cartridge::mapper::chr_latch::ChrLatches;
+use crate::cartridge::mapper::{Mirroring, mapper::Mapper};
+
+/// MMC4 state: PRG bank, CHR latches, mirroring, PRG RAM.
+#[derive(Clone)]
+pub struct Mapper10 {
+    prg_rom: Vec<u8>,
+    chr_rom: Vec<u8>,
+    prg_ram: Vec<u8>,
+    prg_bank: usize,
+    chr: ChrLatches,
+    mirroring: Mirroring,
+}
+
+impl Mapper10 {
+    /// Create MMC4 with PRG, CHR ROM and the header's mirroring (until $F000 is written).
+    pub fn new(prg_rom: Vec<u8>, chr_rom: Vec<u8>, mirroring: Mirroring) -> Self {
+        Self {
+            prg_rom,
+            chr_rom,
+            prg_ram: vec![0; 0x2000],
+            prg_bank: 0,
+            chr: ChrLatches::new(false),
+            mirroring,
+        }
+    }
+
+    fn prg_bank_count(&self) -> usize {
+        (self.prg_rom.len() / 0x4000).max(1)
+    }
+
+    /// 16 KiB PRG bank at `addr` ($8000–$FFFF).
+    fn prg_bank(&self, addr: u16) -> usize {
+        if addr < 0xC000 {
+            self.prg_bank % self.prg_bank_count()
+        } else {
+            self.prg_bank_count() - 1
+        }
+    }
+}
+
+impl Mapper for Mapper10 {
+    fn clone_box(&self) -> Box<dyn Mapper> {
+        Box::new(self.clone())
+    }
+
+    fn name(&self) -> &'static str {
+        "MMC4"
+    }
+
+    fn read(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x1FFF => {
+                let offset = self.chr.bank(addr) * 0x1000 + (addr as usize & 0x0FFF);
+                self.chr_rom[offset % self.chr_rom.len()]
+            }
+            0x6000..=0x7FFF => self.prg_ram[addr as usize - 0x6000],
+            0x8000..=0xFFFF => {
+                self.prg_rom[self.prg_bank(addr) * 0x4000 + (addr as usize & 0x3FFF)]
+            }
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, addr: u16, data: u8) {
+        match addr {
+            0x6000..=0x7FFF => self.prg_ram[addr as usize - 0x6000] = data,
+            0xA000..=0xAFFF => self.prg_bank = (data & 0x0F) as usize,
+            0xB000..=0xBFFF => self.chr.banks[0][0] = (data & 0x1F) as usize,
+            0xC000..=0xCFFF => self.chr.banks[0][1] = (data & 0x1F) as usize,
+            0xD000..=0xDFFF => self.chr.banks[1][0] = (data & 0x1F) as usize,
+            0xE000..=0xEFFF => self.chr.banks[1][1] = (data & 0x1F) as usize,
+            0xF000..=0xFFFF => {
+                self.mirroring = if data & 1 != 0 {
+                    Mirroring::Horizontal
+                } else {
+                    Mirroring::Vertical
+                };
+            }
+            _ => {}
+        }
+    }
+
+    fn mirroring(&mut self) -> Mirroring {
+        self.mirroring
+    }
+
+    /// The fetch that hits $FD/$FE uses the old bank; the latch flips afterwards.
+    fn on_chr_access(&mut self, addr: u16, _ppu_dot: u64) {
+        self.chr.on_fetch(addr);
+    }
+
+    /// 16 KiB units.
+    fn prg_bank_at(&self, addr: u16) -> Option<usize> {
+        (addr >= 0x8000).then(|| self.prg_bank(addr))
+    }
+
+    /// 4 KiB units.
+    fn chr_bank_at(&self, addr: u16) -> Option<usize> {
+        (addr < 0x2000).then(|| self.chr.bank(addr))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `count` banks of `size` bytes, each filled with its bank number.
+    fn banks(count: u8, size: usize) -> Vec<u8> {
+        (0..count).flat_map(|bank| vec![bank; size]).collect()
+    }
+
+    #[test]
+    fn latch_switches_chr_and_a000_switches_16k_prg() {
+        let mut m = Mapper10::new(banks(8, 0x4000), banks(8, 0x1000), Mirroring::Vertical);
+        m.write(0xA000, 3);
+        assert_eq!((m.read(0x8000), m.read(0xBFFF)), (3, 3));
+        assert_eq!(m.read(0xC000), 7);
+
+        m.write(0xB000, 4); // $0000 window, latch $FD
+        m.write(0xC000, 5); // $0000 window, latch $FE (power-on)
+        assert_eq!(m.read(0x0000), 5);
+        // MMC4 reacts to all of $0FD8–$0FDF; the triggering fetch still sees the old bank.
+        assert_eq!(m.read(0x0FDB), 5);
+        m.on_chr_access(0x0FDB, 0);
+        assert_eq!(m.read(0x0000), 4);
+        m.on_chr_access(0x0FE8, 0);
+        assert_eq!(m.read(0x0000), 5);
+    }
+}
@@ -0,0 +1,133 @@
+//! Mapper 11 (Color Dreams): 32 KiB PRG and 8 KiB CHR switching from one register.
+//!
+//! [Color Dreams](https://www.nesdev.org/wiki/Color_Dreams): any write to $8000–$FFFF selects the
+//! 32 KiB PRG bank (bits 0–3; the original boards wire bits 0–1) and the 8 KiB CHR ROM bank
+//! (bits 4–7). Mirroring is fixed by the board. Used by unlicensed Color Dreams / Wisdom Tree carts.
+//!
+//! The register sits on the ROM's data bus, so a write can suffer a
+//! [bus conflict](https://www.nesdev.org/wiki/Bus_conflict): the ROM drives the byte at the
+//! written address and the register latches `data & rom`. Off by default (games avoid conflicts
+//! by writing to a ROM byte holding the same value); enable with `set_bus_conflicts`. NES 2.0
+//! headers with submapper 2 turn it on.
+
+use crate::cartridge::mapper::{Mirroring, mapper::Mapper};
+
+/// Color Dreams: register-selected 32 KiB PRG and 8 KiB CHR bank.
+#[derive(Clone)]
+pub struct Mapper11 {
+    prg_rom: Vec<u8>,
+    chr_rom: Vec<u8>,
+    mirroring: Mirroring,
+    prg_bank: usize,
+    chr_bank: usize,
+    /// AND register writes with the ROM byte at the written address.
+    bus_conflicts: bool,
+}
+
+impl Mapper11 {
+    /// Create Color Dreams with PRG, CHR ROM and the header's mirroring. Powers on in bank 0.
+    pub fn new(prg_rom: Vec<u8>, chr_rom: Vec<u8>, mirroring: Mirroring) -> Self {
+        Self {
+            prg_rom,
+            chr_rom,
+            mirroring,
+            prg_bank: 0,
+            chr_bank: 0,
+            bus_conflicts: false,
+        }
+    }
+
+    /// Emulate bus conflicts on register writes (default off).
+    pub fn set_bus_conflicts(&mut self, enabled: bool) {
+        self.bus_conflicts = enabled;
+    }
+
+    fn prg_banks(&self) -> usize {
+        (self.prg_rom.len() / 0x8000).max(1)
+    }
+
+    fn chr_banks(&self) -> usize {
+        (self.chr_rom.len() / 0x2000).max(1)
+    }
+}
+
+impl Mapper for Mapper11 {
+    fn clone_box(&self) -> Box<dyn Mapper> {
+        Box::new(self.clone())
+    }
+
+    fn name(&self) -> &'static str {
+        "Color Dreams"
+    }
+
+    fn read(&self, addr: u16) -> u8 {
+        match addr {
+            0x8000..=0xFFFF => {
+                let offset = self.prg_bank * 0x8000 + (addr as usize - 0x8000);
+                self.prg_rom[offset % self.prg_rom.len()]
+            }
+            0x0000..=0x1FFF => {
+                let offset = self.chr_bank * 0x2000 + addr as usize;
+                self.chr_rom[offset % self.chr_rom.len()]
+            }
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, addr: u16, data: u8) {
+        if addr >= 0x8000 {
+            let data = if self.bus_conflicts { data & self.read(addr) } else { data };
+            self.prg_bank = (data & 0x0F) as usize % self.prg_banks();
+            self.chr_bank = (data >> 4) as usize % self.chr_banks();
+        }
+    }
+
+    fn mirroring(&mut self) -> Mirroring {
+        self.mirroring
+    }
+
+    /// 16 KiB units, like NROM/MMC1: the 32 KiB bank covers two consecutive ones.
+    fn prg_bank_at(&self, addr: u16) -> Option<usize> {
+        (addr >= 0x8000).then(|| self.prg_bank * 2 + (addr as usize - 0x8000) / 0x4000)
+    }
+
+    /// 8 KiB units.
+    fn chr_bank_at(&self, addr: u16) -> Option<usize> {
+        (addr < 0x2000).then_some(self.chr_bank)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `count` banks of `size` bytes, each filled with its bank number.
+    fn banks(count: u8, size: usize) -> Vec<u8> {
+        (0..count).flat_map(|bank| vec![bank; size]).collect()
+    }
+
+    #[test]
+    fn register_switches_prg_and_chr_banks() {
+        let mut m = Mapper11::new(banks(4, 0x8000), banks(4, 0x2000), Mirroring::Vertical);
+        assert_eq!((m.read(0x8000), m.read(0x0000)), (0, 0));
+        m.write(0x8000, 0x32);
+        assert_eq!((m.read(0x8000), m.read(0xFFFF)), (2, 2));
+        assert_eq!((m.read(0x0000), m.read(0x1FFF)), (3, 3));
+        assert_eq!(m.prg_bank_at(0xC000), Some(5));
+        assert_eq!(m.chr_bank_at(0x0000), Some(3));
+    }
+
+    #[test]
+    fn bus_conflicts_and_the_written_value_with_the_rom_byte() {
+        let mut m = Mapper11::new(banks(8, 0x8000), banks(4, 0x2000), Mirroring::Vertical);
+        m.write(0x8000, 0x25);
+        assert_eq!((m.read(0x8000), m.read(0x0000)), (5, 2));
+        m.set_bus_conflicts(true);
+        // The ROM under $8000 now holds 5: $36 & 5 = 4 (PRG 4, CHR 0).
+        m.write(0x8000, 0x36);
+        assert_eq!((m.read(0x8000), m.read(0x0000)), (4, 0));
+        m.set_bus_conflicts(false);
+        m.write(0x8000, 0x36);
+        assert_eq!((m.read(0x8000), m.read(0x0000)), (6, 3));
+    }
+}
@@ -0,0 +1,259 @@
+//! Mapper 19 (Namco 163): 8 KiB PRG / 1 KiB CHR banking, banked nametables, CPU-cycle IRQ.
+//!
+//! [Namco 163](https://www.nesdev.org/wiki/Namco_163) (Rolling Thunder, Megami Tensei II):
+//!
+//! - $4800: data port for the chip's 128 bytes of internal RAM (address and auto-increment set
+//!   through $F800).
+//! - $5000 / $5800: IRQ counter low 8 bits / high 7 bits + enable (bit 7). The 15-bit counter
+//!   counts up every CPU cycle while enabled and raises the IRQ on reaching $7FFF. Writing either
+//!   register acknowledges the IRQ.
+//! - $8000–$B800 (every $800): 1 KiB CHR banks for $0000–$1FFF.
+//! - $C000–$D800 (every $800): nametable banks for $2000–$2C00. Values $E0–$FF pick a page of
+//!   console nametable RAM (bit 0); lower values a 1 KiB CHR ROM page (read-only).
+//! - $E000 / $E800 / $F000: 8 KiB PRG banks at $8000 / $A000 / $C000 (bits 0–5); $E000 fixed to
+//!   the last bank.
+//! - $F800: internal RAM address (bits 0–6) and auto-increment (bit 7); also PRG RAM write
+//!   protect (writes to $6000–$7FFF need bits 4–7 = $4 and the 2 KiB chunk's bit 0–3 clear).
+//!
+//! Because any quadrant can point at either page, this mapper holds the nametable RAM itself
+//! and answers every nametable access. Not emulated yet: the 8-channel wavetable audio (the
+//! internal RAM is kept, so a later audio stage can read the waveforms) and CHR banks $E0–$FF
+//! mapping nametable RAM into pattern space.
+
+use crate::cartridge::mapper::{Mirroring, mapper::Mapper};
+
+/// Namco 163 state.
+#[derive(Clone)]
+pub struct Mapper19 {
+    prg_rom: Vec<u8>,
+    chr_rom: Vec<u8>,
+    prg_ram: Vec<u8>,
+    /// 2 KiB nametable RAM (stands in for the console's CIRAM).
+    ciram: Vec<u8>,
+    /// Internal 128-byte RAM (sound registers and waveforms).
+    internal_ram: [u8; 128],
+    /// $F800: internal RAM address / auto-increment / PRG RAM write protect.
+    address_port: u8,
+    /// 1 KiB CHR banks for $0000–$1FFF.
+    chr_banks: [u8; 8],
+    /// Nametable banks for $2000, $2400, $2800, $2C00.
+    nt_banks: [u8; 4],
+    /// 8 KiB PRG banks for $8000, $A000, $C000.
+    prg_banks: [u8; 3],
+    /// 15-bit IRQ counter.
+    irq_counter: u16,
+    irq_enabled: bool,
+    irq_pending: bool,
+}
+
+impl Mapper19 {
+    /// Create Namco 163. `mirroring` seeds the nametable banks until the game writes them.
+    pub fn new(prg_rom: Vec<u8>, chr_rom: Vec<u8>, mirroring: Mirroring) -> Self {
+        let nt_banks = match mirroring {
+            Mirroring::Vertical | Mirroring::FourScreen => [0xE0, 0xE1, 0xE0, 0xE1],
+            Mirroring::Horizontal => [0xE0, 0xE0, 0xE1, 0xE1],
+            Mirroring::OneScreenLower => [0xE0; 4],
+            Mirroring::OneScreenUpper => [0xE1; 4],
+        };
+        Self {
+            prg_rom,
+            chr_rom,
+            prg_ram: vec![0; 0x2000],
+            ciram: vec![0; 0x800],
+            internal_ram: [0; 128],
+            address_port: 0,
+            chr_banks: [0; 8],
+            nt_banks,
+            prg_banks: [0; 3],
+            irq_counter: 0,
+            irq_enabled: false,
+            irq_pending: false,
+        }
+    }
+
+    fn prg_bank_count(&self) -> usize {
+        (self.prg_rom.len() / 0x2000).max(1)
+    }
+
+    /// 8 KiB PRG bank at `addr` ($8000–$FFFF).
+    fn prg_bank(&self, addr: u16) -> usize {
+        match (addr - 0x8000) >> 13 {
+            slot @ 0..=2 => (self.prg_banks[slot as usize] & 0x3F) as usize % self.prg_bank_count(),
+            _ => self.prg_bank_count() - 1,
+        }
+    }
+
+    fn chr_rom_byte(&self, page: u8, offset: usize) -> u8 {
+        if self.chr_rom.is_empty() {
+            return 0;
+        }
+        self.chr_rom[(page as usize * 0x400 + offset) % self.chr_rom.len()]
+    }
+
+    fn prg_ram_writable(&self, addr: u16) -> bool {
+        let chunk = (addr as usize - 0x6000) >> 11;
+        self.address_port & 0xF0 == 0x40 && self.address_port & (1 << chunk) == 0
+    }
+
+    /// Internal RAM access through $4800: returns the address used and advances it if auto-
+    /// increment is on.
+    fn internal_ram_addr(&mut self) -> usize {
+        let addr = (self.address_port & 0x7F) as usize;
+        if self.address_port & 0x80 != 0 {
+            self.address_port = 0x80 | (self.address_port.wrapping_add(1) & 0x7F);
+        }
+        addr
+    }
+}
+
+impl Mapper for Mapper19 {
+    fn clone_box(&self) -> Box<dyn Mapper> {
+        Box::new(self.clone())
+    }
+
+    fn name(&self) -> &'static str {
+        "Namco 163"
+    }
+
+    fn read(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x1FFF => {
+                self.chr_rom_byte(self.chr_banks[addr as usize >> 10], addr as usize & 0x3FF)
+            }
+            0x6000..=0x7FFF => self.prg_ram[addr as usize - 0x6000],
+            0x8000..=0xFFFF => {
+                self.prg_rom[self.prg_bank(addr) * 0x2000 + (addr as usize & 0x1FFF)]
+            }
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, addr: u16, data: u8) {
+        match addr {
+            0x4800..=0x4FFF => {
+                let i = self.internal_ram_addr();
+                self.internal_ram[i] = data;
+            }
+            0x5000..=0x57FF => {
+                self.irq_counter = (self.irq_counter & 0x7F00) | data as u16;
+                self.irq_pending = false;
+            }
+            0x5800..=0x5FFF => {
+                self.irq_counter = (self.irq_counter & 0x00FF) | ((data & 0x7F) as u16) << 8;
+                self.irq_enabled = data & 0x80 != 0;
+                self.irq_pending = false;
+            }
+            0x6000..=0x7FFF if self.prg_ram_writable(addr) => {
+                self.prg_ram[addr as usize - 0x6000] = data;
+            }
+            0x8000..=0xBFFF => self.chr_banks[(addr as usize - 0x8000) >> 11] = data,
+            0xC000..=0xDFFF => self.nt_banks[(addr as usize - 0xC000) >> 11] = data,
+            0xE000..=0xE7FF => self.prg_banks[0] = data,
+            0xE800..=0xEFFF => self.prg_banks[1] = data,
+            0xF000..=0xF7FF => self.prg_banks[2] = data,
+            0xF800..=0xFFFF => self.address_port = data,
+            _ => {}
+        }
+    }
+
+    /// Unused: every nametable access goes through `ppu_nametable_read`/`write`.
+    fn mirroring(&mut self) -> Mirroring {
+        Mirroring::Vertical
+    }
+
+    fn expansion_read(&mut self, addr: u16) -> Option<u8> {
+        match addr {
+            0x4800..=0x4FFF => {
+                let i = self.internal_ram_addr();
+                Some(self.internal_ram[i])
+            }
+            0x5000..=0x57FF => Some(self.irq_counter as u8),
+            0x5800..=0x5FFF => {
+                Some((self.irq_counter >> 8) as u8 | if self.irq_enabled { 0x80 } else { 0 })
+            }
+            _ => None,
+        }
+    }
+
+    fn ppu_nametable_read(&mut self, addr: u16) -> Option<u8> {
+        let bank = self.nt_banks[(addr as usize >> 10) & 3];
+        let offset = addr as usize & 0x3FF;
+        Some(if bank >= 0xE0 {
+            self.ciram[(bank as usize & 1) * 0x400 + offset]
+        } else {
+            self.chr_rom_byte(bank, offset)
+        })
+    }
+
+    fn ppu_nametable_write(&mut self, addr: u16, data: u8) -> bool {
+        let bank = self.nt_banks[(addr as usize >> 10) & 3];
+        if bank >= 0xE0 {
+            self.ciram[(bank as usize & 1) * 0x400 + (addr as usize & 0x3FF)] = data;
+        }
+        true
+    }
+
+    fn cpu_clock(&mut self, cycles: usize) {
+        if !self.irq_enabled || self.irq_counter == 0x7FFF {
+            return;
+        }
+        self.irq_counter = (self.irq_counter as usize + cycles).min(0x7FFF) as u16;
+        if self.irq_counter == 0x7FFF {
+            self.irq_pending = true;
+        }
+    }
+
+    fn poll_irq(&mut self) -> bool {
+        self.irq_pending
+    }
+
+    /// 8 KiB units.
+    fn prg_bank_at(&self, addr: u16) -> Option<usize> {
+        (addr >= 0x8000).then(|| self.prg_bank(addr))
+    }
+
+    /// 1 KiB units.
+    fn chr_bank_at(&self, addr: u16) -> Option<usize> {
+        (addr < 0x2000).then(|| self.chr_banks[addr as usize >> 10] as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `count` banks of `size` bytes, each filled with its bank number.
+    fn banks(count: u8, size: usize) -> Vec<u8> {
+        (0..count).flat_map(|bank| vec![bank; size]).collect()
+    }
+
+    #[test]
+    fn switches_8k_prg_and_1k_chr_banks() {
+        let mut m = Mapper19::new(banks(8, 0x2000), banks(16, 0x400), Mirroring::Vertical);
+        m.write(0xE000, 3);
+        m.write(0xE800, 0x45); // bits 6-7 ignored
+        m.write(0xF000, 1);
+        m.write(0x8000, 9);
+        m.write(0xB800, 12);
+        assert_eq!((m.read(0x8000), m.read(0xA000), m.read(0xC000)), (3, 5, 1));
+        assert_eq!(m.read(0xE000), 7);
+        assert_eq!((m.read(0x0000), m.read(0x1C00)), (9, 12));
+        assert_eq!(m.prg_bank_at(0xA000), Some(5));
+        assert_eq!(m.chr_bank_at(0x1FFF), Some(12));
+    }
+
+    #[test]
+    fn irq_fires_when_counter_reaches_7fff_and_write_acknowledges() {
+        let mut m = Mapper19::new(banks(2, 0x2000), banks(8, 0x400), Mirroring::Vertical);
+        m.write(0x5000, 0xFD);
+        m.write(0x5800, 0xFF); // high bits $7F, enable
+        m.cpu_clock(1);
+        assert!(!m.poll_irq());
+        assert_eq!(m.expansion_read(0x5000), Some(0xFE));
+        m.cpu_clock(5);
+        assert!(m.poll_irq());
+        assert_eq!(m.expansion_read(0x5800), Some(0xFF));
+        m.write(0x5000, 0);
+        assert!(!m.poll_irq());
+    }
+}
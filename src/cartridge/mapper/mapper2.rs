@@ -0,0 +1,126 @@
+//! Mapper 2 (UxROM): 16 KiB switchable PRG bank plus a fixed last bank.
+//!
+//! [UxROM](https://www.nesdev.org/wiki/UxROM) (UNROM, UOROM): any write to $8000–$FFFF selects the
+//! 16 KiB bank at $8000–$BFFF; $C000–$FFFF is fixed to the last bank. 8 KiB CHR RAM; mirroring is
+//! fixed by the board. Used by Mega Man, Castlevania, Contra and DuckTales.
+//!
+//! The register sits on the ROM's data bus, so a write can suffer a
+//! [bus conflict](https://www.nesdev.org/wiki/Bus_conflict) (the register latches `data & rom`).
+//! Off by default; the loader enables it for NES 2.0 submapper 2.
+
+use crate::cartridge::mapper::{Mirroring, mapper::Mapper};
+
+/// UxROM: one register selecting the low 16 KiB PRG bank.
+#[derive(Clone)]
+pub struct Mapper2 {
+    prg_rom: Vec<u8>,
+    /// 8 KiB CHR RAM (or CHR ROM on the odd board that has it).
+    chr: Vec<u8>,
+    chr_is_ram: bool,
+    mirroring: Mirroring,
+    prg_bank: usize,
+    /// AND register writes with the ROM byte at the written address.
+    bus_conflicts: bool,
+}
+
+impl Mapper2 {
+    /// Create UxROM with the header's mirroring. `chr_is_ram` makes CHR writable. Powers on in
+    /// bank 0.
+    pub fn new(prg_rom: Vec<u8>, chr: Vec<u8>, chr_is_ram: bool, mirroring: Mirroring) -> Self {
+        Self {
+            prg_rom,
+            chr,
+            chr_is_ram,
+            mirroring,
+            prg_bank: 0,
+            bus_conflicts: false,
+        }
+    }
+
+    /// Emulate bus conflicts on register writes (default off).
+    pub fn set_bus_conflicts(&mut self, enabled: bool) {
+        self.bus_conflicts = enabled;
+    }
+
+    fn prg_banks(&self) -> usize {
+        (self.prg_rom.len() / 0x4000).max(1)
+    }
+
+    /// 16 KiB PRG bank at `addr` ($8000–$FFFF).
+    fn prg_bank(&self, addr: u16) -> usize {
+        if addr < 0xC000 {
+            self.prg_bank
+        } else {
+            self.prg_banks() - 1
+        }
+    }
+}
+
+impl Mapper for Mapper2 {
+    fn clone_box(&self) -> Box<dyn Mapper> {
+        Box::new(self.clone())
+    }
+
+    fn name(&self) -> &'static str {
+        "UxROM"
+    }
+
+    fn read(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x1FFF => self.chr[addr as usize % self.chr.len()],
+            0x8000..=0xFFFF => {
+                let offset = self.prg_bank(addr) * 0x4000 + (addr as usize & 0x3FFF);
+                self.prg_rom[offset % self.prg_rom.len()]
+            }
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, addr: u16, data: u8) {
+        match addr {
+            0x0000..=0x1FFF if self.chr_is_ram => {
+                let i = addr as usize % self.chr.len();
+                self.chr[i] = data;
+            }
+            0x8000..=0xFFFF => {
+                let data = if self.bus_conflicts { data & self.read(addr) } else { data };
+                self.prg_bank = data as usize % self.prg_banks();
+            }
+            _ => {}
+        }
+    }
+
+    fn mirroring(&mut self) -> Mirroring {
+        self.mirroring
+    }
+
+    /// 16 KiB units.
+    fn prg_bank_at(&self, addr: u16) -> Option<usize> {
+        (addr >= 0x8000).then(|| self.prg_bank(addr))
+    }
+
+    fn chr_bank_at(&self, addr: u16) -> Option<usize> {
+        (addr < 0x2000).then_some(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `count` banks of `size` bytes, each filled with its bank number.
+    fn banks(count: u8, size: usize) -> Vec<u8> {
+        (0..count).flat_map(|bank| vec![bank; size]).collect()
+    }
+
+    #[test]
+    fn switches_8000_and_keeps_c000_on_the_last_bank() {
+        let mut m = Mapper2::new(banks(8, 0x4000), vec![0; 0x2000], true, Mirroring::Horizontal);
+        assert_eq!((m.read(0x8000), m.read(0xC000)), (0, 7));
+        m.write(0x8000, 3);
+        assert_eq!((m.read(0xBFFF), m.read(0xC000), m.read(0xFFFF)), (3, 7, 7));
+        m.write(0x0010, 0x5A);
+        assert_eq!(m.read(0x0010), 0x5A);
+        assert_eq!(m.mirroring(), Mirroring::Horizontal);
+    }
+}
@@ -0,0 +1,181 @@
+//! Mapper 34: BNROM and NINA-001, two unrelated boards sharing one iNES number.
+//!
+//! - [BNROM](https://www.nesdev.org/wiki/BNROM): writes to $8000–$FFFF select a 32 KiB PRG bank;
+//!   8 KiB CHR RAM. Used by Deadly Towers and homebrew.
+//! - [NINA-001](https://www.nesdev.org/wiki/NINA-001): 8 KiB PRG RAM at $6000–$7FFF, whose last
+//!   three bytes also act as registers: $7FFD = 32 KiB PRG bank, $7FFE / $7FFF = 4 KiB CHR ROM bank
+//!   at $0000 / $1000. Used by Impossible Mission II.
+//!
+//! The boards are told apart by CHR: NINA-001 has CHR ROM, BNROM has CHR RAM.
+//!
+//! BNROM's register sits on the ROM's data bus, so writes can suffer a
+//! [bus conflict](https://www.nesdev.org/wiki/Bus_conflict) (the register latches `data & rom`).
+//! Off by default; enable with `set_bus_conflicts` (NES 2.0 submapper 2 does). NINA-001
+//! registers are in RAM space and never conflict.
+
+use crate::cartridge::mapper::{Mirroring, mapper::Mapper};
+
+/// BNROM or NINA-001 state.
+#[derive(Clone)]
+pub struct Mapper34 {
+    prg_rom: Vec<u8>,
+    /// CHR ROM (NINA-001) or 8 KiB CHR RAM (BNROM).
+    chr: Vec<u8>,
+    prg_ram: Vec<u8>,
+    mirroring: Mirroring,
+    /// True for NINA-001 (CHR ROM present).
+    nina: bool,
+    prg_bank: usize,
+    /// NINA-001 4 KiB CHR banks for $0000 and $1000.
+    chr_banks: [usize; 2],
+    /// BNROM: AND register writes with the ROM byte at the written address.
+    bus_conflicts: bool,
+}
+
+impl Mapper34 {
+    /// Create the board. `chr_is_ram` (no CHR ROM in the header) selects BNROM; otherwise NINA-001.
+    pub fn new(prg_rom: Vec<u8>, chr: Vec<u8>, chr_is_ram: bool, mirroring: Mirroring) -> Self {
+        Self {
+            prg_rom,
+            chr,
+            prg_ram: vec![0; 0x2000],
+            mirroring,
+            nina: !chr_is_ram,
+            prg_bank: 0,
+            chr_banks: [0, 1],
+            bus_conflicts: false,
+        }
+    }
+
+    /// Emulate bus conflicts on BNROM register writes (default off).
+    pub fn set_bus_conflicts(&mut self, enabled: bool) {
+        self.bus_conflicts = enabled;
+    }
+
+    fn prg_banks(&self) -> usize {
+        (self.prg_rom.len() / 0x8000).max(1)
+    }
+
+    /// CHR offset for PPU `addr`: 4 KiB banks on NINA-001, a fixed 8 KiB on BNROM.
+    fn chr_offset(&self, addr: u16) -> usize {
+        if self.nina {
+            let bank = self.chr_banks[(addr as usize >> 12) & 1];
+            (bank * 0x1000 + (addr as usize & 0x0FFF)) % self.chr.len()
+        } else {
+            addr as usize % self.chr.len()
+        }
+    }
+}
+
+impl Mapper for Mapper34 {
+    fn clone_box(&self) -> Box<dyn Mapper> {
+        Box::new(self.clone())
+    }
+
+    fn name(&self) -> &'static str {
+        if self.nina { "NINA-001" } else { "BNROM" }
+    }
+
+    fn read(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x1FFF => self.chr[self.chr_offset(addr)],
+            0x6000..=0x7FFF if self.nina => self.prg_ram[addr as usize - 0x6000],
+            0x8000..=0xFFFF => {
+                let offset = self.prg_bank * 0x8000 + (addr as usize - 0x8000);
+                self.prg_rom[offset % self.prg_rom.len()]
+            }
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, addr: u16, data: u8) {
+        match addr {
+            0x0000..=0x1FFF if !self.nina => {
+                let offset = self.chr_offset(addr);
+                self.chr[offset] = data;
+            }
+            0x6000..=0x7FFF if self.nina => {
+                // The register bytes are ordinary RAM too; the write reaches both.
+                self.prg_ram[addr as usize - 0x6000] = data;
+                match addr {
+                    0x7FFD => self.prg_bank = (data & 1) as usize % self.prg_banks(),
+                    0x7FFE => self.chr_banks[0] = (data & 0x0F) as usize,
+                    0x7FFF => self.chr_banks[1] = (data & 0x0F) as usize,
+                    _ => {}
+                }
+            }
+            0x8000..=0xFFFF if !self.nina => {
+                let data = if self.bus_conflicts { data & self.read(addr) } else { data };
+                self.prg_bank = data as usize % self.prg_banks();
+            }
+            _ => {}
+        }
+    }
+
+    fn mirroring(&mut self) -> Mirroring {
+        self.mirroring
+    }
+
+    /// 16 KiB units, like NROM/MMC1: the 32 KiB bank covers two consecutive ones.
+    fn prg_bank_at(&self, addr: u16) -> Option<usize> {
+        (addr >= 0x8000).then(|| self.prg_bank * 2 + (addr as usize - 0x8000) / 0x4000)
+    }
+
+    /// 4 KiB units on NINA-001; 0 (the only 8 KiB bank) on BNROM.
+    fn chr_bank_at(&self, addr: u16) -> Option<usize> {
+        if addr >= 0x2000 {
+            None
+        } else if self.nina {
+            Some(self.chr_banks[(addr as usize >> 12) & 1])
+        } else {
+            Some(0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `count` banks of `size` bytes, each filled with its bank number.
+    fn banks(count: u8, size: usize) -> Vec<u8> {
+        (0..count).flat_map(|bank| vec![bank; size]).collect()
+    }
+
+    #[test]
+    fn bnrom_switches_prg_and_keeps_chr_ram() {
+        let mut m = Mapper34::new(banks(4, 0x8000), vec![0; 0x2000], true, Mirroring::Vertical);
+        assert_eq!(m.name(), "BNROM");
+        m.write(0x8000, 3);
+        assert_eq!((m.read(0x8000), m.read(0xFFFF)), (3, 3));
+        m.write(0x1234, 0x5A);
+        assert_eq!(m.read(0x1234), 0x5A);
+    }
+
+    #[test]
+    fn bnrom_bus_conflicts_and_the_written_value_with_the_rom_byte() {
+        let mut m = Mapper34::new(banks(8, 0x8000), vec![0; 0x2000], true, Mirroring::Vertical);
+        m.write(0x8000, 5);
+        assert_eq!(m.read(0x8000), 5);
+        m.set_bus_conflicts(true);
+        // The ROM under $8000 now holds 5: 6 & 5 = 4.
+        m.write(0x8000, 6);
+        assert_eq!(m.read(0x8000), 4);
+        m.set_bus_conflicts(false);
+        m.write(0x8000, 6);
+        assert_eq!(m.read(0x8000), 6);
+    }
+
+    #[test]
+    fn nina_001_switches_prg_and_both_chr_windows() {
+        let mut m = Mapper34::new(banks(2, 0x8000), banks(4, 0x1000), false, Mirroring::Vertical);
+        assert_eq!(m.name(), "NINA-001");
+        m.write(0x7FFD, 1);
+        m.write(0x7FFE, 2);
+        m.write(0x7FFF, 3);
+        assert_eq!(m.read(0x8000), 1);
+        assert_eq!((m.read(0x0000), m.read(0x1000)), (2, 3));
+        // The register bytes are RAM as well.
+        assert_eq!(m.read(0x7FFE), 2);
+    }
+}
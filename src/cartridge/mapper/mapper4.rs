@@ -3,24 +3,37 @@
 //! [MMC3](https://www.nesdev.org/wiki/MMC3): Bank select at $8000–$9FFE (even), bank data at
 //! $8001–$9FFF (odd). R0/R1 = 2 KiB CHR, R2–R5 = 1 KiB CHR, R6/R7 = 8 KiB PRG. Mirroring at
 //! $A000–$BFFE (even). IRQ latch $C000, reload $C001, disable $E000, enable $E001. IRQ counter
-//! clocks on PPU CHR A12 rising edge (tracked in read when addr is in CHR range).
+//! clocks on PPU CHR A12 rising edge, but only after A12 has been low for a while: the MMC3
+//! filters out short low pulses, so only the first sprite fetch after background fetches counts.
+//! $A001 (odd) enables PRG RAM (bit 7) and write-protects it (bit 6), except on MMC6 (NES 2.0
+//! submapper 1), whose finer-grained $A001 is not emulated and leaves the RAM open.
 
 use crate::cartridge::mapper::{Mirroring, mapper::Mapper};
 
+/// PPU dots A12 must stay low before a rising edge clocks the IRQ counter. The MMC3 counts M2
+/// falling edges while A12 is low and needs about three (~9–12 dots); 10 matches common emulators
+/// and passes `mmc3_test`.
+const A12_LOW_FILTER_DOTS: u64 = 10;
+
 /// MMC3 state: bank registers, mirroring, PRG RAM, IRQ counter/latch/enable.
+#[derive(Clone)]
 pub struct Mapper4 {
     prg_rom: Vec<u8>,
+    /// CHR ROM, or CHR RAM (TNROM/TGROM) when `chr_is_ram`; banked the same way either way.
     chr_rom: Vec<u8>,
+    chr_is_ram: bool,
     prg_ram: Vec<u8>,
     /// Bank select ($8000): bits 0–2 = register index, bit 6 = PRG mode, bit 7 = CHR A12 invert.
     bank_select: u8,
     /// R0–R5 CHR, R6–R7 PRG (R0/R1 are 2 KiB, others 1 KiB / 8 KiB).
     regs: [u8; 8],
     mirroring: Mirroring,
-    /// PRG RAM enable (bit 7 of $A001). We leave RAM always enabled for MMC6 compatibility.
-    _prg_ram_enable: bool,
-    /// PRG RAM write protect (bit 6 of $A001). Not enforced for compatibility.
-    _prg_ram_write_protect: bool,
+    /// PRG RAM enable (bit 7 of $A001).
+    prg_ram_enable: bool,
+    /// PRG RAM write protect (bit 6 of $A001).
+    prg_ram_write_protect: bool,
+    /// Whether the two bits above are honored; off for MMC6, whose $A001 means something else.
+    enforce_prg_ram_protect: bool,
     /// IRQ latch ($C000), counter, reload flag, enabled ($E001).
     irq_latch: u8,
     irq_counter: u8,
@@ -29,27 +42,40 @@ pub struct Mapper4 {
     irq_pending: bool,
     /// Previous PPU A12 (from CHR address) to detect rising edge.
     last_chr_a12: bool,
+    /// PPU dot at which A12 last went low.
+    a12_low_since: u64,
 }
 
 impl Mapper4 {
-    /// Create MMC3 with PRG ROM, CHR ROM, and initial mirroring from iNES header (byte 6 bit 0).
-    /// PRG RAM 8 KiB is allocated for save RAM.
-    pub fn new(prg_rom: Vec<u8>, chr_rom: Vec<u8>, mirroring: Mirroring) -> Self {
+    /// Create MMC3 with PRG ROM, CHR ROM (or RAM if `chr_is_ram`), and initial mirroring from
+    /// iNES header (byte 6 bit 0). PRG RAM 8 KiB is allocated for save RAM; it starts enabled and
+    /// writable, and `enforce_prg_ram_protect` makes $A001 able to disable or write-protect it
+    /// (pass false for MMC6 boards, which keep it always accessible here).
+    pub fn new(
+        prg_rom: Vec<u8>,
+        chr_rom: Vec<u8>,
+        chr_is_ram: bool,
+        mirroring: Mirroring,
+        enforce_prg_ram_protect: bool,
+    ) -> Self {
         Self {
             prg_rom,
             chr_rom,
+            chr_is_ram,
             prg_ram: vec![0; 8 * 1024],
             bank_select: 0,
             regs: [0; 8],
             mirroring,
-            _prg_ram_enable: true,
-            _prg_ram_write_protect: false,
+            prg_ram_enable: true,
+            prg_ram_write_protect: false,
+            enforce_prg_ram_protect,
             irq_latch: 0,
             irq_counter: 0,
             irq_reload_pending: false,
             irq_enabled: false,
             irq_pending: false,
             last_chr_a12: false,
+            a12_low_since: 0,
         }
     }
 
@@ -62,11 +88,6 @@ impl Mapper4 {
         self.chr_rom.len() / 0x400
     }
 
-    /// Number of 2 KiB CHR banks (for R0/R1).
-    fn chr_bank_count_2k(&self) -> usize {
-        self.chr_rom.len() / 0x800
-    }
-
     /// Clock IRQ counter on PPU A12 rising edge (call when CHR read address has A12 going 0→1).
     fn clock_irq(&mut self) {
         if self.irq_counter == 0 || self.irq_reload_pending {
@@ -80,121 +101,77 @@ impl Mapper4 {
         }
     }
 
-    /// CHR bank for 2 KiB slot (R0 or R1): register value with low bit cleared.
-    fn chr_2k_bank(&self, reg: u8) -> usize {
-        ((self.regs[reg as usize] & 0xFE) as usize) % self.chr_bank_count_2k().max(1)
+    /// 8 KiB PRG bank mapped at `addr` ($8000–$FFFF). PRG mode (bit 6 of bank select) swaps
+    /// which of $8000/$C000 is R6 and which is fixed to the second-last bank; $E000 is always last.
+    fn prg_bank(&self, addr: u16) -> usize {
+        let bank_count = self.prg_bank_count().max(1);
+        let last = bank_count - 1;
+        let second_last = last.saturating_sub(1);
+        let r6 = (self.regs[6] & 0x3F) as usize % bank_count;
+        let r7 = (self.regs[7] & 0x3F) as usize % bank_count;
+        let prg_mode = (self.bank_select & 0x40) != 0;
+
+        match ((addr - 0x8000) >> 13, prg_mode) {
+            (0, false) | (2, true) => r6,
+            (1, _) => r7,
+            (0, true) | (2, false) => second_last,
+            _ => last,
+        }
+    }
+
+    /// 1 KiB CHR page mapped at `addr` ($0000–$1FFF). Without CHR A12 inversion, R0/R1 select
+    /// 2 KiB at $0000/$0800 (low bit ignored) and R2–R5 select 1 KiB at $1000–$1C00; inversion
+    /// (bit 7 of bank select) swaps the two halves.
+    fn chr_page(&self, addr: u16) -> usize {
+        let a = if self.bank_select & 0x80 != 0 {
+            addr ^ 0x1000
+        } else {
+            addr
+        };
+        let page = match a & 0x1FFF {
+            0x0000..=0x07FF => (self.regs[0] & 0xFE) as usize + ((a >> 10) & 1) as usize,
+            0x0800..=0x0FFF => (self.regs[1] & 0xFE) as usize + ((a >> 10) & 1) as usize,
+            _ => self.regs[2 + ((a as usize - 0x1000) >> 10)] as usize,
+        };
+        page % self.chr_bank_count_1k().max(1)
+    }
+
+    fn prg_ram_readable(&self) -> bool {
+        self.prg_ram_enable || !self.enforce_prg_ram_protect
     }
 
-    /// CHR bank for 1 KiB slot (R2–R5).
-    fn chr_1k_bank(&self, reg: u8) -> usize {
-        (self.regs[reg as usize] as usize) % self.chr_bank_count_1k().max(1)
+    fn prg_ram_writable(&self) -> bool {
+        !self.enforce_prg_ram_protect || (self.prg_ram_enable && !self.prg_ram_write_protect)
     }
 
     fn read_chr(&self, addr: u16) -> u8 {
-        let a = addr as usize;
-        let chr_invert = (self.bank_select & 0x80) != 0;
-        let n_1k = self.chr_bank_count_1k();
-        if n_1k == 0 {
+        if self.chr_bank_count_1k() == 0 {
             return 0;
         }
-
-        if !chr_invert {
-            // Two 2 KiB at $0000–$0FFF, four 1 KiB at $1000–$1FFF
-            match addr {
-                0x0000..=0x07FF => {
-                    let bank = self.chr_2k_bank(0);
-                    self.chr_rom[bank * 0x800 + (a & 0x7FF)]
-                }
-                0x0800..=0x0FFF => {
-                    let bank = self.chr_2k_bank(1);
-                    self.chr_rom[bank * 0x800 + (a & 0x7FF)]
-                }
-                0x1000..=0x13FF => {
-                    let bank = self.chr_1k_bank(2);
-                    self.chr_rom[bank * 0x400 + (a & 0x3FF)]
-                }
-                0x1400..=0x17FF => {
-                    let bank = self.chr_1k_bank(3);
-                    self.chr_rom[bank * 0x400 + (a & 0x3FF)]
-                }
-                0x1800..=0x1BFF => {
-                    let bank = self.chr_1k_bank(4);
-                    self.chr_rom[bank * 0x400 + (a & 0x3FF)]
-                }
-                0x1C00..=0x1FFF => {
-                    let bank = self.chr_1k_bank(5);
-                    self.chr_rom[bank * 0x400 + (a & 0x3FF)]
-                }
-                _ => 0,
-            }
-        } else {
-            // Four 1 KiB at $0000–$0FFF, two 2 KiB at $1000–$1FFF
-            match addr {
-                0x0000..=0x03FF => {
-                    let bank = self.chr_1k_bank(2);
-                    self.chr_rom[bank * 0x400 + (a & 0x3FF)]
-                }
-                0x0400..=0x07FF => {
-                    let bank = self.chr_1k_bank(3);
-                    self.chr_rom[bank * 0x400 + (a & 0x3FF)]
-                }
-                0x0800..=0x0BFF => {
-                    let bank = self.chr_1k_bank(4);
-                    self.chr_rom[bank * 0x400 + (a & 0x3FF)]
-                }
-                0x0C00..=0x0FFF => {
-                    let bank = self.chr_1k_bank(5);
-                    self.chr_rom[bank * 0x400 + (a & 0x3FF)]
-                }
-                0x1000..=0x17FF => {
-                    let bank = self.chr_2k_bank(0);
-                    self.chr_rom[bank * 0x800 + (a - 0x1000)]
-                }
-                0x1800..=0x1FFF => {
-                    let bank = self.chr_2k_bank(1);
-                    self.chr_rom[bank * 0x800 + (a - 0x1800)]
-                }
-                _ => 0,
-            }
-        }
+        self.chr_rom[self.chr_page(addr) * 0x400 + (addr as usize & 0x3FF)]
     }
 }
 
 impl Mapper for Mapper4 {
+    fn clone_box(&self) -> Box<dyn Mapper> {
+        Box::new(self.clone())
+    }
+
+    fn name(&self) -> &'static str {
+        "MMC3"
+    }
+
     fn read(&self, addr: u16) -> u8 {
         match addr {
             0x0000..=0x1FFF => self.read_chr(addr),
+            // Disabled PRG RAM leaves the bus floating at the address high byte.
+            0x6000..=0x7FFF if !self.prg_ram_readable() => (addr >> 8) as u8,
             0x6000..=0x7FFF => {
                 let i = (addr - 0x6000) as usize;
                 *self.prg_ram.get(i).unwrap_or(&0)
             }
             0x8000..=0xFFFF => {
-                let prg_mode = (self.bank_select & 0x40) != 0;
-                let bank_count = self.prg_bank_count().max(1);
-                let last = bank_count - 1;
-                let second_last = last.saturating_sub(1);
-                let addr = addr as usize;
-
-                let (bank_8000, bank_a000, bank_c000, bank_e000) = if !prg_mode {
-                    let r6 = (self.regs[6] & 0x3F) as usize % bank_count;
-                    let r7 = (self.regs[7] & 0x3F) as usize % bank_count;
-                    (r6, r7, second_last, last)
-                } else {
-                    let r6 = (self.regs[6] & 0x3F) as usize % bank_count;
-                    let r7 = (self.regs[7] & 0x3F) as usize % bank_count;
-                    (second_last, r7, r6, last)
-                };
-
-                let segment = ((addr as usize) - 0x8000) >> 13;
-                let offset_8k = (addr as usize) & 0x1FFF;
-                let bank = match segment {
-                    0 => bank_8000,
-                    1 => bank_a000,
-                    2 => bank_c000,
-                    3 => bank_e000,
-                    _ => last,
-                };
-                let phys = bank * 0x2000 + offset_8k;
+                let phys = self.prg_bank(addr) * 0x2000 + (addr as usize & 0x1FFF);
                 *self.prg_rom.get(phys).unwrap_or(&0)
             }
             _ => 0,
@@ -203,9 +180,13 @@ impl Mapper for Mapper4 {
 
     fn write(&mut self, addr: u16, data: u8) {
         match addr {
-            0x0000..=0x1FFF => {
-                // CHR is ROM on standard MMC3; writes ignored (no CHR RAM on TxROM).
+            0x0000..=0x1FFF if self.chr_is_ram && self.chr_bank_count_1k() > 0 => {
+                let i = self.chr_page(addr) * 0x400 + (addr as usize & 0x3FF);
+                self.chr_rom[i] = data;
             }
+            // CHR ROM: writes ignored.
+            0x0000..=0x1FFF => {}
+            0x6000..=0x7FFF if !self.prg_ram_writable() => {}
             0x6000..=0x7FFF => {
                 let i = (addr - 0x6000) as usize;
                 if let Some(b) = self.prg_ram.get_mut(i) {
@@ -222,14 +203,18 @@ impl Mapper for Mapper4 {
             }
             0xA000..=0xBFFF => {
                 if addr & 1 == 0 {
-                    self.mirroring = if data & 1 != 0 {
-                        Mirroring::Vertical
-                    } else {
-                        Mirroring::Horizontal
-                    };
+                    // Four-screen boards wire the nametables to their own VRAM; $A000 has no
+                    // effect there.
+                    if !matches!(self.mirroring, Mirroring::FourScreen) {
+                        self.mirroring = if data & 1 != 0 {
+                            Mirroring::Vertical
+                        } else {
+                            Mirroring::Horizontal
+                        };
+                    }
                 } else {
-                    self._prg_ram_enable = data & 0x80 != 0;
-                    self._prg_ram_write_protect = data & 0x40 != 0;
+                    self.prg_ram_enable = data & 0x80 != 0;
+                    self.prg_ram_write_protect = data & 0x40 != 0;
                 }
             }
             0xC000..=0xDFFF => {
@@ -257,18 +242,115 @@ impl Mapper for Mapper4 {
         self.mirroring
     }
 
-    /// Called when the PPU reads a CHR address; clocks IRQ counter on A12 rising edge.
-    fn on_chr_access(&mut self, addr: u16) {
+    /// Called when the PPU reads a CHR address; clocks IRQ counter on an A12 rising edge that
+    /// follows at least `A12_LOW_FILTER_DOTS` of A12 low.
+    fn on_chr_access(&mut self, addr: u16, ppu_dot: u64) {
         let a12 = (addr & 0x1000) != 0;
-        if !self.last_chr_a12 && a12 {
-            self.clock_irq();
+        if a12 && !self.last_chr_a12 {
+            if ppu_dot.saturating_sub(self.a12_low_since) >= A12_LOW_FILTER_DOTS {
+                self.clock_irq();
+            }
+        } else if !a12 && self.last_chr_a12 {
+            self.a12_low_since = ppu_dot;
         }
         self.last_chr_a12 = a12;
     }
 
+    /// Asserted until acknowledged by a write to $E000.
     fn poll_irq(&mut self) -> bool {
-        let p = self.irq_pending;
-        self.irq_pending = false;
-        p
+        self.irq_pending
+    }
+
+    fn prg_bank_at(&self, addr: u16) -> Option<usize> {
+        (addr >= 0x8000).then(|| self.prg_bank(addr))
+    }
+
+    fn chr_bank_at(&self, addr: u16) -> Option<usize> {
+        (addr < 0x2000 && self.chr_bank_count_1k() > 0).then(|| self.chr_page(addr))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mmc3(chr_rom: Vec<u8>, mirroring: Mirroring) -> Mapper4 {
+        let chr_is_ram = chr_rom.is_empty();
+        let chr_rom = if chr_is_ram { vec![0; 8 * 1024] } else { chr_rom };
+        Mapper4::new(vec![0; 32 * 1024], chr_rom, chr_is_ram, mirroring, true)
+    }
+
+    #[test]
+    fn irq_counter_ignores_a12_rises_after_short_low_pulses() {
+        let mut m = mmc3(vec![0; 8 * 1024], Mirroring::Vertical);
+        m.write(0xC000, 1);
+        m.write(0xE001, 0);
+        // A12 low from dot 0: the rise at 20 reloads the counter with 1.
+        m.on_chr_access(0x0000, 0);
+        m.on_chr_access(0x1000, 20);
+        // Low for only two dots: filtered out, otherwise this would hit 0.
+        m.on_chr_access(0x0000, 22);
+        m.on_chr_access(0x1000, 24);
+        assert!(!m.poll_irq());
+        // A long low pulse counts: 1 -> 0 raises the IRQ.
+        m.on_chr_access(0x0000, 30);
+        m.on_chr_access(0x1000, 50);
+        assert!(m.poll_irq());
+    }
+
+    #[test]
+    fn reports_switched_prg_bank() {
+        let mut m = mmc3(vec![0; 8 * 1024], Mirroring::Vertical);
+        m.write(0x8000, 6);
+        m.write(0x8001, 1);
+        assert_eq!(m.prg_bank_at(0x8000), Some(1));
+        // PRG mode 1 moves R6 to $C000 and fixes $8000 to the second-last bank.
+        m.write(0x8000, 0x46);
+        assert_eq!(m.prg_bank_at(0x8000), Some(2));
+        assert_eq!(m.prg_bank_at(0xC000), Some(1));
+    }
+
+    #[test]
+    fn chr_ram_is_written_through_the_selected_bank() {
+        let mut m = mmc3(Vec::new(), Mirroring::Vertical);
+        // R0 = 2: $0000–$07FF shows CHR RAM $0800–$0FFF.
+        m.write(0x8000, 0);
+        m.write(0x8001, 2);
+        m.write(0x0000, 0xAB);
+        // R1 = 2: the same RAM now also appears at $0800.
+        m.write(0x8000, 1);
+        m.write(0x8001, 2);
+        assert_eq!(m.read(0x0000), 0xAB);
+        assert_eq!(m.read(0x0800), 0xAB);
+    }
+
+    #[test]
+    fn four_screen_ignores_a000_and_keeps_prg_ram_writable() {
+        let mut m = mmc3(vec![0; 8 * 1024], Mirroring::FourScreen);
+        m.write(0xA000, 0x01);
+        m.write(0xA000, 0x00);
+        assert_eq!(m.mirroring(), Mirroring::FourScreen);
+        m.write(0x6000, 0x5A);
+        assert_eq!(m.read(0x6000), 0x5A);
+    }
+
+    #[test]
+    fn a001_enables_protects_and_disables_prg_ram() {
+        let mut m = mmc3(Vec::new(), Mirroring::Vertical);
+        m.write(0xA001, 0x80); // enabled, writable
+        m.write(0x6000, 0x12);
+        assert_eq!(m.read(0x6000), 0x12);
+        m.write(0xA001, 0xC0); // enabled, write-protected
+        m.write(0x6000, 0x34);
+        assert_eq!(m.read(0x6000), 0x12);
+        m.write(0xA001, 0x00); // disabled: open bus (the high address byte)
+        assert_eq!(m.read(0x6000), 0x60);
+
+        // Without enforcement (MMC6 boards) $A001 changes nothing.
+        let prg = vec![0; 32 * 1024];
+        let mut lax = Mapper4::new(prg, vec![0; 8 * 1024], true, Mirroring::Vertical, false);
+        lax.write(0xA001, 0x00);
+        lax.write(0x6000, 0x56);
+        assert_eq!(lax.read(0x6000), 0x56);
     }
 }
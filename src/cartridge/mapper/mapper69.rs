@@ -0,0 +1,284 @@
+//! Mapper 69 (Sunsoft FME-7 / 5B): 8 KiB PRG / 1 KiB CHR banking, CPU-cycle IRQ, 5B audio.
+//!
+//! [Sunsoft FME-7](https://www.nesdev.org/wiki/Sunsoft_FME-7) (Batman: Return of the Joker,
+//! Gimmick!) is programmed through a command/parameter pair:
+//!
+//! - $8000–$9FFF: command (bits 0–3).
+//! - $A000–$BFFF: parameter for the selected command:
+//!   - $0–$7: 1 KiB CHR bank for $0000, $0400, …, $1C00.
+//!   - $8: $6000–$7FFF: bank (bits 0–5), RAM instead of ROM (bit 6), RAM enable (bit 7).
+//!   - $9–$B: 8 KiB PRG bank for $8000 / $A000 / $C000; $E000 is fixed to the last bank.
+//!   - $C: mirroring (0 = vertical, 1 = horizontal, 2 = one-screen lower, 3 = one-screen upper).
+//!   - $D: IRQ control: bit 0 = IRQ enable, bit 7 = counter enable. Acknowledges the IRQ.
+//!   - $E / $F: IRQ counter low / high byte. The 16-bit counter decrements every CPU cycle while
+//!     enabled and raises the IRQ when it wraps from $0000 to $FFFF.
+//!
+//! The 5B variant adds [Sunsoft 5B audio](https://www.nesdev.org/wiki/Sunsoft_5B_audio), a
+//! YM2149 core: $C000–$DFFF selects a register (bits 0–3), $E000–$FFFF writes it. Emulated: the
+//! three square channels (registers $0–$5 tone period, $7 mixer, $8–$A volume). Not emulated:
+//! the noise generator and the envelope (registers $6, $B–$D; envelope-mode volumes play at
+//! their fixed level).
+
+use crate::cartridge::mapper::{Mirroring, mapper::Mapper};
+
+/// Output of one 5B channel at volume 15, in NES mixer units (a full-volume pulse is ~0.15).
+const SUNSOFT5B_CHANNEL_LEVEL: f32 = 0.15;
+
+/// Sunsoft 5B tone generators: three squares with 12-bit periods and logarithmic volume.
+#[derive(Clone, Default)]
+struct Sunsoft5b {
+    /// Register selected through $C000.
+    address: u8,
+    /// 12-bit tone periods for channels A, B, C.
+    periods: [u16; 3],
+    /// Counts up to the period, then flips the square.
+    counters: [u16; 3],
+    high: [bool; 3],
+    /// Register $7: bits 0–2 disable the tone of A, B, C (the channel then outputs its volume
+    /// level constantly).
+    mixer: u8,
+    volumes: [u8; 3],
+    /// Tones advance every 16 CPU cycles.
+    prescaler: u8,
+    /// Sum of channel output over the last `clock` call, for averaging.
+    level_sum: f32,
+    level_cycles: usize,
+    /// Output averaged over the last `clock` call (box filter down to the APU's sample rate).
+    output: f32,
+}
+
+impl Sunsoft5b {
+    fn write_register(&mut self, data: u8) {
+        match self.address {
+            r @ (0 | 2 | 4) => {
+                let ch = r as usize / 2;
+                self.periods[ch] = (self.periods[ch] & 0xF00) | data as u16;
+            }
+            r @ (1 | 3 | 5) => {
+                let ch = r as usize / 2;
+                self.periods[ch] = (self.periods[ch] & 0x0FF) | ((data & 0x0F) as u16) << 8;
+            }
+            7 => self.mixer = data,
+            r @ 8..=10 => self.volumes[r as usize - 8] = data & 0x0F,
+            _ => {}
+        }
+    }
+
+    /// Amplitude of volume `v` (0–15): 3 dB per step, 0 silent.
+    fn volume_level(v: u8) -> f32 {
+        if v == 0 {
+            0.0
+        } else {
+            SUNSOFT5B_CHANNEL_LEVEL * 10f32.powf((v as f32 - 15.0) * 3.0 / 20.0)
+        }
+    }
+
+    fn level(&self) -> f32 {
+        (0..3)
+            .filter(|&ch| self.high[ch] || self.mixer & (1 << ch) != 0)
+            .map(|ch| Self::volume_level(self.volumes[ch]))
+            .sum()
+    }
+
+    fn clock(&mut self, cycles: usize) {
+        for _ in 0..cycles {
+            self.prescaler += 1;
+            if self.prescaler == 16 {
+                self.prescaler = 0;
+                for ch in 0..3 {
+                    self.counters[ch] += 1;
+                    if self.counters[ch] >= self.periods[ch].max(1) {
+                        self.counters[ch] = 0;
+                        self.high[ch] = !self.high[ch];
+                    }
+                }
+            }
+            self.level_sum += self.level();
+            self.level_cycles += 1;
+        }
+        if self.level_cycles > 0 {
+            self.output = self.level_sum / self.level_cycles as f32;
+            self.level_sum = 0.0;
+            self.level_cycles = 0;
+        }
+    }
+}
+
+/// Sunsoft FME-7 / 5B state.
+#[derive(Clone)]
+pub struct Mapper69 {
+    prg_rom: Vec<u8>,
+    chr: Vec<u8>,
+    chr_is_ram: bool,
+    prg_ram: Vec<u8>,
+    command: u8,
+    /// 1 KiB CHR banks for $0000–$1FFF.
+    chr_banks: [u8; 8],
+    /// Command $8: $6000 bank, RAM select and RAM enable.
+    ram_bank: u8,
+    /// 8 KiB PRG banks for $8000, $A000, $C000.
+    prg_banks: [u8; 3],
+    mirroring: Mirroring,
+    irq_counter: u16,
+    irq_enabled: bool,
+    counter_enabled: bool,
+    irq_pending: bool,
+    audio: Sunsoft5b,
+}
+
+impl Mapper69 {
+    /// Create FME-7. `chr_is_ram` makes `chr` writable (boards without CHR ROM).
+    pub fn new(prg_rom: Vec<u8>, chr: Vec<u8>, chr_is_ram: bool, mirroring: Mirroring) -> Self {
+        Self {
+            prg_rom,
+            chr,
+            chr_is_ram,
+            prg_ram: vec![0; 0x2000],
+            command: 0,
+            chr_banks: [0; 8],
+            ram_bank: 0,
+            prg_banks: [0; 3],
+            mirroring,
+            irq_counter: 0,
+            irq_enabled: false,
+            counter_enabled: false,
+            irq_pending: false,
+            audio: Sunsoft5b::default(),
+        }
+    }
+
+    fn prg_bank_count(&self) -> usize {
+        (self.prg_rom.len() / 0x2000).max(1)
+    }
+
+    /// 8 KiB PRG bank at `addr` ($8000–$FFFF).
+    fn prg_bank(&self, addr: u16) -> usize {
+        match (addr - 0x8000) >> 13 {
+            slot @ 0..=2 => (self.prg_banks[slot as usize] & 0x3F) as usize % self.prg_bank_count(),
+            _ => self.prg_bank_count() - 1,
+        }
+    }
+
+    fn chr_offset(&self, addr: u16) -> usize {
+        (self.chr_banks[addr as usize >> 10] as usize * 0x400 + (addr as usize & 0x3FF))
+            % self.chr.len()
+    }
+
+    fn write_parameter(&mut self, data: u8) {
+        match self.command {
+            c @ 0..=7 => self.chr_banks[c as usize] = data,
+            8 => self.ram_bank = data,
+            c @ 9..=0xB => self.prg_banks[c as usize - 9] = data,
+            0xC => {
+                self.mirroring = match data & 3 {
+                    0 => Mirroring::Vertical,
+                    1 => Mirroring::Horizontal,
+                    2 => Mirroring::OneScreenLower,
+                    _ => Mirroring::OneScreenUpper,
+                };
+            }
+            0xD => {
+                self.irq_enabled = data & 0x01 != 0;
+                self.counter_enabled = data & 0x80 != 0;
+                self.irq_pending = false;
+            }
+            0xE => self.irq_counter = (self.irq_counter & 0xFF00) | data as u16,
+            _ => self.irq_counter = (self.irq_counter & 0x00FF) | (data as u16) << 8,
+        }
+    }
+}
+
+impl Mapper for Mapper69 {
+    fn clone_box(&self) -> Box<dyn Mapper> {
+        Box::new(self.clone())
+    }
+
+    fn name(&self) -> &'static str {
+        "Sunsoft FME-7"
+    }
+
+    fn read(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x1FFF => self.chr[self.chr_offset(addr)],
+            0x6000..=0x7FFF if self.ram_bank & 0x40 == 0 => {
+                let bank = (self.ram_bank & 0x3F) as usize % self.prg_bank_count();
+                self.prg_rom[bank * 0x2000 + (addr as usize & 0x1FFF)]
+            }
+            0x6000..=0x7FFF if self.ram_bank & 0x80 != 0 => self.prg_ram[addr as usize - 0x6000],
+            0x8000..=0xFFFF => {
+                self.prg_rom[self.prg_bank(addr) * 0x2000 + (addr as usize & 0x1FFF)]
+            }
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, addr: u16, data: u8) {
+        match addr {
+            0x0000..=0x1FFF if self.chr_is_ram => {
+                let i = self.chr_offset(addr);
+                self.chr[i] = data;
+            }
+            0x6000..=0x7FFF if self.ram_bank & 0xC0 == 0xC0 => {
+                self.prg_ram[addr as usize - 0x6000] = data;
+            }
+            0x8000..=0x9FFF => self.command = data & 0x0F,
+            0xA000..=0xBFFF => self.write_parameter(data),
+            0xC000..=0xDFFF => self.audio.address = data & 0x0F,
+            0xE000..=0xFFFF => self.audio.write_register(data),
+            _ => {}
+        }
+    }
+
+    fn mirroring(&mut self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn cpu_clock(&mut self, cycles: usize) {
+        self.audio.clock(cycles);
+        if !self.counter_enabled {
+            return;
+        }
+        for _ in 0..cycles {
+            self.irq_counter = self.irq_counter.wrapping_sub(1);
+            if self.irq_counter == 0xFFFF && self.irq_enabled {
+                self.irq_pending = true;
+            }
+        }
+    }
+
+    fn poll_irq(&mut self) -> bool {
+        self.irq_pending
+    }
+
+    fn audio_output(&self) -> f32 {
+        self.audio.output
+    }
+
+    /// 8 KiB units.
+    fn prg_bank_at(&self, addr: u16) -> Option<usize> {
+        (addr >= 0x8000).then(|| self.prg_bank(addr))
+    }
+
+    /// 1 KiB units.
+    fn chr_bank_at(&self, addr: u16) -> Option<usize> {
+        (addr < 0x2000).then(|| self.chr_offset(addr) >> 10)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tone_period_and_volume_produce_output() {
+        let mut m = Mapper69::new(vec![0; 0x8000], vec![0; 0x2000], false, Mirroring::Vertical);
+        m.cpu_clock(64);
+        assert_eq!(m.audio_output(), 0.0);
+        for (register, value) in [(0, 1), (1, 0), (7, 0xFE), (8, 15)] {
+            m.write(0xC000, register);
+            m.write(0xE000, value);
+        }
+        m.cpu_clock(64);
+        assert!(m.audio_output() > 0.0);
+    }
+}
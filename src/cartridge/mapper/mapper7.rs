@@ -0,0 +1,119 @@
+//! Mapper 7 (AxROM): 32 KiB PRG switching and one-screen mirroring from one register.
+//!
+//! [AxROM](https://www.nesdev.org/wiki/AxROM) (ANROM, AMROM, AOROM): any write to $8000–$FFFF
+//! selects the 32 KiB PRG bank (bits 0–2) and which 1 KiB of nametable RAM all four nametables
+//! show (bit 4). 8 KiB CHR RAM. Used by Battletoads and Wizards & Warriors.
+//!
+//! The register sits on the ROM's data bus, so a write can suffer a
+//! [bus conflict](https://www.nesdev.org/wiki/Bus_conflict) (the register latches `data & rom`).
+//! Off by default; the loader enables it for NES 2.0 submapper 2.
+
+use crate::cartridge::mapper::{Mirroring, mapper::Mapper};
+
+/// AxROM: register-selected 32 KiB PRG bank and one-screen page.
+#[derive(Clone)]
+pub struct Mapper7 {
+    prg_rom: Vec<u8>,
+    /// 8 KiB CHR RAM (or CHR ROM on the odd board that has it).
+    chr: Vec<u8>,
+    chr_is_ram: bool,
+    mirroring: Mirroring,
+    prg_bank: usize,
+    /// AND register writes with the ROM byte at the written address.
+    bus_conflicts: bool,
+}
+
+impl Mapper7 {
+    /// Create AxROM. `chr_is_ram` makes CHR writable. Powers on in bank 0 showing the lower
+    /// nametable; the header's mirroring bit does not apply.
+    pub fn new(prg_rom: Vec<u8>, chr: Vec<u8>, chr_is_ram: bool) -> Self {
+        Self {
+            prg_rom,
+            chr,
+            chr_is_ram,
+            mirroring: Mirroring::OneScreenLower,
+            prg_bank: 0,
+            bus_conflicts: false,
+        }
+    }
+
+    /// Emulate bus conflicts on register writes (default off).
+    pub fn set_bus_conflicts(&mut self, enabled: bool) {
+        self.bus_conflicts = enabled;
+    }
+
+    fn prg_banks(&self) -> usize {
+        (self.prg_rom.len() / 0x8000).max(1)
+    }
+}
+
+impl Mapper for Mapper7 {
+    fn clone_box(&self) -> Box<dyn Mapper> {
+        Box::new(self.clone())
+    }
+
+    fn name(&self) -> &'static str {
+        "AxROM"
+    }
+
+    fn read(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x1FFF => self.chr[addr as usize % self.chr.len()],
+            0x8000..=0xFFFF => {
+                let offset = self.prg_bank * 0x8000 + (addr as usize - 0x8000);
+                self.prg_rom[offset % self.prg_rom.len()]
+            }
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, addr: u16, data: u8) {
+        match addr {
+            0x0000..=0x1FFF if self.chr_is_ram => {
+                let i = addr as usize % self.chr.len();
+                self.chr[i] = data;
+            }
+            0x8000..=0xFFFF => {
+                let data = if self.bus_conflicts { data & self.read(addr) } else { data };
+                self.prg_bank = (data & 0x07) as usize % self.prg_banks();
+                self.mirroring = if data & 0x10 != 0 {
+                    Mirroring::OneScreenUpper
+                } else {
+                    Mirroring::OneScreenLower
+                };
+            }
+            _ => {}
+        }
+    }
+
+    /// One-screen page selected by register bit 4.
+    fn mirroring(&mut self) -> Mirroring {
+        self.mirroring
+    }
+
+    /// 16 KiB units, like NROM/MMC1: the 32 KiB bank covers two consecutive ones.
+    fn prg_bank_at(&self, addr: u16) -> Option<usize> {
+        (addr >= 0x8000).then(|| self.prg_bank * 2 + (addr as usize - 0x8000) / 0x4000)
+    }
+
+    fn chr_bank_at(&self, addr: u16) -> Option<usize> {
+        (addr < 0x2000).then_some(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_switches_32k_prg_and_the_one_screen_page() {
+        let prg = (0..4u8).flat_map(|bank| vec![bank; 0x8000]).collect();
+        let mut m = Mapper7::new(prg, vec![0; 0x2000], true);
+        assert_eq!(m.mirroring(), Mirroring::OneScreenLower);
+        m.write(0x8000, 0x12);
+        assert_eq!((m.read(0x8000), m.read(0xFFFF)), (2, 2));
+        assert_eq!(m.mirroring(), Mirroring::OneScreenUpper);
+        m.write(0x8000, 0x02);
+        assert_eq!(m.mirroring(), Mirroring::OneScreenLower);
+    }
+}
@@ -0,0 +1,138 @@
+//! Mapper 9 (MMC2): 8 KiB PRG switching and latch-driven 4 KiB CHR switching.
+//!
+//! [MMC2](https://www.nesdev.org/wiki/MMC2) (Mike Tyson's Punch-Out!!):
+//!
+//! - $8000–$9FFF: switchable 8 KiB PRG bank ($A000); $A000–$FFFF fixed to the last three banks.
+//! - $0000 / $1000: 4 KiB CHR banks chosen by the FD/FE latches (see `chr_latch`); registers
+//!   $B000/$C000 (window 0, FD/FE) and $D000/$E000 (window 1, FD/FE). Unlike MMC4, the $0000
+//!   latch only reacts to exactly $0FD8 / $0FE8.
+//! - $F000: mirroring (bit 0: 0 = vertical, 1 = horizontal).
+
+use crate::cartridge::mapper::chr_latch::ChrLatches;
+use crate::cartridge::mapper::{Mirroring, mapper::Mapper};
+
+/// MMC2 state: PRG bank, CHR latches, mirroring.
+#[derive(Clone)]
+pub struct Mapper9 {
+    prg_rom: Vec<u8>,
+    chr_rom: Vec<u8>,
+    prg_bank: usize,
+    chr: ChrLatches,
+    mirroring: Mirroring,
+}
+
+impl Mapper9 {
+    /// Create MMC2 with PRG, CHR ROM and the header's mirroring (until $F000 is written).
+    pub fn new(prg_rom: Vec<u8>, chr_rom: Vec<u8>, mirroring: Mirroring) -> Self {
+        Self {
+            prg_rom,
+            chr_rom,
+            prg_bank: 0,
+            chr: ChrLatches::new(true),
+            mirroring,
+        }
+    }
+
+    fn prg_bank_count(&self) -> usize {
+        (self.prg_rom.len() / 0x2000).max(1)
+    }
+
+    /// 8 KiB PRG bank at `addr` ($8000–$FFFF).
+    fn prg_bank(&self, addr: u16) -> usize {
+        let count = self.prg_bank_count();
+        match (addr - 0x8000) >> 13 {
+            0 => self.prg_bank % count,
+            slot => count.saturating_sub(4 - slot as usize),
+        }
+    }
+}
+
+impl Mapper for Mapper9 {
+    fn clone_box(&self) -> Box<dyn Mapper> {
+        Box::new(self.clone())
+    }
+
+    fn name(&self) -> &'static str {
+        "MMC2"
+    }
+
+    fn read(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x1FFF => {
+                let offset = self.chr.bank(addr) * 0x1000 + (addr as usize & 0x0FFF);
+                self.chr_rom[offset % self.chr_rom.len()]
+            }
+            0x8000..=0xFFFF => {
+                self.prg_rom[self.prg_bank(addr) * 0x2000 + (addr as usize & 0x1FFF)]
+            }
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, addr: u16, data: u8) {
+        match addr {
+            0xA000..=0xAFFF => self.prg_bank = (data & 0x0F) as usize,
+            0xB000..=0xBFFF => self.chr.banks[0][0] = (data & 0x1F) as usize,
+            0xC000..=0xCFFF => self.chr.banks[0][1] = (data & 0x1F) as usize,
+            0xD000..=0xDFFF => self.chr.banks[1][0] = (data & 0x1F) as usize,
+            0xE000..=0xEFFF => self.chr.banks[1][1] = (data & 0x1F) as usize,
+            0xF000..=0xFFFF => {
+                self.mirroring = if data & 1 != 0 {
+                    Mirroring::Horizontal
+                } else {
+                    Mirroring::Vertical
+                };
+            }
+            _ => {}
+        }
+    }
+
+    fn mirroring(&mut self) -> Mirroring {
+        self.mirroring
+    }
+
+    /// The fetch that hits $FD/$FE uses the old bank; the latch flips afterwards.
+    fn on_chr_access(&mut self, addr: u16, _ppu_dot: u64) {
+        self.chr.on_fetch(addr);
+    }
+
+    /// 8 KiB units.
+    fn prg_bank_at(&self, addr: u16) -> Option<usize> {
+        (addr >= 0x8000).then(|| self.prg_bank(addr))
+    }
+
+    /// 4 KiB units.
+    fn chr_bank_at(&self, addr: u16) -> Option<usize> {
+        (addr < 0x2000).then(|| self.chr.bank(addr))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `count` banks of `size` bytes, each filled with its bank number.
+    fn banks(count: u8, size: usize) -> Vec<u8> {
+        (0..count).flat_map(|bank| vec![bank; size]).collect()
+    }
+
+    #[test]
+    fn tile_fetches_flip_the_latches_and_the_chr_banks() {
+        let mut m = Mapper9::new(banks(8, 0x2000), banks(8, 0x1000), Mirroring::Vertical);
+        for (addr, bank) in [(0xB000, 1), (0xC000, 2), (0xD000, 3), (0xE000, 4)] {
+            m.write(addr, bank);
+        }
+        m.on_chr_access(0x0FE8, 0);
+        m.on_chr_access(0x1FE8, 0);
+        assert_eq!((m.read(0x0000), m.read(0x1000)), (2, 4));
+        m.on_chr_access(0x0FD8, 0);
+        assert_eq!((m.read(0x0000), m.read(0x1000)), (1, 4));
+        m.on_chr_access(0x1FD8, 0);
+        assert_eq!((m.read(0x0000), m.read(0x1000)), (1, 3));
+
+        m.write(0xA000, 2); // $A000-$FFFF: fixed to the last three banks
+        assert_eq!((m.read(0x8000), m.read(0xA000), m.read(0xE000)), (2, 5, 7));
+        m.write(0xF000, 1);
+        assert_eq!(m.mirroring(), Mirroring::Horizontal);
+    }
+}
@@ -1,16 +1,24 @@
 //! NES mappers for PRG/CHR memory mapping and nametable mirroring.
 //!
 //! - **Mapper 0** ([NROM](https://www.nesdev.org/wiki/NROM)): no bank switching.
-//! - **Mapper 1** ([MMC1](https://www.nesdev.org/wiki/MMC1)): shift-register bank switching.
+//! - **Mapper 1** ([MMC1](https://www.nesdev.org/wiki/MMC1)): shift-register PRG and CHR bank switching.
+//! - **Mapper 2** ([UxROM](https://www.nesdev.org/wiki/UxROM)): 16 KiB PRG switching with a fixed last bank.
 //! - **Mapper 4** ([MMC3](https://www.nesdev.org/wiki/MMC3)): bank switching, switchable mirroring, PRG RAM, scanline IRQ.
+//! - **Mapper 7** ([AxROM](https://www.nesdev.org/wiki/AxROM)): 32 KiB PRG switching, register-selected one-screen mirroring.
+//! - **Mapper 9** ([MMC2](https://www.nesdev.org/wiki/MMC2)): 8 KiB PRG switching, CHR banks picked by FD/FE latches.
+//! - **Mapper 10** ([MMC4](https://www.nesdev.org/wiki/MMC4)): 16 KiB PRG switching, CHR banks picked by FD/FE latches.
+//! - **Mapper 11** ([Color Dreams](https://www.nesdev.org/wiki/Color_Dreams)): 32 KiB PRG + 8 KiB CHR from one register.
+//! - **Mapper 19** ([Namco 163](https://www.nesdev.org/wiki/Namco_163)): 8 KiB PRG / 1 KiB CHR banks, banked nametables, CPU-cycle IRQ (no audio yet).
+//! - **Mapper 34** ([BNROM](https://www.nesdev.org/wiki/BNROM) / [NINA-001](https://www.nesdev.org/wiki/NINA-001)): 32 KiB PRG switching; NINA-001 adds 4 KiB CHR banks.
+//! - **Mapper 69** ([Sunsoft FME-7](https://www.nesdev.org/wiki/Sunsoft_FME-7)): 8 KiB PRG / 1 KiB CHR banks, CPU-cycle IRQ, Sunsoft 5B square channels.
 //!
 //! Mirroring controls how the PPU maps the four logical nametables ($2000, $2400, $2800, $2C00) to
-//! 2 KiB of internal RAM. See [PPU nametables](https://www.nesdev.org/wiki/PPU_nametables#Nametable_mirroring).
+//! 2 KiB of internal RAM (4 KiB with four-screen cartridge VRAM). See [PPU nametables](https://www.nesdev.org/wiki/PPU_nametables#Nametable_mirroring).
 
 /// Nametable mirroring: Horizontal = left/right pairs share data (vertical mirroring in NESdev terms);
 /// Vertical = top/bottom pairs share data (horizontal mirroring). One-screen = all four logical
 /// nametables map to the same 1 KiB (lower or upper half of the 2 KiB RAM). See Mirroring.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Mirroring {
     Horizontal,
     Vertical,
@@ -18,10 +26,22 @@ pub enum Mirroring {
     OneScreenLower,
     /// All four nametables use the second 1 KiB ($2400–$27FF).
     OneScreenUpper,
+    /// Each logical nametable has its own 1 KiB: the cartridge adds 2 KiB of VRAM next to the
+    /// console's (Gauntlet, Rad Racer II). Set by iNES flag 6 bit 3.
+    FourScreen,
 }
 
+pub mod chr_latch;
 pub mod mapper;
 
 pub mod mapper0;
 pub mod mapper1;
+pub mod mapper2;
 pub mod mapper4;
+pub mod mapper7;
+pub mod mapper9;
+pub mod mapper10;
+pub mod mapper11;
+pub mod mapper19;
+pub mod mapper34;
+pub mod mapper69;
@@ -1,7 +1,7 @@
 //! NES cartridge loading and mapper support.
 //!
 //! - **cartridge**: Loads iNES (.nes) files, holds PRG/CHR and mapper.
-//! - **mapper**: NROM (0), MMC1 (1), MMC3 (4); PRG/CHR bank switching and nametable mirroring.
+//! - **mapper**: NROM (0), MMC1 (1), MMC3 (4), MMC4 (10), Color Dreams (11), Namco 163 (19), BNROM/NINA-001 (34), FME-7 (69); PRG/CHR bank switching and nametable mirroring.
 
 pub mod cartridge;
 pub mod mapper;
\ No newline at end of file
@@ -0,0 +1,313 @@
+//! Command-line options for the `elaris` binary.
+//!
+//! Hand-rolled parser: `elaris [OPTIONS] [path/to/game.nes]`. A bare ROM path keeps working; bad
+//! arguments produce an error message followed by the usage text.
+
+use std::fmt;
+use std::path::PathBuf;
+
+use elaris::display::{ColorAdjust, CrtFilter, DisplayConfig, FrameSkip, Overscan, PixelAspect};
+use elaris::region::Region;
+
+/// Usage text printed for `--help` and after argument errors.
+pub const USAGE: &str = "\
+Usage: elaris [OPTIONS] [path/to/game.nes]
+
+Runs an iNES ROM (default: test/nestest.nes).
+
+Options:
+  --region <ntsc|pal>      Console region (default: from the ROM header, else NTSC)
+  --scale <1-6>            Integer window scale (default: 3)
+  --aspect <square|ntsc>   Square pixels or NTSC 8:7 pixels (default: square)
+  --fullscreen             Borderless window stretched to fit the screen
+  --overscan <0-16>        Hide this many pixels at each edge, on screen and in screenshots
+  --screenshot-full        Capture the full 256×240 frame regardless of --overscan
+  --brightness <-100-100>  Picture brightness offset in percent (default: 0)
+  --contrast <0-200>       Picture contrast in percent (default: 100)
+  --hue <-180-180>         Picture hue rotation in degrees (default: 0)
+  --saturation <0-200>     Picture saturation in percent (default: 100)
+  --palette <FILE>         Load colors from a .pal file (64 or 512 colors)
+  --scanlines <0-100>      Enable the CRT filter with this scanline darkness (default: 50)
+  --crt-blur               Enable the CRT filter with horizontal phosphor blur
+  --frame-skip <N|auto>    Present every N+1th frame, or skip when behind (default: 0)
+  --pacing <sleep|audio|window>
+                           Frame timing source (default: sleep)
+  --mute                   Disable audio output
+  --volume <0-100>         Output volume in percent (default: 100)
+  --save-dir <DIR>         Directory for files written by the frontend (default: .)
+  --record-audio <FILE>    Write all emulated audio to a 16-bit WAV file on exit
+  --headless               Run without a window or audio device
+  --frames <N>             Stop after N frames
+  --port2 <none|keyboard|paddle|powerpad>
+                           Device on port 2 / expansion port (default: none)
+  -v, --verbose            Log debug messages to stderr (ELARIS_LOG=<level> overrides)
+  -h, --help               Print this help and exit";
+
+/// Device plugged into port 2 / the expansion port.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Port2Choice {
+    #[default]
+    None,
+    /// Family BASIC keyboard, driven from the host keyboard.
+    Keyboard,
+    /// Arkanoid paddle, driven from the mouse.
+    Paddle,
+    /// Power Pad, driven from the host keyboard.
+    PowerPad,
+}
+
+/// What the windowed frame loop waits on between frames.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Pacing {
+    /// Sleep out the rest of each frame's wall-clock budget.
+    #[default]
+    Sleep,
+    /// Run frames whenever the audio queue runs low, so playback never starves.
+    Audio,
+    /// Let minifb's frame limiter (`set_target_fps`) block in `update_with_buffer`. A timer, not
+    /// the display's vsync.
+    Window,
+}
+
+/// Parsed frontend options.
+#[derive(Debug)]
+pub struct Options {
+    pub rom: String,
+    /// Region override; `None` uses the ROM header's region, or NTSC.
+    pub region: Option<Region>,
+    pub display: DisplayConfig,
+    pub color: ColorAdjust,
+    /// `.pal` file replacing the built-in colors.
+    pub palette: Option<String>,
+    pub crt: CrtFilter,
+    pub fullscreen: bool,
+    /// Screenshots ignore the overscan crop.
+    pub screenshot_full: bool,
+    pub frame_skip: FrameSkip,
+    pub pacing: Pacing,
+    pub mute: bool,
+    /// Output volume, 0.0–1.0.
+    pub volume: f32,
+    pub save_dir: PathBuf,
+    pub record_audio: Option<PathBuf>,
+    pub headless: bool,
+    pub frames: Option<u64>,
+    pub port2: Port2Choice,
+    pub verbose: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            rom: "test/nestest.nes".to_string(),
+            region: None,
+            display: DisplayConfig::default(),
+            color: ColorAdjust::default(),
+            palette: None,
+            crt: CrtFilter::default(),
+            fullscreen: false,
+            screenshot_full: false,
+            frame_skip: FrameSkip::default(),
+            pacing: Pacing::Sleep,
+            mute: false,
+            volume: 1.0,
+            save_dir: PathBuf::from("."),
+            record_audio: None,
+            headless: false,
+            frames: None,
+            port2: Port2Choice::None,
+            verbose: false,
+        }
+    }
+}
+
+/// Result of parsing: run with options, or print help and exit.
+#[derive(Debug)]
+pub enum Command {
+    Run(Box<Options>),
+    Help,
+}
+
+/// Argument error; displayed above the usage text.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ArgError {
+    MissingValue(&'static str),
+    InvalidValue { flag: &'static str, value: String },
+    UnknownFlag(String),
+    ExtraPositional(String),
+}
+
+impl fmt::Display for ArgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgError::MissingValue(flag) => write!(f, "{flag} requires a value"),
+            ArgError::InvalidValue { flag, value } => {
+                write!(f, "invalid value `{value}` for {flag}")
+            }
+            ArgError::UnknownFlag(flag) => write!(f, "unknown option `{flag}`"),
+            ArgError::ExtraPositional(arg) => {
+                write!(f, "unexpected argument `{arg}` (only one ROM path is accepted)")
+            }
+        }
+    }
+}
+
+/// Parse arguments (excluding the program name).
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Command, ArgError> {
+    let mut opts = Options::default();
+    let mut rom = None;
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--region" => {
+                opts.region = Some(match value(&mut args, "--region")?.as_str() {
+                    "ntsc" => Region::Ntsc,
+                    "pal" => Region::Pal,
+                    other => return Err(invalid("--region", other)),
+                });
+            }
+            "--scale" => {
+                let v = value(&mut args, "--scale")?;
+                let scale = v.parse().ok().filter(|s| (1..=6).contains(s));
+                let scale = scale.ok_or_else(|| invalid("--scale", &v))?;
+                opts.display = DisplayConfig {
+                    scale,
+                    ..opts.display
+                };
+            }
+            "--aspect" => {
+                opts.display.aspect = match value(&mut args, "--aspect")?.as_str() {
+                    "square" => PixelAspect::Square,
+                    "ntsc" => PixelAspect::Ntsc,
+                    other => return Err(invalid("--aspect", other)),
+                };
+            }
+            "--overscan" => {
+                let px = ranged(&mut args, "--overscan", 0..=16)?;
+                opts.display.overscan = Overscan::uniform(px as usize);
+            }
+            "--screenshot-full" => opts.screenshot_full = true,
+            "--brightness" => {
+                let percent = ranged(&mut args, "--brightness", -100..=100)?;
+                opts.color.brightness = percent as f32 / 100.0;
+            }
+            "--contrast" => {
+                opts.color.contrast = ranged(&mut args, "--contrast", 0..=200)? as f32 / 100.0;
+            }
+            "--hue" => opts.color.hue = ranged(&mut args, "--hue", -180..=180)? as f32,
+            "--saturation" => {
+                opts.color.saturation = ranged(&mut args, "--saturation", 0..=200)? as f32 / 100.0;
+            }
+            "--palette" => opts.palette = Some(value(&mut args, "--palette")?),
+            "--scanlines" => {
+                let percent = ranged(&mut args, "--scanlines", 0..=100)?;
+                opts.crt.scanline_intensity = percent as f32 / 100.0;
+                opts.crt.enabled = true;
+            }
+            "--crt-blur" => {
+                opts.crt.blur = true;
+                opts.crt.enabled = true;
+            }
+            "--fullscreen" => opts.fullscreen = true,
+            "--frame-skip" => {
+                let v = value(&mut args, "--frame-skip")?;
+                opts.frame_skip = match v.as_str() {
+                    "auto" => FrameSkip::Auto,
+                    n => FrameSkip::Fixed(n.parse().map_err(|_| invalid("--frame-skip", &v))?),
+                };
+            }
+            "--pacing" => {
+                opts.pacing = match value(&mut args, "--pacing")?.as_str() {
+                    "sleep" => Pacing::Sleep,
+                    "audio" => Pacing::Audio,
+                    "window" => Pacing::Window,
+                    other => return Err(invalid("--pacing", other)),
+                };
+            }
+            "--mute" => opts.mute = true,
+            "--volume" => {
+                let v = value(&mut args, "--volume")?;
+                let percent: u8 = v
+                    .parse()
+                    .ok()
+                    .filter(|p| *p <= 100)
+                    .ok_or_else(|| invalid("--volume", &v))?;
+                opts.volume = percent as f32 / 100.0;
+            }
+            "--save-dir" => opts.save_dir = PathBuf::from(value(&mut args, "--save-dir")?),
+            "--record-audio" => {
+                opts.record_audio = Some(PathBuf::from(value(&mut args, "--record-audio")?));
+            }
+            "--headless" => opts.headless = true,
+            "--frames" => {
+                let v = value(&mut args, "--frames")?;
+                opts.frames = Some(v.parse().map_err(|_| invalid("--frames", &v))?);
+            }
+            "--port2" => {
+                opts.port2 = match value(&mut args, "--port2")?.as_str() {
+                    "none" => Port2Choice::None,
+                    "keyboard" => Port2Choice::Keyboard,
+                    "paddle" => Port2Choice::Paddle,
+                    "powerpad" => Port2Choice::PowerPad,
+                    other => return Err(invalid("--port2", other)),
+                };
+            }
+            "-v" | "--verbose" => opts.verbose = true,
+            flag if flag.starts_with('-') => return Err(ArgError::UnknownFlag(arg)),
+            _ if rom.is_some() => return Err(ArgError::ExtraPositional(arg)),
+            _ => rom = Some(arg),
+        }
+    }
+
+    if let Some(rom) = rom {
+        opts.rom = rom;
+    }
+    Ok(Command::Run(Box::new(opts)))
+}
+
+fn value<I: Iterator<Item = String>>(args: &mut I, flag: &'static str) -> Result<String, ArgError> {
+    args.next().ok_or(ArgError::MissingValue(flag))
+}
+
+/// Next argument as an integer within `range`.
+fn ranged<I: Iterator<Item = String>>(
+    args: &mut I,
+    flag: &'static str,
+    range: std::ops::RangeInclusive<i32>,
+) -> Result<i32, ArgError> {
+    let v = value(args, flag)?;
+    v.parse()
+        .ok()
+        .filter(|n| range.contains(n))
+        .ok_or_else(|| invalid(flag, &v))
+}
+
+fn invalid(flag: &'static str, value: &str) -> ArgError {
+    ArgError::InvalidValue {
+        flag,
+        value: value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pacing(args: &[&str]) -> Result<Pacing, ArgError> {
+        match parse(args.iter().map(|arg| arg.to_string()))? {
+            Command::Run(opts) => Ok(opts.pacing),
+            Command::Help => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn pacing_defaults_to_sleep_and_parses_each_mode() {
+        assert_eq!(pacing(&[]), Ok(Pacing::Sleep));
+        assert_eq!(pacing(&["--pacing", "audio"]), Ok(Pacing::Audio));
+        assert_eq!(pacing(&["--pacing", "window"]), Ok(Pacing::Window));
+        assert_eq!(pacing(&["--pacing", "vsync"]), Err(invalid("--pacing", "vsync")));
+        assert_eq!(pacing(&["--pacing", "vblank"]), Err(invalid("--pacing", "vblank")));
+    }
+}
@@ -9,10 +9,34 @@
 //!    Order: A, B, Select, Start, Up, Down, Left, Right. Unused bits read as open bus (we use $40).
 //!
 //! Only the low 3 bits of the write are latched (controller port latch + expansion); we use bit 0
-//! for strobe. D0 is the data line for the standard controller; $4017 (port 2) is not implemented.
+//! for strobe. D0 is the data line for the standard controller.
+//!
+//! Other devices (keyboard, paddle, …) implement [`InputDevice`] and are plugged into the bus's
+//! port 2 / expansion slot, read through $4017.
+
+use std::any::Any;
+
+/// A device on controller port 2 or the expansion port. It sees every $4016 write (OUT0–OUT2 in
+/// bits 0–2) and answers $4017 reads with the data bits it drives (D0–D4); the bus fills the rest
+/// with open bus. See [Input devices](https://www.nesdev.org/wiki/Input_devices).
+pub trait InputDevice: Any {
+    /// $4016 write: bit 0 = OUT0 (strobe), bits 1–2 = OUT1/OUT2 (expansion port only).
+    fn write(&mut self, data: u8);
+    /// $4017 read: data lines driven by the device (bits 0–4).
+    fn read(&mut self) -> u8;
+    /// Copy of this device with its current state, so a `NesBus` can be cloned.
+    fn clone_box(&self) -> Box<dyn InputDevice>;
+}
+
+impl Clone for Box<dyn InputDevice> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
 
 /// Represents a single NES controller on port 1 ($4016).
 /// Hardware: 4021 8-bit parallel-in/serial-out shift register; CLK on read, parallel load on strobe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Controller {
     /// Current button states. Bit order: 0=A, 1=B, 2=Select, 3=Start, 4=Up, 5=Down, 6=Left, 7=Right.
     /// 1 = pressed. Latched into `shift` when game writes 1 to $4016 (strobe).
@@ -21,6 +45,12 @@ pub struct Controller {
     pub shift: u8,
 }
 
+impl Default for Controller {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Controller {
     /// Create a new controller with no buttons pressed.
     pub fn new() -> Self {
@@ -0,0 +1,190 @@
+//! Debugger breakpoints with optional register/memory conditions.
+//!
+//! A [`Breakpoint`] fires when the CPU is about to execute an instruction and its [`Condition`]
+//! holds, e.g. "PC = $C123 and A = $FF" or "$0300 is nonzero" (any PC). Breakpoints are checked
+//! after each `step`, so a hit means the instruction at `cpu.pc` has not run yet. Memory is read
+//! with [`Bus::peek`] so checking never disturbs PPU/APU registers.
+
+use crate::bus::Bus;
+use crate::cpu::cpu::CPU;
+
+/// CPU register a [`Condition`] can compare. `Pc` compares all 16 bits; the rest are 8-bit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Register {
+    A,
+    X,
+    Y,
+    Sp,
+    Pc,
+    /// Processor status (P).
+    Status,
+}
+
+/// Comparison applied as `current <op> value`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compare {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// `current & value != 0` (test flag bits or masks).
+    AnyBits,
+}
+
+impl Compare {
+    fn apply(self, current: u16, value: u16) -> bool {
+        match self {
+            Compare::Eq => current == value,
+            Compare::Ne => current != value,
+            Compare::Lt => current < value,
+            Compare::Le => current <= value,
+            Compare::Gt => current > value,
+            Compare::Ge => current >= value,
+            Compare::AnyBits => current & value != 0,
+        }
+    }
+}
+
+/// Predicate over CPU registers and memory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Condition {
+    /// Always true (plain address breakpoint).
+    Always,
+    Register {
+        reg: Register,
+        cmp: Compare,
+        value: u16,
+    },
+    Memory {
+        addr: u16,
+        cmp: Compare,
+        value: u8,
+    },
+    /// True when every inner condition is true.
+    All(Vec<Condition>),
+    /// True when at least one inner condition is true.
+    Any(Vec<Condition>),
+}
+
+impl Condition {
+    pub fn register(reg: Register, cmp: Compare, value: u16) -> Self {
+        Condition::Register { reg, cmp, value }
+    }
+
+    pub fn memory(addr: u16, cmp: Compare, value: u8) -> Self {
+        Condition::Memory { addr, cmp, value }
+    }
+
+    /// PC equals `pc`.
+    pub fn pc(pc: u16) -> Self {
+        Self::register(Register::Pc, Compare::Eq, pc)
+    }
+
+    /// 8-bit register equals `value`, e.g. `Condition::reg_eq(Register::A, 0xFF)`.
+    pub fn reg_eq(reg: Register, value: u8) -> Self {
+        Self::register(reg, Compare::Eq, value as u16)
+    }
+
+    /// Memory at `addr` equals `value`.
+    pub fn mem_eq(addr: u16, value: u8) -> Self {
+        Self::memory(addr, Compare::Eq, value)
+    }
+
+    /// Memory at `addr` is nonzero.
+    pub fn mem_nonzero(addr: u16) -> Self {
+        Self::memory(addr, Compare::Ne, 0)
+    }
+
+    /// Both `self` and `other` must hold.
+    pub fn and(self, other: Condition) -> Self {
+        match (self, other) {
+            (Condition::Always, c) | (c, Condition::Always) => c,
+            (Condition::All(mut v), c) => {
+                v.push(c);
+                Condition::All(v)
+            }
+            (a, b) => Condition::All(vec![a, b]),
+        }
+    }
+
+    /// Either `self` or `other` must hold.
+    pub fn or(self, other: Condition) -> Self {
+        match (self, other) {
+            (Condition::Any(mut v), c) => {
+                v.push(c);
+                Condition::Any(v)
+            }
+            (a, b) => Condition::Any(vec![a, b]),
+        }
+    }
+
+    /// Evaluate against the CPU's current registers and memory.
+    pub fn holds<B: Bus>(&self, cpu: &mut CPU<B>) -> bool {
+        match self {
+            Condition::Always => true,
+            Condition::Register { reg, cmp, value } => {
+                let current = match reg {
+                    Register::A => cpu.a as u16,
+                    Register::X => cpu.x as u16,
+                    Register::Y => cpu.y as u16,
+                    Register::Sp => cpu.sp as u16,
+                    Register::Pc => cpu.pc,
+                    Register::Status => cpu.status as u16,
+                };
+                cmp.apply(current, *value)
+            }
+            Condition::Memory { addr, cmp, value } => {
+                cmp.apply(cpu.bus.peek(*addr) as u16, *value as u16)
+            }
+            Condition::All(conds) => conds.iter().all(|c| c.holds(cpu)),
+            Condition::Any(conds) => conds.iter().any(|c| c.holds(cpu)),
+        }
+    }
+}
+
+/// A breakpoint: an optional PC plus a condition. Build with [`Breakpoint::at`] or
+/// [`Breakpoint::when`], then narrow with [`Breakpoint::and`]:
+///
+/// ```
+/// use elaris::cpu::breakpoint::{Breakpoint, Condition, Register};
+///
+/// // Break at $C123, but only when A = $FF.
+/// let bp = Breakpoint::at(0xC123).and(Condition::reg_eq(Register::A, 0xFF));
+/// // Break anywhere once $0300 becomes nonzero.
+/// let watch = Breakpoint::when(Condition::mem_nonzero(0x0300));
+/// # let _ = (bp, watch);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Breakpoint {
+    pub condition: Condition,
+    /// Disabled breakpoints are kept but never fire.
+    pub enabled: bool,
+}
+
+impl Breakpoint {
+    /// Fire when PC reaches `pc`.
+    pub fn at(pc: u16) -> Self {
+        Self::when(Condition::pc(pc))
+    }
+
+    /// Fire whenever `condition` holds, at any PC.
+    pub fn when(condition: Condition) -> Self {
+        Self {
+            condition,
+            enabled: true,
+        }
+    }
+
+    /// Additionally require `condition`.
+    pub fn and(mut self, condition: Condition) -> Self {
+        self.condition = self.condition.and(condition);
+        self
+    }
+
+    /// True if enabled and the condition holds for `cpu`.
+    pub fn is_hit<B: Bus>(&self, cpu: &mut CPU<B>) -> bool {
+        self.enabled && self.condition.holds(cpu)
+    }
+}
@@ -8,26 +8,100 @@
 //!
 //! - **Reset**: [Reset vector](https://www.nesdev.org/wiki/CPU_memory_map#Vectors) at $FFFC–$FFFD;
 //!   [power-up state](https://www.nesdev.org/wiki/CPU_power_up_state) (SP=$FD, P=$34; I and U set).
-//! - **NMI**: [NMI](https://www.nesdev.org/wiki/NMI) triggered by PPU vblank; polled before each
-//!   instruction. Vectors at $FFFA–$FFFB.
+//! - **NMI/IRQ**: [NMI](https://www.nesdev.org/wiki/NMI) triggered by PPU vblank, IRQ by mappers
+//!   and the APU (level-triggered).
+//!   Lines are [polled](https://www.nesdev.org/wiki/CPU_interrupts) before the last cycle of each
+//!   instruction and serviced before the next one. Vectors at $FFFA–$FFFB (NMI), $FFFE–$FFFF (IRQ).
 //! - **Stack**: $0100–$01FF; SP is 8-bit, stack grows downward. BRK/IRQ push P, PC (high then low).
 //! - **JAM**: Opcodes $02, $12, $22, … ($x2) lock the CPU; we set `halted` and stop (used by nestest).
 
-use core::panic;
-
 use crate::{
     bus::Bus,
+    cpu::breakpoint::Breakpoint,
+    cpu::disasm,
+    cpu::profile::CpuStats,
     cpu::flags::{
         FLAG_BREAK, FLAG_CARRY, FLAG_DECIMAL, FLAG_INTERRUPT_DISABLE, FLAG_NEGATIVE, FLAG_OVERFLOW,
         FLAG_UNUSED, FLAG_ZERO,
     },
 };
 
-use ansi_term::Colour::Red;
+/// What the CPU does when it fetches an opcode this emulator doesn't implement (the unstable
+/// unofficial opcodes XAA/$8B and LXA/$AB).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IllegalOpcodeAction {
+    /// Panic with the opcode and address; useful while developing the CPU core.
+    Panic,
+    /// Log an error and set `halted`, so the frontend can stop and report it.
+    #[default]
+    Halt,
+    /// Log a warning, skip the opcode and its operand bytes, and continue (2 cycles).
+    NopAndLog,
+}
+
+/// Source of an interrupt sequence. BRK pushes P with B set; IRQ and NMI push it with B clear.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Interrupt {
+    Brk,
+    Irq,
+    Nmi,
+}
+
+/// CPU state before an instruction executes, passed to the tracer set with `CPU::set_tracer`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub pc: u16,
+    pub opcode: u8,
+    pub a: u8,
+    pub x: u8,
+    pub y: u8,
+    pub sp: u8,
+    pub status: u8,
+    pub cycles: usize,
+    /// The same instruction as a nestest.log line (see `CPU::trace_line`).
+    pub line: String,
+}
+
+/// Why `CPU::step_until_breakpoint` stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepOutcome {
+    /// A breakpoint fired; the instruction at this PC has not executed yet.
+    BreakpointHit(u16),
+    /// A JAM opcode halted the CPU.
+    Halted,
+    /// The cycle budget ran out.
+    CycleLimit,
+}
+
+/// The register set as one value, for debuggers and save states. See `CPU::snapshot`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CpuState {
+    pub a: u8,
+    pub x: u8,
+    pub y: u8,
+    pub sp: u8,
+    pub pc: u16,
+    pub status: u8,
+    pub cycles: usize,
+    pub halted: bool,
+}
+
+type TraceFn = dyn FnMut(&TraceEntry);
+
+/// Optional per-instruction callback. A cloned CPU (e.g. a save state) starts without one.
+#[derive(Default)]
+struct Tracer(Option<Box<TraceFn>>);
+
+impl Clone for Tracer {
+    fn clone(&self) -> Self {
+        Self(None)
+    }
+}
 
 /// 6502 CPU with generic bus for memory and I/O (PPU, APU, cartridge, controller).
 /// Registers: A (accumulator), X, Y (index); SP (stack pointer, $0100–$01FF); PC (program counter);
 /// P (status: N V - B D I Z C). Cycles: total CPU cycles elapsed (for nestest / timing).
+#[derive(Clone)]
 pub struct CPU<B: Bus> {
     pub a: u8,
     pub x: u8,
@@ -39,11 +113,61 @@ pub struct CPU<B: Bus> {
     pub bus: B,
     /// True when a JAM (illegal) opcode ($02, $12, $22, …) has been executed; CPU stops.
     pub halted: bool,
+    /// Debugger breakpoints, checked after every `step`. See `cpu::breakpoint`.
+    pub breakpoints: Vec<Breakpoint>,
+    /// Index into `breakpoints` of the one that fired on the last `step`, if any. The instruction
+    /// at `pc` has not executed yet; calling `step` again runs it.
+    pub breakpoint_hit: Option<usize>,
+    /// Handling of opcodes that aren't implemented. Defaults to `Halt`.
+    pub on_illegal: IllegalOpcodeAction,
+    /// Honor the D flag in ADC/SBC like an NMOS 6502. The 2A03 has no decimal mode, so this is
+    /// off by default; turn it on only to run the core as a generic 6502.
+    pub decimal_enabled: bool,
+    /// Called before every instruction when set. See `set_tracer`.
+    tracer: Tracer,
+    /// NMI edge seen at the last poll; serviced at the start of the next `step`.
+    nmi_pending: bool,
+    /// IRQ line level at the last poll. Level-triggered: it stays high until the source is
+    /// acknowledged, so a handler that returns without acknowledging is entered again.
+    irq_line: bool,
+    /// IRQ line was asserted and I was clear at the last poll; serviced at the next `step`.
+    irq_pending: bool,
+    /// Cycles of the current `step` already passed to `bus.tick` (interrupt sequences tick
+    /// part-way through to sample NMI for hijacking).
+    ticked_cycles: usize,
+    stats: CpuStats,
 }
 
 impl<B: Bus> CPU<B> {
+    /// Create a CPU attached to `bus` in the power-up register state: A, X, Y = 0, SP = $FD,
+    /// P = $24 (I and U set). PC stays 0 until `reset` loads the reset vector.
+    pub fn new(bus: B) -> Self {
+        Self {
+            a: 0,
+            x: 0,
+            y: 0,
+            sp: 0xFD,
+            pc: 0,
+            status: FLAG_INTERRUPT_DISABLE | FLAG_UNUSED,
+            cycles: 0,
+            bus,
+            halted: false,
+            breakpoints: Vec::new(),
+            breakpoint_hit: None,
+            on_illegal: IllegalOpcodeAction::default(),
+            decimal_enabled: false,
+            tracer: Tracer::default(),
+            nmi_pending: false,
+            irq_line: false,
+            irq_pending: false,
+            ticked_cycles: 0,
+            stats: CpuStats::default(),
+        }
+    }
+
     /// Reset: load PC from reset vector $FFFC–$FFFD (supplied by cartridge). SP=$FD, P=$24 (I and U
-    /// set). A, X, Y = 0. Reset takes 7 cycles on real hardware (we set cycles = 7).
+    /// set). A, X, Y = 0. Reset takes 7 cycles on real hardware; the bus is ticked through them so
+    /// the PPU and APU start in step with `cycles`.
     /// See NESdev "Reset vector", "CPU power up state".
     pub fn reset(&mut self) {
         let lo = self.bus.read(0xFFFC) as u16;
@@ -58,32 +182,173 @@ impl<B: Bus> CPU<B> {
         self.x = 0;
         self.y = 0;
         self.halted = false;
+        self.nmi_pending = false;
+        self.irq_line = false;
+        self.irq_pending = false;
 
+        self.bus.tick(7);
         self.cycles = 7;
     }
 
-    /// Execute one instruction: check NMI, fetch opcode at PC, execute (updating cycles), then
-    /// tick the bus by the instruction's cycle count (PPU advances 3×, APU 1× per CPU cycle).
-    pub fn step(&mut self) {
+    /// Execute one instruction (or one interrupt sequence) and tick the bus by its cycle count
+    /// (PPU advances 3×, APU 1× per CPU cycle).
+    ///
+    /// Interrupt lines are polled before the instruction's last cycle, as on the 6502: an
+    /// interrupt raised during the last cycle waits one more instruction, and CLI/SEI/PLP change I
+    /// after the poll, so e.g. `CLI` still runs the next instruction before a pending IRQ. A
+    /// pending interrupt is serviced (7 cycles, also ticked) as its own `step`.
+    ///
+    /// Returns the CPU cycles this step took, including DMA stalls (7 for an interrupt
+    /// sequence, 0 when halted).
+    pub fn step(&mut self) -> usize {
         if self.halted {
-            return;
+            return 0;
         }
 
-        // NMI is level-sensitive; we poll once per instruction. If PPU raised NMI (vblank + enable),
-        // push PC and P, set PC from $FFFA–$FFFB, set I. See NMI.
-        if self.bus.poll_nmi() {
+        let prev_cycles = self.cycles;
+        self.ticked_cycles = 0;
+        if self.nmi_pending {
+            // Push PC and P, set PC from $FFFA–$FFFB, set I. See NMI.
+            self.nmi_pending = false;
             self.nmi();
-        }
-        // IRQ (e.g. MMC3 scanline IRQ): poll and handle if I flag clear.
-        if self.bus.poll_irq() {
+            let rest = self.cycles - prev_cycles - self.ticked_cycles;
+            self.tick_bus(rest);
+        } else if self.irq_pending {
+            // IRQ (e.g. MMC3 scanline IRQ), accepted at the last poll because I was clear.
+            self.irq_pending = false;
+            self.irq_line = false;
             self.irq();
+            let rest = self.cycles - prev_cycles - self.ticked_cycles;
+            self.tick_bus(rest);
+        } else {
+            if self.tracer.0.is_some() {
+                self.run_tracer();
+            }
+            let interrupts_disabled = self.status & FLAG_INTERRUPT_DISABLE != 0;
+            let opcode = self.fetch_byte();
+            self.execute_opcode(opcode);
+            self.stats.record_instruction(opcode);
+            let cycle_diff = self.cycles - prev_cycles - self.ticked_cycles;
+
+            self.tick_bus(cycle_diff.saturating_sub(1));
+            // CLI, SEI and PLP update I in their last cycle, after the poll.
+            let irq_masked = match opcode {
+                0x58 | 0x78 | 0x28 => interrupts_disabled,
+                _ => self.status & FLAG_INTERRUPT_DISABLE != 0,
+            };
+            self.poll_interrupts(irq_masked);
+            self.tick_bus(cycle_diff.min(1));
         }
 
-        let opcode = self.fetch_byte();
-        let prev_cycles = self.cycles;
-        self.execute_opcode(opcode);
-        let cycle_diff = self.cycles - prev_cycles;
-        self.bus.tick(cycle_diff);
+        let cycles = self.cycles - prev_cycles;
+        self.stats.total_cycles += cycles as u64;
+        self.breakpoint_hit = self.check_breakpoints();
+        cycles
+    }
+
+    /// An NMI or IRQ was accepted at the last poll, so the next `step` runs the interrupt
+    /// sequence instead of an instruction.
+    pub fn interrupt_pending(&self) -> bool {
+        self.nmi_pending || self.irq_pending
+    }
+
+    /// Copy out the registers, cycle count and halt flag.
+    pub fn snapshot(&self) -> CpuState {
+        CpuState {
+            a: self.a,
+            x: self.x,
+            y: self.y,
+            sp: self.sp,
+            pc: self.pc,
+            status: self.status,
+            cycles: self.cycles,
+            halted: self.halted,
+        }
+    }
+
+    /// Load registers from a `snapshot`. The bus and any pending interrupt are left as they are.
+    pub fn restore(&mut self, s: CpuState) {
+        self.a = s.a;
+        self.x = s.x;
+        self.y = s.y;
+        self.sp = s.sp;
+        self.pc = s.pc;
+        self.status = s.status;
+        self.cycles = s.cycles;
+        self.halted = s.halted;
+    }
+
+    /// Instruction/cycle totals since power-on or the last `reset_stats`.
+    pub fn stats(&self) -> &CpuStats {
+        &self.stats
+    }
+
+    /// Zero the profiling counters. See `CpuStats::reset`.
+    pub fn reset_stats(&mut self) {
+        self.stats.reset();
+    }
+
+    /// Turn the per-opcode histogram on (starting from zero) or off.
+    pub fn set_opcode_histogram(&mut self, enabled: bool) {
+        self.stats.opcode_counts = enabled.then(|| Box::new([0; 256]));
+    }
+
+    /// Advance the bus and record that these cycles of the current step have been ticked. DMA
+    /// stalls the bus inserted while ticking count toward `cycles` too.
+    fn tick_bus(&mut self, cycles: usize) {
+        self.bus.tick(cycles);
+        let stall = self.bus.take_stall_cycles();
+        self.cycles += stall;
+        self.ticked_cycles += cycles + stall;
+    }
+
+    /// Sample the NMI edge and IRQ line. See CPU_interrupts ("Detailed interrupt behavior").
+    fn poll_interrupts(&mut self, irq_masked: bool) {
+        if self.bus.poll_nmi() {
+            self.nmi_pending = true;
+        }
+        self.irq_line = self.bus.poll_irq();
+        self.irq_pending = self.irq_line && !irq_masked;
+    }
+
+    /// Add a breakpoint and return its index.
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        self.breakpoints.push(breakpoint);
+        self.breakpoints.len() - 1
+    }
+
+    /// Remove and return the breakpoint at `index`; later breakpoints move down one index.
+    pub fn remove_breakpoint(&mut self, index: usize) -> Option<Breakpoint> {
+        (index < self.breakpoints.len()).then(|| self.breakpoints.remove(index))
+    }
+
+    /// Step until a breakpoint fires, the CPU halts, or at least `max_cycles` have run. The
+    /// first instruction always executes, so calling this again resumes from a breakpoint.
+    pub fn step_until_breakpoint(&mut self, max_cycles: usize) -> StepOutcome {
+        let mut spent = 0;
+        while !self.halted {
+            if spent >= max_cycles {
+                return StepOutcome::CycleLimit;
+            }
+            spent += self.step();
+            if !self.halted && self.breakpoint_hit.is_some() {
+                return StepOutcome::BreakpointHit(self.pc);
+            }
+        }
+        StepOutcome::Halted
+    }
+
+    /// Index of the first breakpoint whose condition holds for the current state.
+    fn check_breakpoints(&mut self) -> Option<usize> {
+        if self.breakpoints.is_empty() {
+            return None;
+        }
+        // Conditions read registers and peek memory through `self`, so take the list out while
+        // evaluating.
+        let breakpoints = std::mem::take(&mut self.breakpoints);
+        let hit = breakpoints.iter().position(|bp| bp.is_hit(self));
+        self.breakpoints = breakpoints;
+        hit
     }
 
     /// JAM: undocumented opcodes that lock the CPU ($02, $12, $22, $32, $42, $52, $62, $72,
@@ -99,6 +364,100 @@ impl<B: Bus> CPU<B> {
         byte
     }
 
+    /// Indexed stores (absolute,X/Y and (indirect),Y) always read from the base address's page
+    /// with the indexed low byte before the high byte is fixed up, then write; that is why they
+    /// take a fixed cycle count. The read is discarded, but registers like $2007 see it.
+    fn dummy_read_before_fixup(&mut self, base: u16, final_addr: u16) {
+        self.bus.read((base & 0xFF00) | (final_addr & 0x00FF));
+    }
+
+    /// Read the operand of a read-modify-write instruction (INC, ASL, SLO, …). The 6502 writes
+    /// the unmodified value back while it computes the result, so registers that react to
+    /// writes (PPU, APU, mapper) see two writes: this one, then the caller's final write.
+    fn rmw_read(&mut self, addr: u16) -> u8 {
+        let value = self.bus.read(addr);
+        self.bus.write(addr, value);
+        value
+    }
+
+    /// ADC: A + value + C, setting N, V, Z, C.
+    fn add_with_carry(&mut self, value: u8) {
+        let a = self.a;
+        let carry_in = self.binary_add(value);
+        if self.decimal_enabled && self.status & FLAG_DECIMAL != 0 {
+            self.decimal_add(a, value, carry_in);
+        }
+    }
+
+    /// SBC: A - value - (1 - C), i.e. ADC of the complement.
+    fn subtract_with_borrow(&mut self, value: u8) {
+        let a = self.a;
+        let carry_in = self.binary_add(value ^ 0xFF);
+        if self.decimal_enabled && self.status & FLAG_DECIMAL != 0 {
+            self.decimal_subtract(a, value, carry_in);
+        }
+    }
+
+    /// Binary A + value + C into A with flags; returns the carry that went in.
+    fn binary_add(&mut self, value: u8) -> u16 {
+        let carry_in = (self.status & FLAG_CARRY) as u16;
+        let sum = self.a as u16 + value as u16 + carry_in;
+        let result = sum as u8;
+        if sum > 0xFF {
+            self.status |= FLAG_CARRY;
+        } else {
+            self.status &= !FLAG_CARRY;
+        }
+        if ((!(self.a ^ value) & (self.a ^ result)) & 0x80) != 0 {
+            self.status |= FLAG_OVERFLOW;
+        } else {
+            self.status &= !FLAG_OVERFLOW;
+        }
+        self.a = result;
+        self.update_zero_and_negative_flags(self.a);
+        carry_in
+    }
+
+    /// NMOS [decimal ADC](http://www.6502.org/tutorials/decimal_mode.html): A is BCD-corrected
+    /// and C is the decimal carry. Z stays as the binary sum left it; N and V come from the sum
+    /// after only the low digit is corrected, which is what makes invalid BCD inputs odd.
+    fn decimal_add(&mut self, a: u8, value: u8, carry_in: u16) {
+        let mut low = (a & 0x0F) as u16 + (value & 0x0F) as u16 + carry_in;
+        if low >= 0x0A {
+            low = ((low + 0x06) & 0x0F) + 0x10;
+        }
+        let mut sum = (a & 0xF0) as u16 + (value & 0xF0) as u16 + low;
+        let overflow = !(a ^ value) & (a ^ sum as u8) & 0x80 != 0;
+        let negative = sum & 0x80 != 0;
+        if sum >= 0xA0 {
+            sum += 0x60;
+        }
+        self.status &= !(FLAG_CARRY | FLAG_OVERFLOW | FLAG_NEGATIVE);
+        if sum > 0xFF {
+            self.status |= FLAG_CARRY;
+        }
+        if overflow {
+            self.status |= FLAG_OVERFLOW;
+        }
+        if negative {
+            self.status |= FLAG_NEGATIVE;
+        }
+        self.a = sum as u8;
+    }
+
+    /// NMOS decimal SBC: all flags are the binary ones; only A is BCD-corrected.
+    fn decimal_subtract(&mut self, a: u8, value: u8, carry_in: u16) {
+        let mut low = (a & 0x0F) as i16 - (value & 0x0F) as i16 + carry_in as i16 - 1;
+        if low < 0 {
+            low = ((low - 0x06) & 0x0F) - 0x10;
+        }
+        let mut result = (a & 0xF0) as i16 - (value & 0xF0) as i16 + low;
+        if result < 0 {
+            result -= 0x60;
+        }
+        self.a = result as u8;
+    }
+
     /// Fetch 16-bit little-endian address from PC (low byte first, then high).
     fn fetch_word(&mut self) -> u16 {
         let lo = self.fetch_byte() as u16;
@@ -106,12 +465,98 @@ impl<B: Bus> CPU<B> {
         (hi << 8) | lo
     }
 
-    /// Print nestest-compatible trace line (PC, opcode, A, X, Y, P, SP, CYC).
-    fn trace(&self, pc: u16, opcode: u8) {
-        println!(
-            "{:04X}  {:02X}        A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} CYC:{}",
-            pc, opcode, self.a, self.x, self.y, self.status, self.sp, self.cycles
-        );
+    /// Call `f` with a `TraceEntry` before every instruction (not before interrupt sequences),
+    /// e.g. to log or collect a nestest-style trace. Without a tracer `step` does no trace work.
+    pub fn set_tracer(&mut self, f: impl FnMut(&TraceEntry) + 'static) {
+        self.tracer = Tracer(Some(Box::new(f)));
+    }
+
+    /// Remove the tracer set with `set_tracer`.
+    pub fn clear_tracer(&mut self) {
+        self.tracer = Tracer::default();
+    }
+
+    fn run_tracer(&mut self) {
+        let entry = TraceEntry {
+            pc: self.pc,
+            opcode: self.bus.peek(self.pc),
+            a: self.a,
+            x: self.x,
+            y: self.y,
+            sp: self.sp,
+            status: self.status,
+            cycles: self.cycles,
+            line: self.trace_line(),
+        };
+        if let Some(tracer) = &mut self.tracer.0 {
+            tracer(&entry);
+        }
+    }
+
+    /// nestest.log-format line for the instruction at PC, before it runs: address, bytes,
+    /// disassembly with the effective address and value it touches (e.g. `STX $00 = 00`), then
+    /// registers, the PPU position if the bus reports one, and the cycle count. Memory is read
+    /// with `peek`, so tracing has no side effects.
+    pub fn trace_line(&mut self) -> String {
+        let ins = disasm::disassemble(&mut self.bus, self.pc, None);
+        let annotation = self.trace_annotation(&ins);
+        let ppu = match self.bus.ppu_position() {
+            Some((scanline, dot)) => format!("PPU:{scanline:>3},{dot:>3} "),
+            None => String::new(),
+        };
+        format!(
+            "{:<47} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} {}CYC:{}",
+            format!("{ins}{annotation}"),
+            self.a,
+            self.x,
+            self.y,
+            self.status,
+            self.sp,
+            ppu,
+            self.cycles
+        )
+    }
+
+    /// Effective address and memory value suffix for `trace_line`, in nestest.log's notation.
+    fn trace_annotation(&mut self, ins: &disasm::Instruction) -> String {
+        let b1 = ins.bytes[1];
+        let word = u16::from_le_bytes([ins.bytes[1], ins.bytes[2]]);
+        let read_word_zp = |cpu: &mut Self, zp: u8| {
+            u16::from_le_bytes([cpu.bus.peek(zp as u16), cpu.bus.peek(zp.wrapping_add(1) as u16)])
+        };
+        match ins.mode {
+            disasm::AddrMode::ZeroPage => format!(" = {:02X}", self.bus.peek(b1 as u16)),
+            disasm::AddrMode::ZeroPageX | disasm::AddrMode::ZeroPageY => {
+                let index = if ins.mode == disasm::AddrMode::ZeroPageX { self.x } else { self.y };
+                let addr = b1.wrapping_add(index);
+                format!(" @ {addr:02X} = {:02X}", self.bus.peek(addr as u16))
+            }
+            // JMP and JSR use the address itself, not the byte there.
+            disasm::AddrMode::Absolute if matches!(ins.opcode, 0x4C | 0x20) => String::new(),
+            disasm::AddrMode::Absolute => format!(" = {:02X}", self.bus.peek(word)),
+            disasm::AddrMode::AbsoluteX | disasm::AddrMode::AbsoluteY => {
+                let index = if ins.mode == disasm::AddrMode::AbsoluteX { self.x } else { self.y };
+                let addr = word.wrapping_add(index as u16);
+                format!(" @ {addr:04X} = {:02X}", self.bus.peek(addr))
+            }
+            disasm::AddrMode::Indirect => {
+                // Same page-wrap as `jmp_indirect`: the high byte comes from $xx00, not $xy00.
+                let hi_addr = (word & 0xFF00) | (word.wrapping_add(1) & 0x00FF);
+                let target = u16::from_le_bytes([self.bus.peek(word), self.bus.peek(hi_addr)]);
+                format!(" = {target:04X}")
+            }
+            disasm::AddrMode::IndirectX => {
+                let ptr = b1.wrapping_add(self.x);
+                let addr = read_word_zp(self, ptr);
+                format!(" @ {ptr:02X} = {addr:04X} = {:02X}", self.bus.peek(addr))
+            }
+            disasm::AddrMode::IndirectY => {
+                let base = read_word_zp(self, b1);
+                let addr = base.wrapping_add(self.y as u16);
+                format!(" = {base:04X} @ {addr:04X} = {:02X}", self.bus.peek(addr))
+            }
+            _ => String::new(),
+        }
     }
 
     /// Decode opcode and run the corresponding instruction. Cycle counts follow NESdev instruction
@@ -171,6 +616,16 @@ impl<B: Bus> CPU<B> {
             0x87 => self.sax_zeropage(),
             0x97 => self.sax_zeropage_y(),
             0x83 => self.sax_indirect_x(),
+            0x0B | 0x2B => self.anc_immediate(),
+            0x4B => self.alr_immediate(),
+            0x6B => self.arr_immediate(),
+            0xCB => self.sbx_immediate(),
+            0xBB => self.las_absolute_y(),
+            0x9C => self.shy_absolute_x(),
+            0x9E => self.shx_absolute_y(),
+            0x9B => self.tas_absolute_y(),
+            0x9F => self.ahx_absolute_y(),
+            0x93 => self.ahx_indirect_y(),
             0x4C => self.jmp_absolute(),
             0x6C => self.jmp_indirect(),
             0x29 => self.and_immediate(),
@@ -332,11 +787,27 @@ impl<B: Bus> CPU<B> {
             0x36 => self.rol_zeropage_x(),
             0x24 => self.bit_zeropage(),
             0x2C => self.bit_absolute(),
-            _ => panic!(
-                "{} unimplemented opcode: ${:02X}",
-                Red.bold().paint("ERROR"),
-                opcode
-            ),
+            _ => self.illegal_opcode(opcode),
+        }
+    }
+
+    /// Opcode not handled by `execute_opcode`; behaviour follows `on_illegal`.
+    fn illegal_opcode(&mut self, opcode: u8) {
+        let pc = self.pc.wrapping_sub(1);
+        match self.on_illegal {
+            IllegalOpcodeAction::Panic => {
+                panic!("unimplemented opcode ${opcode:02X} at ${pc:04X}")
+            }
+            IllegalOpcodeAction::Halt => {
+                log::error!("unimplemented opcode ${opcode:02X} at ${pc:04X}; halting CPU");
+                self.halted = true;
+            }
+            IllegalOpcodeAction::NopAndLog => {
+                log::warn!("unimplemented opcode ${opcode:02X} at ${pc:04X}; skipping");
+                let operands = disasm::addr_mode(opcode).operand_len();
+                self.pc = self.pc.wrapping_add(operands);
+                self.cycles += 2;
+            }
         }
     }
 
@@ -677,6 +1148,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_word();
         let final_addr = base.wrapping_add(self.x as u16);
 
+        self.dummy_read_before_fixup(base, final_addr);
         self.bus.write(final_addr, self.a);
         self.cycles += 5;
     }
@@ -685,6 +1157,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_word();
         let final_addr = base.wrapping_add(self.y as u16);
 
+        self.dummy_read_before_fixup(base, final_addr);
         self.bus.write(final_addr, self.a);
 
         self.cycles += 5;
@@ -712,6 +1185,7 @@ impl<B: Bus> CPU<B> {
 
         let final_addr = base.wrapping_add(self.y as u16);
 
+        self.dummy_read_before_fixup(base, final_addr);
         self.bus.write(final_addr, self.a);
         self.cycles += 6;
     }
@@ -796,6 +1270,152 @@ impl<B: Bus> CPU<B> {
         self.cycles += 6;
     }
 
+    /// LAS (undocumented, $BB, absolute,Y): A = X = SP = memory AND SP. +1 cycle on page cross.
+    fn las_absolute_y(&mut self) {
+        let base = self.fetch_word();
+        let final_addr = base.wrapping_add(self.y as u16);
+
+        let value = self.bus.read(final_addr) & self.sp;
+        self.a = value;
+        self.x = value;
+        self.sp = value;
+        self.update_zero_and_negative_flags(value);
+
+        self.cycles += 4;
+
+        if (base & 0xFF00) != (final_addr & 0xFF00) {
+            self.cycles += 1;
+        }
+    }
+
+    /// Shared store of SHX, SHY, TAS and AHX: write `value AND (high byte of base + 1)` to
+    /// base + index. These opcodes are unstable on real hardware; this is the commonly observed
+    /// behaviour, including that on a page cross the stored value also replaces the high byte of
+    /// the address. Without a page cross the result is reliable.
+    fn unstable_store(&mut self, base: u16, index: u8, value: u8) {
+        let final_addr = base.wrapping_add(index as u16);
+        let value = value & ((base >> 8) as u8).wrapping_add(1);
+        let addr = if (base & 0xFF00) != (final_addr & 0xFF00) {
+            ((value as u16) << 8) | (final_addr & 0x00FF)
+        } else {
+            final_addr
+        };
+        self.bus.write(addr, value);
+    }
+
+    /// SHY (undocumented, $9C, absolute,X): store Y AND (H + 1). See `unstable_store`.
+    fn shy_absolute_x(&mut self) {
+        let base = self.fetch_word();
+        self.unstable_store(base, self.x, self.y);
+        self.cycles += 5;
+    }
+
+    /// SHX (undocumented, $9E, absolute,Y): store X AND (H + 1). See `unstable_store`.
+    fn shx_absolute_y(&mut self) {
+        let base = self.fetch_word();
+        self.unstable_store(base, self.y, self.x);
+        self.cycles += 5;
+    }
+
+    /// TAS (undocumented, $9B, absolute,Y): SP = A AND X, then store SP AND (H + 1).
+    fn tas_absolute_y(&mut self) {
+        let base = self.fetch_word();
+        self.sp = self.a & self.x;
+        self.unstable_store(base, self.y, self.sp);
+        self.cycles += 5;
+    }
+
+    /// AHX (undocumented, $9F, absolute,Y): store A AND X AND (H + 1).
+    fn ahx_absolute_y(&mut self) {
+        let base = self.fetch_word();
+        self.unstable_store(base, self.y, self.a & self.x);
+        self.cycles += 5;
+    }
+
+    /// AHX (undocumented, $93, (indirect),Y): store A AND X AND (H + 1), H from the pointer.
+    fn ahx_indirect_y(&mut self) {
+        let zp = self.fetch_byte();
+
+        let lo = self.bus.read(zp as u16) as u16;
+        let hi = self.bus.read(zp.wrapping_add(1) as u16) as u16;
+        let base = (hi << 8) | lo;
+
+        self.unstable_store(base, self.y, self.a & self.x);
+        self.cycles += 6;
+    }
+
+    /// ANC (undocumented, $0B/$2B): AND immediate, then copy bit 7 of the result into carry.
+    fn anc_immediate(&mut self) {
+        let value = self.fetch_byte();
+        self.a &= value;
+        self.update_zero_and_negative_flags(self.a);
+
+        if self.a & 0x80 != 0 {
+            self.status |= FLAG_CARRY;
+        } else {
+            self.status &= !FLAG_CARRY;
+        }
+
+        self.cycles += 2;
+    }
+
+    /// ALR (undocumented, $4B): AND immediate, then LSR A.
+    fn alr_immediate(&mut self) {
+        let value = self.fetch_byte();
+        self.a &= value;
+
+        if self.a & 0x01 != 0 {
+            self.status |= FLAG_CARRY;
+        } else {
+            self.status &= !FLAG_CARRY;
+        }
+        self.a >>= 1;
+
+        self.update_zero_and_negative_flags(self.a);
+        self.cycles += 2;
+    }
+
+    /// ARR (undocumented, $6B): AND immediate, then ROR A; carry = result bit 6 and overflow =
+    /// bit 6 XOR bit 5 instead of the usual ROR flags.
+    fn arr_immediate(&mut self) {
+        let value = self.fetch_byte();
+        let old_carry = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
+        self.a = ((self.a & value) >> 1) | (old_carry << 7);
+        self.update_zero_and_negative_flags(self.a);
+
+        let bit6 = (self.a >> 6) & 1;
+        let bit5 = (self.a >> 5) & 1;
+        if bit6 != 0 {
+            self.status |= FLAG_CARRY;
+        } else {
+            self.status &= !FLAG_CARRY;
+        }
+        if bit6 ^ bit5 != 0 {
+            self.status |= FLAG_OVERFLOW;
+        } else {
+            self.status &= !FLAG_OVERFLOW;
+        }
+
+        self.cycles += 2;
+    }
+
+    /// SBX / AXS (undocumented, $CB): X = (A AND X) - immediate, without borrow; carry and N/Z
+    /// are set as by CMP.
+    fn sbx_immediate(&mut self) {
+        let value = self.fetch_byte();
+        let ax = self.a & self.x;
+
+        if ax >= value {
+            self.status |= FLAG_CARRY;
+        } else {
+            self.status &= !FLAG_CARRY;
+        }
+
+        self.x = ax.wrapping_sub(value);
+        self.update_zero_and_negative_flags(self.x);
+        self.cycles += 2;
+    }
+
     fn and_immediate(&mut self) {
         let value = self.fetch_byte();
         self.a &= value;
@@ -1107,26 +1727,7 @@ impl<B: Bus> CPU<B> {
 
     fn adc_immediate(&mut self) {
         let value = self.fetch_byte();
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-
-        let sum = self.a as u16 + value as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((!(self.a ^ value) & (self.a ^ result)) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-
-        self.update_zero_and_negative_flags(self.a);
+        self.add_with_carry(value);
         self.cycles += 2;
     }
 
@@ -1134,24 +1735,7 @@ impl<B: Bus> CPU<B> {
         let addr = self.fetch_word();
         let value = self.bus.read(addr);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-        let sum = self.a as u16 + value as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((!(self.a ^ value) & (self.a ^ result)) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.add_with_carry(value);
 
         self.cycles += 4;
     }
@@ -1161,24 +1745,7 @@ impl<B: Bus> CPU<B> {
         let final_addr = base.wrapping_add(self.x as u16);
         let value = self.bus.read(final_addr);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-        let sum = self.a as u16 + value as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((!(self.a ^ value) & (self.a ^ result)) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.add_with_carry(value);
 
         self.cycles += 4;
 
@@ -1192,24 +1759,7 @@ impl<B: Bus> CPU<B> {
         let final_addr = base.wrapping_add(self.y as u16);
         let value = self.bus.read(final_addr);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-        let sum = self.a as u16 + value as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((!(self.a ^ value) & (self.a ^ result)) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.add_with_carry(value);
 
         self.cycles += 4;
 
@@ -1222,24 +1772,7 @@ impl<B: Bus> CPU<B> {
         let addr = self.fetch_byte() as u16;
         let value = self.bus.read(addr);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-        let sum = self.a as u16 + value as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((!(self.a ^ value) & (self.a ^ result)) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.add_with_carry(value);
 
         self.cycles += 3;
     }
@@ -1250,24 +1783,7 @@ impl<B: Bus> CPU<B> {
 
         let value = self.bus.read(addr);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-        let sum = self.a as u16 + value as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((!(self.a ^ value) & (self.a ^ result)) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.add_with_carry(value);
 
         self.cycles += 4;
     }
@@ -1282,24 +1798,7 @@ impl<B: Bus> CPU<B> {
 
         let value = self.bus.read(addr);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-        let sum = self.a as u16 + value as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((!(self.a ^ value) & (self.a ^ result)) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.add_with_carry(value);
 
         self.cycles += 6;
     }
@@ -1314,24 +1813,7 @@ impl<B: Bus> CPU<B> {
         let final_addr = base.wrapping_add(self.y as u16);
         let value = self.bus.read(final_addr);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-        let sum = self.a as u16 + value as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((!(self.a ^ value) & (self.a ^ result)) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.add_with_carry(value);
 
         self.cycles += 5;
 
@@ -1342,27 +1824,7 @@ impl<B: Bus> CPU<B> {
 
     fn sbc_immediate(&mut self) {
         let value = self.fetch_byte();
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-
-        let value = value ^ 0xFF;
-        let sum = self.a as u16 + value as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if (((self.a ^ result) & (result ^ value)) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-
-        self.update_zero_and_negative_flags(self.a);
+        self.subtract_with_borrow(value);
 
         self.cycles += 2;
     }
@@ -1371,26 +1833,7 @@ impl<B: Bus> CPU<B> {
         let addr = self.fetch_word();
         let value = self.bus.read(addr);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-
-        let value = value ^ 0xFF;
-        let sum = self.a as u16 + value as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((self.a ^ result) & (result ^ value) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.subtract_with_borrow(value);
 
         self.cycles += 4;
     }
@@ -1400,26 +1843,7 @@ impl<B: Bus> CPU<B> {
         let final_addr = base.wrapping_add(self.x as u16);
         let value = self.bus.read(final_addr);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-
-        let value = value ^ 0xFF;
-        let sum = self.a as u16 + value as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((self.a ^ result) & (result ^ value) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.subtract_with_borrow(value);
 
         self.cycles += 4;
 
@@ -1433,26 +1857,7 @@ impl<B: Bus> CPU<B> {
         let final_addr = base.wrapping_add(self.y as u16);
         let value = self.bus.read(final_addr);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-
-        let value = value ^ 0xFF;
-        let sum = self.a as u16 + value as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((self.a ^ result) & (result ^ value) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.subtract_with_borrow(value);
 
         self.cycles += 4;
 
@@ -1465,26 +1870,7 @@ impl<B: Bus> CPU<B> {
         let addr = self.fetch_byte() as u16;
         let value = self.bus.read(addr);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-
-        let value = value ^ 0xFF;
-        let sum = self.a as u16 + value as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((self.a ^ result) & (result ^ value) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.subtract_with_borrow(value);
         self.cycles += 3;
     }
 
@@ -1494,26 +1880,7 @@ impl<B: Bus> CPU<B> {
 
         let value = self.bus.read(addr);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-
-        let value = value ^ 0xFF;
-        let sum = self.a as u16 + value as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((self.a ^ result) & (result ^ value) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.subtract_with_borrow(value);
         self.cycles += 4;
     }
 
@@ -1527,26 +1894,7 @@ impl<B: Bus> CPU<B> {
 
         let value = self.bus.read(addr);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-
-        let value = value ^ 0xFF;
-        let sum = self.a as u16 + value as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((self.a ^ result) & (result ^ value) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.subtract_with_borrow(value);
 
         self.cycles += 6;
     }
@@ -1561,26 +1909,7 @@ impl<B: Bus> CPU<B> {
         let final_addr = base.wrapping_add(self.y as u16);
         let value = self.bus.read(final_addr);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-
-        let value = value ^ 0xFF;
-        let sum = self.a as u16 + value as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((self.a ^ result) & (result ^ value) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.subtract_with_borrow(value);
 
         self.cycles += 5;
 
@@ -1616,7 +1945,7 @@ impl<B: Bus> CPU<B> {
 
     fn inc_zeropage(&mut self) {
         let addr = self.fetch_byte() as u16;
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         value = value.wrapping_add(1);
 
@@ -1629,7 +1958,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_byte();
         let addr = base.wrapping_add(self.x) as u16;
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
         value = value.wrapping_add(1);
 
         self.bus.write(addr, value);
@@ -1639,7 +1968,7 @@ impl<B: Bus> CPU<B> {
 
     fn inc_absolute(&mut self) {
         let addr = self.fetch_word();
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         value = value.wrapping_add(1);
 
@@ -1652,7 +1981,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_word();
         let addr = base.wrapping_add(self.x as u16);
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
         value = value.wrapping_add(1);
 
         self.bus.write(addr, value);
@@ -1669,30 +1998,11 @@ impl<B: Bus> CPU<B> {
     fn isc_absolute(&mut self) {
         let addr = self.fetch_word();
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
         value = value.wrapping_add(1);
         self.bus.write(addr, value);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-        let inv = value ^ 0xFF;
-
-        let sum = self.a as u16 + inv as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((self.a ^ result) & (result ^ inv) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.subtract_with_borrow(value);
         self.cycles += 6;
     }
 
@@ -1700,30 +2010,11 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_word();
         let final_addr = base.wrapping_add(self.x as u16);
 
-        let mut value = self.bus.read(final_addr);
+        let mut value = self.rmw_read(final_addr);
         value = value.wrapping_add(1);
         self.bus.write(final_addr, value);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-        let inv = value ^ 0xFF;
-
-        let sum = self.a as u16 + inv as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((self.a ^ result) & (result ^ inv) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.subtract_with_borrow(value);
         self.cycles += 7;
     }
 
@@ -1731,92 +2022,35 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_word();
         let final_addr = base.wrapping_add(self.y as u16);
 
-        let mut value = self.bus.read(final_addr);
+        let mut value = self.rmw_read(final_addr);
         value = value.wrapping_add(1);
         self.bus.write(final_addr, value);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-        let inv = value ^ 0xFF;
-
-        let sum = self.a as u16 + inv as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((self.a ^ result) & (result ^ inv) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.subtract_with_borrow(value);
         self.cycles += 7;
     }
 
     fn isc_zeropage(&mut self) {
         let addr = self.fetch_byte() as u16;
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
         value = value.wrapping_add(1);
         self.bus.write(addr, value);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-        let inv = value ^ 0xFF;
-
-        let sum = self.a as u16 + inv as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((self.a ^ result) & (result ^ inv) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.subtract_with_borrow(value);
 
         self.cycles += 5;
     }
 
-    fn isc_zeropage_x(&mut self) {
-        let base = self.fetch_byte();
-        let addr = base.wrapping_add(self.x) as u16;
-
-        let mut value = self.bus.read(addr);
-        value = value.wrapping_add(1);
-        self.bus.write(addr, value);
-
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-        let inv = value ^ 0xFF;
-
-        let sum = self.a as u16 + inv as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((self.a ^ result) & (result ^ inv) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
+    fn isc_zeropage_x(&mut self) {
+        let base = self.fetch_byte();
+        let addr = base.wrapping_add(self.x) as u16;
 
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        let mut value = self.rmw_read(addr);
+        value = value.wrapping_add(1);
+        self.bus.write(addr, value);
+
+        self.subtract_with_borrow(value);
         self.cycles += 6;
     }
 
@@ -1828,30 +2062,11 @@ impl<B: Bus> CPU<B> {
         let hi = self.bus.read(ptr.wrapping_add(1) as u16) as u16;
         let addr = (hi << 8) | lo;
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
         value = value.wrapping_add(1);
         self.bus.write(addr, value);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-        let inv = value ^ 0xFF;
-
-        let sum = self.a as u16 + inv as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((self.a ^ result) & (result ^ inv) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.subtract_with_borrow(value);
 
         self.cycles += 8;
     }
@@ -1865,30 +2080,11 @@ impl<B: Bus> CPU<B> {
 
         let final_addr = base.wrapping_add(self.y as u16);
 
-        let mut value = self.bus.read(final_addr);
+        let mut value = self.rmw_read(final_addr);
         value = value.wrapping_add(1);
         self.bus.write(final_addr, value);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-        let inv = value ^ 0xFF;
-
-        let sum = self.a as u16 + inv as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((self.a ^ result) & (result ^ inv) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.subtract_with_borrow(value);
 
         self.cycles += 8;
     }
@@ -1907,7 +2103,7 @@ impl<B: Bus> CPU<B> {
 
     fn dec_absolute(&mut self) {
         let addr = self.fetch_word();
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         value = value.wrapping_sub(1);
 
@@ -1920,7 +2116,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_word();
         let addr = base.wrapping_add(self.x as u16);
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
         value = value.wrapping_sub(1);
 
         self.bus.write(addr, value);
@@ -1930,7 +2126,7 @@ impl<B: Bus> CPU<B> {
 
     fn dec_zeropage(&mut self) {
         let addr = self.fetch_byte() as u16;
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         value = value.wrapping_sub(1);
 
@@ -1943,7 +2139,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_byte();
         let addr = base.wrapping_add(self.x) as u16;
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
         value = value.wrapping_sub(1);
 
         self.bus.write(addr, value);
@@ -2148,7 +2344,7 @@ impl<B: Bus> CPU<B> {
 
     fn dcp_absolute(&mut self) {
         let addr = self.fetch_word();
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         value = value.wrapping_sub(1);
 
@@ -2170,7 +2366,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_word();
         let final_addr = base.wrapping_add(self.x as u16);
 
-        let mut value = self.bus.read(final_addr);
+        let mut value = self.rmw_read(final_addr);
         value = value.wrapping_sub(1);
         self.bus.write(final_addr, value);
 
@@ -2191,7 +2387,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_word();
         let final_addr = base.wrapping_add(self.y as u16);
 
-        let mut value = self.bus.read(final_addr);
+        let mut value = self.rmw_read(final_addr);
         value = value.wrapping_sub(1);
         self.bus.write(final_addr, value);
 
@@ -2210,7 +2406,7 @@ impl<B: Bus> CPU<B> {
 
     fn dcp_zeropage(&mut self) {
         let addr = self.fetch_byte() as u16;
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
         value = value.wrapping_sub(1);
 
         self.bus.write(addr, value);
@@ -2231,7 +2427,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_byte();
         let addr = base.wrapping_add(self.x) as u16;
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
         value = value.wrapping_sub(1);
         self.bus.write(addr, value);
 
@@ -2255,7 +2451,7 @@ impl<B: Bus> CPU<B> {
         let hi = self.bus.read(ptr.wrapping_add(1) as u16) as u16;
         let addr = (hi << 8) | lo;
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         value = value.wrapping_sub(1);
 
@@ -2282,7 +2478,7 @@ impl<B: Bus> CPU<B> {
 
         let final_addr = base.wrapping_add(self.y as u16);
 
-        let mut value = self.bus.read(final_addr);
+        let mut value = self.rmw_read(final_addr);
         value = value.wrapping_sub(1);
 
         self.bus.write(final_addr, value);
@@ -2451,6 +2647,10 @@ impl<B: Bus> CPU<B> {
         self.cycles += 2;
     }
 
+    // Unofficial NOPs (CPU_unofficial_opcodes): each reads its operand like a load would, through
+    // the bus, so register side effects still happen. Cycles: imm/implied 2, zp 3, zp,X 4, abs 4,
+    // abs,X 4 (+1 on page cross).
+
     fn nop_zeropage(&mut self) {
         let addr = self.fetch_byte() as u16;
         let _ = self.bus.read(addr);
@@ -2474,13 +2674,14 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_word();
         let final_addr = base.wrapping_add(self.x as u16);
 
-        let _ = self.bus.read(final_addr);
-
-        self.cycles += 4;
-
+        // On a page cross the CPU first reads from the un-carried address, then the real one.
         if (base & 0xFF00) != (final_addr & 0xFF00) {
+            let _ = self.bus.read((base & 0xFF00) | (final_addr & 0x00FF));
             self.cycles += 1;
         }
+        let _ = self.bus.read(final_addr);
+
+        self.cycles += 4;
     }
 
     fn nop_immediate(&mut self) {
@@ -2542,7 +2743,7 @@ impl<B: Bus> CPU<B> {
 
     fn lsr_zeropage(&mut self) {
         let addr = self.fetch_byte() as u16;
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         if value & 0x01 != 0 {
             self.status |= FLAG_CARRY;
@@ -2562,7 +2763,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_byte();
         let addr = base.wrapping_add(self.x) as u16;
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         if value & 0x01 != 0 {
             self.status |= FLAG_CARRY;
@@ -2579,7 +2780,7 @@ impl<B: Bus> CPU<B> {
 
     fn lsr_absolute(&mut self) {
         let addr = self.fetch_word();
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         if value & 0x01 != 0 {
             self.status |= FLAG_CARRY;
@@ -2598,7 +2799,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_word();
         let addr = base.wrapping_add(self.x as u16);
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         if value & 0x01 != 0 {
             self.status |= FLAG_CARRY;
@@ -2616,7 +2817,7 @@ impl<B: Bus> CPU<B> {
     fn sre_absolute(&mut self) {
         let addr = self.fetch_word();
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         if value & 0x01 != 0 {
             self.status |= FLAG_CARRY;
@@ -2635,7 +2836,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_word();
         let addr = base.wrapping_add(self.x as u16);
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         if value & 0x01 != 0 {
             self.status |= FLAG_CARRY;
@@ -2654,7 +2855,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_word();
         let addr = base.wrapping_add(self.y as u16);
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         if value & 0x01 != 0 {
             self.status |= FLAG_CARRY;
@@ -2672,7 +2873,7 @@ impl<B: Bus> CPU<B> {
     fn sre_zeropage(&mut self) {
         let addr = self.fetch_byte() as u16;
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         if value & 0x01 != 0 {
             self.status |= FLAG_CARRY;
@@ -2691,7 +2892,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_byte();
         let addr = base.wrapping_add(self.x) as u16;
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         if value & 0x01 != 0 {
             self.status |= FLAG_CARRY;
@@ -2714,7 +2915,7 @@ impl<B: Bus> CPU<B> {
         let hi = self.bus.read(ptr.wrapping_add(1) as u16) as u16;
         let addr = (hi << 8) | lo;
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         if value & 0x01 != 0 {
             self.status |= FLAG_CARRY;
@@ -2738,7 +2939,7 @@ impl<B: Bus> CPU<B> {
 
         let addr = base.wrapping_add(self.y as u16);
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         if value & 0x01 != 0 {
             self.status |= FLAG_CARRY;
@@ -2769,7 +2970,7 @@ impl<B: Bus> CPU<B> {
 
     fn asl_absolute(&mut self) {
         let addr = self.fetch_word();
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         if value & 0x80 != 0 {
             self.status |= FLAG_CARRY;
@@ -2788,7 +2989,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_word();
         let addr = base.wrapping_add(self.x as u16);
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         if value & 0x80 != 0 {
             self.status |= FLAG_CARRY;
@@ -2805,7 +3006,7 @@ impl<B: Bus> CPU<B> {
 
     fn asl_zeropage(&mut self) {
         let addr = self.fetch_byte() as u16;
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         if value & 0x80 != 0 {
             self.status |= FLAG_CARRY;
@@ -2824,7 +3025,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_byte();
         let addr = base.wrapping_add(self.x) as u16;
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         if value & 0x80 != 0 {
             self.status |= FLAG_CARRY;
@@ -2842,7 +3043,7 @@ impl<B: Bus> CPU<B> {
     fn slo_absolute(&mut self) {
         let addr = self.fetch_word();
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         if value & 0x80 != 0 {
             self.status |= FLAG_CARRY;
@@ -2862,7 +3063,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_word();
         let addr = base.wrapping_add(self.x as u16);
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         if value & 0x80 != 0 {
             self.status |= FLAG_CARRY;
@@ -2883,7 +3084,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_word();
         let addr = base.wrapping_add(self.y as u16);
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         if value & 0x80 != 0 {
             self.status |= FLAG_CARRY;
@@ -2903,7 +3104,7 @@ impl<B: Bus> CPU<B> {
     fn slo_zeropage(&mut self) {
         let addr = self.fetch_byte() as u16;
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         if value & 0x80 != 0 {
             self.status |= FLAG_CARRY;
@@ -2925,7 +3126,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_byte();
         let addr = base.wrapping_add(self.x) as u16;
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         if value & 0x80 != 0 {
             self.status |= FLAG_CARRY;
@@ -2949,7 +3150,7 @@ impl<B: Bus> CPU<B> {
         let hi = self.bus.read(ptr.wrapping_add(1) as u16) as u16;
         let addr = (hi << 8) | lo;
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
         if value & 0x80 != 0 {
             self.status |= FLAG_CARRY;
         } else {
@@ -2974,7 +3175,7 @@ impl<B: Bus> CPU<B> {
 
         let addr = base.wrapping_add(self.y as u16);
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         if value & 0x80 != 0 {
             self.status |= FLAG_CARRY;
@@ -2994,7 +3195,7 @@ impl<B: Bus> CPU<B> {
     fn rla_absolute(&mut self) {
         let addr = self.fetch_word();
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
         let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
 
         if value & 0x80 != 0 {
@@ -3015,7 +3216,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_word();
         let addr = base.wrapping_add(self.x as u16);
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
         let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
 
         if value & 0x80 != 0 {
@@ -3037,7 +3238,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_word();
         let addr = base.wrapping_add(self.y as u16);
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
         let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
 
         if value & 0x80 != 0 {
@@ -3058,7 +3259,7 @@ impl<B: Bus> CPU<B> {
     fn rla_zeropage(&mut self) {
         let addr = self.fetch_byte() as u16;
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
         let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
 
         if value & 0x80 != 0 {
@@ -3079,7 +3280,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_byte();
         let addr = base.wrapping_add(self.x) as u16;
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
         let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
 
         if value & 0x80 != 0 {
@@ -3104,7 +3305,7 @@ impl<B: Bus> CPU<B> {
         let hi = self.bus.read(ptr.wrapping_add(1) as u16) as u16;
         let addr = (hi << 8) | lo;
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
         let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
 
         if value & 0x80 != 0 {
@@ -3130,7 +3331,7 @@ impl<B: Bus> CPU<B> {
 
         let addr = base.wrapping_add(self.y as u16);
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
         let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
 
         if value & 0x80 != 0 {
@@ -3165,7 +3366,7 @@ impl<B: Bus> CPU<B> {
 
     fn ror_absolute(&mut self) {
         let addr = self.fetch_word();
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         let old_carry = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
 
@@ -3185,7 +3386,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_word();
         let addr = base.wrapping_add(self.x as u16);
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         let old_carry = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
 
@@ -3204,7 +3405,7 @@ impl<B: Bus> CPU<B> {
 
     fn ror_zeropage(&mut self) {
         let addr = self.fetch_byte() as u16;
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         let old_carry = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
 
@@ -3225,7 +3426,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_byte();
         let addr = base.wrapping_add(self.x) as u16;
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         let old_carry = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
 
@@ -3245,7 +3446,7 @@ impl<B: Bus> CPU<B> {
     fn rra_absolute(&mut self) {
         let addr = self.fetch_word();
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
         let carry_in = if self.status & FLAG_CARRY != 0 {
             0x80
         } else {
@@ -3260,24 +3461,7 @@ impl<B: Bus> CPU<B> {
         value = (value >> 1) | carry_in;
         self.bus.write(addr, value);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-        let sum = self.a as u16 + value as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((!(self.a ^ value) & (self.a ^ result)) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.add_with_carry(value);
         self.cycles += 6;
     }
 
@@ -3285,7 +3469,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_word();
         let addr = base.wrapping_add(self.x as u16);
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
         let carry_in = if self.status & FLAG_CARRY != 0 {
             0x80
         } else {
@@ -3300,24 +3484,7 @@ impl<B: Bus> CPU<B> {
         value = (value >> 1) | carry_in;
         self.bus.write(addr, value);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-        let sum = self.a as u16 + value as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((!(self.a ^ value) & (self.a ^ result)) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.add_with_carry(value);
         self.cycles += 7;
     }
 
@@ -3325,7 +3492,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_word();
         let addr = base.wrapping_add(self.y as u16);
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
         let carry_in = if self.status & FLAG_CARRY != 0 {
             0x80
         } else {
@@ -3340,31 +3507,14 @@ impl<B: Bus> CPU<B> {
         value = (value >> 1) | carry_in;
         self.bus.write(addr, value);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-        let sum = self.a as u16 + value as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((!(self.a ^ value) & (self.a ^ result)) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.add_with_carry(value);
         self.cycles += 7;
     }
 
     fn rra_zeropage(&mut self) {
         let addr = self.fetch_byte() as u16;
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
         let carry_in = if self.status & FLAG_CARRY != 0 {
             0x80
         } else {
@@ -3379,24 +3529,7 @@ impl<B: Bus> CPU<B> {
         value = (value >> 1) | carry_in;
         self.bus.write(addr, value);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-        let sum = self.a as u16 + value as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((!(self.a ^ value) & (self.a ^ result)) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.add_with_carry(value);
         self.cycles += 5;
     }
 
@@ -3404,7 +3537,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_byte();
         let addr = base.wrapping_add(self.x) as u16;
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
         let carry_in = if self.status & FLAG_CARRY != 0 {
             0x80
         } else {
@@ -3419,24 +3552,7 @@ impl<B: Bus> CPU<B> {
         value = (value >> 1) | carry_in;
         self.bus.write(addr, value);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-        let sum = self.a as u16 + value as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((!(self.a ^ value) & (self.a ^ result)) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.add_with_carry(value);
         self.cycles += 6;
     }
 
@@ -3448,7 +3564,7 @@ impl<B: Bus> CPU<B> {
         let hi = self.bus.read(ptr.wrapping_add(1) as u16) as u16;
         let addr = (hi << 8) | lo;
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
         let carry_in = if self.status & FLAG_CARRY != 0 {
             0x80
         } else {
@@ -3463,24 +3579,7 @@ impl<B: Bus> CPU<B> {
         value = (value >> 1) | carry_in;
         self.bus.write(addr, value);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-        let sum = self.a as u16 + value as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((!(self.a ^ value) & (self.a ^ result)) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.add_with_carry(value);
         self.cycles += 8;
     }
 
@@ -3493,7 +3592,7 @@ impl<B: Bus> CPU<B> {
 
         let addr = base.wrapping_add(self.y as u16);
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
         let carry_in = if self.status & FLAG_CARRY != 0 {
             0x80
         } else {
@@ -3508,24 +3607,7 @@ impl<B: Bus> CPU<B> {
         value = (value >> 1) | carry_in;
         self.bus.write(addr, value);
 
-        let carry_in = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
-        let sum = self.a as u16 + value as u16 + carry_in as u16;
-        let result = sum as u8;
-
-        if sum > 0xFF {
-            self.status |= FLAG_CARRY;
-        } else {
-            self.status &= !FLAG_CARRY;
-        }
-
-        if ((!(self.a ^ value) & (self.a ^ result)) & 0x80) != 0 {
-            self.status |= FLAG_OVERFLOW;
-        } else {
-            self.status &= !FLAG_OVERFLOW;
-        }
-
-        self.a = result;
-        self.update_zero_and_negative_flags(self.a);
+        self.add_with_carry(value);
         self.cycles += 8;
     }
 
@@ -3546,7 +3628,7 @@ impl<B: Bus> CPU<B> {
 
     fn rol_absolute(&mut self) {
         let addr = self.fetch_word();
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         let old_carry = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
 
@@ -3567,7 +3649,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_word();
         let addr = base.wrapping_add(self.x as u16);
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         let old_carry = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
 
@@ -3586,7 +3668,7 @@ impl<B: Bus> CPU<B> {
 
     fn rol_zeropage(&mut self) {
         let addr = self.fetch_byte() as u16;
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         let old_carry = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
 
@@ -3607,7 +3689,7 @@ impl<B: Bus> CPU<B> {
         let base = self.fetch_byte();
         let addr = base.wrapping_add(self.x) as u16;
 
-        let mut value = self.bus.read(addr);
+        let mut value = self.rmw_read(addr);
 
         let old_carry = if self.status & FLAG_CARRY != 0 { 1 } else { 0 };
 
@@ -3636,22 +3718,11 @@ impl<B: Bus> CPU<B> {
         self.cycles += 2;
     }
 
+    /// BRK: the stacked PC is the address after the padding byte and the stacked P has B (bit 4)
+    /// set; that B bit is the only way a handler on the shared $FFFE vector can tell BRK from IRQ.
     fn brk(&mut self) {
         self.pc = self.pc.wrapping_add(1); // +1 because of padding byte
-
-        self.push((self.pc >> 8) as u8);
-        self.push(self.pc as u8);
-
-        let status = self.status | FLAG_BREAK | FLAG_UNUSED;
-        self.push(status);
-
-        self.status |= FLAG_INTERRUPT_DISABLE;
-
-        let lo = self.bus.read(0xFFFE) as u16;
-        let hi = self.bus.read(0xFFFF) as u16;
-        self.pc = (hi << 8) | lo;
-
-        self.cycles += 7;
+        self.interrupt(Interrupt::Brk);
     }
 
     fn bit_zeropage(&mut self) {
@@ -3719,42 +3790,51 @@ impl<B: Bus> CPU<B> {
         }
     }
 
-    /// Handle IRQ: push PC and status, jump to $FFFE/$FFFF.
+    /// Handle IRQ: push PC and status (B clear), jump to $FFFE/$FFFF. The I flag was checked when
+    /// the line was polled.
     fn irq(&mut self) {
-        if self.status & FLAG_INTERRUPT_DISABLE != 0 {
-            return;
-        }
-
-        self.push((self.pc >> 8) as u8);
-        self.push(self.pc as u8);
-
-        let status = (self.status & !FLAG_BREAK) | FLAG_UNUSED;
-        self.push(status);
-
-        self.status |= FLAG_INTERRUPT_DISABLE;
-
-        let lo = self.bus.read(0xFFFE) as u16;
-        let hi = self.bus.read(0xFFFF) as u16;
-        self.pc = (hi << 8) | lo;
-
-        self.cycles += 7;
+        self.interrupt(Interrupt::Irq);
     }
 
     /// Handle NMI: push PC and status, jump to $FFFA/$FFFB.
     fn nmi(&mut self) {
+        self.interrupt(Interrupt::Nmi);
+    }
+
+    /// Shared 7-cycle BRK/IRQ/NMI sequence: push PCH, PCL, P; set I; load PC from the vector.
+    ///
+    /// The vector is chosen after the PC pushes, so an NMI that arrives by then "hijacks" a BRK
+    /// or IRQ: the sequence finishes through $FFFA instead of $FFFE, and the pushed P keeps
+    /// BRK's B flag. To sample NMI at the right time the bus is ticked through the push cycles
+    /// here. See [CPU interrupts](https://www.nesdev.org/wiki/CPU_interrupts#Interrupt_hijacking).
+    fn interrupt(&mut self, kind: Interrupt) {
+        // Cycles 1–2: opcode/padding fetch (BRK) or dummy reads (IRQ/NMI). Cycles 3–4: push PC.
         self.push((self.pc >> 8) as u8);
         self.push(self.pc as u8);
+        self.cycles += 4;
+        self.tick_bus(4);
 
-        let status = (self.status & !FLAG_BREAK) | FLAG_UNUSED;
-        self.push(status);
+        let hijacked = kind != Interrupt::Nmi && self.bus.poll_nmi();
 
+        // Cycle 5: push P. Cycles 6–7: fetch vector.
+        let status = if kind == Interrupt::Brk {
+            self.status | FLAG_BREAK | FLAG_UNUSED
+        } else {
+            (self.status & !FLAG_BREAK) | FLAG_UNUSED
+        };
+        self.push(status);
         self.status |= FLAG_INTERRUPT_DISABLE;
 
-        let lo = self.bus.read(0xFFFA) as u16;
-        let hi = self.bus.read(0xFFFB) as u16;
+        let vector = if kind == Interrupt::Nmi || hijacked {
+            0xFFFA
+        } else {
+            0xFFFE
+        };
+        let lo = self.bus.read(vector) as u16;
+        let hi = self.bus.read(vector + 1) as u16;
         self.pc = (hi << 8) | lo;
 
-        self.cycles += 7;
+        self.cycles += 3;
     }
 
     /// Push byte onto stack at $0100+SP.
@@ -3788,3 +3868,449 @@ impl<B: Bus> CPU<B> {
         self.cycles += 2;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::NesBus;
+    use crate::cartridge::cartridge::Cartridge;
+    use crate::region::Region;
+    use std::{cell::RefCell, rc::Rc};
+
+    /// 64 KiB of plain RAM.
+    struct FlatBus(Vec<u8>);
+
+    impl Bus for FlatBus {
+        fn read(&mut self, addr: u16) -> u8 {
+            self.0[addr as usize]
+        }
+
+        fn write(&mut self, addr: u16, data: u8) {
+            self.0[addr as usize] = data;
+        }
+    }
+
+    /// Plain RAM plus an IRQ line and an NMI that asserts once `nmi_at` cycles have been ticked.
+    struct InterruptBus {
+        ram: FlatBus,
+        ticked: usize,
+        nmi_at: Option<usize>,
+        irq: bool,
+    }
+
+    impl Bus for InterruptBus {
+        fn read(&mut self, addr: u16) -> u8 {
+            self.ram.read(addr)
+        }
+
+        fn write(&mut self, addr: u16, data: u8) {
+            self.ram.write(addr, data);
+        }
+
+        fn tick(&mut self, cycles: usize) {
+            self.ticked += cycles;
+        }
+
+        fn poll_nmi(&mut self) -> bool {
+            let asserted = self.nmi_at.is_some_and(|at| self.ticked >= at);
+            if asserted {
+                self.nmi_at = None;
+            }
+            asserted
+        }
+
+        fn poll_irq(&mut self) -> bool {
+            self.irq
+        }
+    }
+
+    /// Plain RAM that records every address read.
+    struct ReadLogBus {
+        ram: FlatBus,
+        reads: Vec<u16>,
+    }
+
+    impl Bus for ReadLogBus {
+        fn read(&mut self, addr: u16) -> u8 {
+            self.reads.push(addr);
+            self.ram.read(addr)
+        }
+
+        fn write(&mut self, addr: u16, data: u8) {
+            self.ram.write(addr, data);
+        }
+    }
+
+    /// Plain RAM that records every write.
+    struct WriteLogBus {
+        ram: FlatBus,
+        writes: Vec<(u16, u8)>,
+    }
+
+    impl Bus for WriteLogBus {
+        fn read(&mut self, addr: u16) -> u8 {
+            self.ram.read(addr)
+        }
+
+        fn write(&mut self, addr: u16, data: u8) {
+            self.writes.push((addr, data));
+            self.ram.write(addr, data);
+        }
+    }
+
+    /// 64 KiB with `program` at $8000, the reset vector there, NMI at $9000 and IRQ at $A000.
+    fn ram_with(program: &[u8]) -> Vec<u8> {
+        let mut ram = vec![0; 0x10000];
+        ram[0x8000..0x8000 + program.len()].copy_from_slice(program);
+        ram[0xFFFA..].copy_from_slice(&[0x00, 0x90, 0x00, 0x80, 0x00, 0xA0]);
+        ram
+    }
+
+    /// CPU on a `FlatBus` with `program` at $8000, reset and ready to run it.
+    fn cpu_with(program: &[u8]) -> CPU<FlatBus> {
+        let mut cpu = CPU::new(FlatBus(ram_with(program)));
+        cpu.reset();
+        cpu
+    }
+
+    /// Like `cpu_with`, on an `InterruptBus` with both lines idle.
+    fn cpu_with_interrupts(program: &[u8]) -> CPU<InterruptBus> {
+        let mut cpu = CPU::new(InterruptBus {
+            ram: FlatBus(ram_with(program)),
+            ticked: 0,
+            nmi_at: None,
+            irq: false,
+        });
+        cpu.reset();
+        cpu
+    }
+
+    fn run<B: Bus>(cpu: &mut CPU<B>, instructions: usize) {
+        for _ in 0..instructions {
+            cpu.step();
+        }
+    }
+
+    #[test]
+    fn step_runs_on_a_bus_without_tick_or_nmi() {
+        // LDA #$42
+        let mut cpu = cpu_with(&[0xA9, 0x42]);
+        assert_eq!(cpu.step(), 2);
+        assert_eq!((cpu.a, cpu.pc), (0x42, 0x8002));
+    }
+
+    #[test]
+    fn stats_count_each_executed_instruction() {
+        // NOP x5
+        let mut cpu = cpu_with(&[0xEA; 5]);
+        cpu.set_opcode_histogram(true);
+        let (instructions, cycles) = (cpu.stats().instructions, cpu.stats().total_cycles);
+        run(&mut cpu, 5);
+        assert_eq!(cpu.stats().instructions, instructions + 5);
+        assert_eq!(cpu.stats().total_cycles, cycles + 10);
+        assert_eq!(cpu.stats().opcode_counts.as_ref().unwrap()[0xEA], 5);
+        cpu.reset_stats();
+        assert_eq!((cpu.stats().instructions, cpu.stats().total_cycles), (0, 0));
+    }
+
+    #[test]
+    fn tracer_sees_each_instruction_before_it_runs_until_cleared() {
+        // LDA #$42; NOP; NOP
+        let mut cpu = cpu_with(&[0xA9, 0x42, 0xEA, 0xEA]);
+        let entries = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&entries);
+        cpu.set_tracer(move |e| sink.borrow_mut().push((e.pc, e.opcode, e.a, e.cycles)));
+        run(&mut cpu, 2);
+        assert_eq!(*entries.borrow(), [(0x8000, 0xA9, 0x00, 7), (0x8002, 0xEA, 0x42, 9)]);
+        cpu.clear_tracer();
+        cpu.step();
+        assert_eq!(entries.borrow().len(), 2);
+    }
+
+    #[test]
+    fn step_returns_cycles_including_page_crossing_and_interrupt_entry() {
+        // LDX #$01; LDA $12FF,X; NOP
+        let mut cpu = cpu_with_interrupts(&[0xA2, 0x01, 0xBD, 0xFF, 0x12, 0xEA]);
+        assert_eq!(cpu.step(), 2);
+        assert_eq!(cpu.step(), 5);
+        cpu.bus.nmi_at = Some(0);
+        // The NMI is seen at the end of the NOP and entered (7 cycles) by the next step.
+        assert_eq!(cpu.step(), 2);
+        assert_eq!(cpu.step(), 7);
+        assert_eq!(cpu.pc, 0x9000);
+    }
+
+    #[test]
+    fn restore_returns_registers_to_the_snapshot() {
+        // LDA #$11; LDX #$22; LDY #$33; PHA; SEC; INX
+        let mut cpu = cpu_with(&[0xA9, 0x11, 0xA2, 0x22, 0xA0, 0x33, 0x48, 0x38, 0xE8]);
+        run(&mut cpu, 3);
+        let snapshot = cpu.snapshot();
+        run(&mut cpu, 3);
+        assert_ne!(cpu.snapshot(), snapshot);
+        cpu.restore(snapshot);
+        assert_eq!(cpu.snapshot(), snapshot);
+        assert_eq!((cpu.a, cpu.x, cpu.y, cpu.sp, cpu.pc), (0x11, 0x22, 0x33, 0xFD, 0x8006));
+        assert_eq!(cpu.cycles, 7 + 6);
+    }
+
+    #[test]
+    fn cli_lets_one_more_instruction_run_before_a_pending_irq() {
+        // CLI; NOP; NOP
+        let mut cpu = cpu_with_interrupts(&[0x58, 0xEA, 0xEA]);
+        cpu.bus.irq = true;
+        run(&mut cpu, 2);
+        assert_eq!(cpu.pc, 0x8002);
+        cpu.step();
+        assert_eq!(cpu.pc, 0xA000);
+    }
+
+    #[test]
+    fn brk_pushes_b_set_and_the_address_after_its_padding_byte() {
+        // BRK (plus padding byte)
+        let mut cpu = cpu_with_interrupts(&[0x00, 0xFF]);
+        cpu.step();
+        assert_eq!(cpu.pc, 0xA000);
+        assert_eq!((cpu.bus.read(0x01FD), cpu.bus.read(0x01FC)), (0x80, 0x02));
+        assert_ne!(cpu.bus.read(0x01FB) & FLAG_BREAK, 0);
+    }
+
+    #[test]
+    fn irq_pushes_b_clear_and_the_next_instruction_address() {
+        // CLI; NOP
+        let mut cpu = cpu_with_interrupts(&[0x58, 0xEA]);
+        cpu.bus.irq = true;
+        run(&mut cpu, 3);
+        assert_eq!(cpu.pc, 0xA000);
+        assert_eq!((cpu.bus.read(0x01FD), cpu.bus.read(0x01FC)), (0x80, 0x02));
+        assert_eq!(cpu.bus.read(0x01FB) & FLAG_BREAK, 0);
+    }
+
+    #[test]
+    fn irq_is_level_triggered_and_masked_by_i() {
+        // SEI; NOP; CLI; NOP; NOP, with RTI as the IRQ handler
+        let mut cpu = cpu_with_interrupts(&[0x78, 0xEA, 0x58, 0xEA, 0xEA]);
+        cpu.bus.ram.0[0xA000] = 0x40;
+        cpu.bus.irq = true;
+        run(&mut cpu, 2);
+        assert_eq!(cpu.pc, 0x8002);
+        run(&mut cpu, 3); // CLI, NOP, IRQ entry
+        assert_eq!(cpu.pc, 0xA000);
+        // Still asserted after RTI: the IRQ is taken again straight away.
+        run(&mut cpu, 2);
+        assert_eq!(cpu.pc, 0xA000);
+        cpu.bus.irq = false;
+        run(&mut cpu, 2);
+        assert_eq!(cpu.pc, 0x8005);
+    }
+
+    #[test]
+    fn rmw_writes_the_original_value_before_the_result() {
+        // INC $00; ASL $0200,X
+        let mut cpu = CPU::new(WriteLogBus {
+            ram: FlatBus(ram_with(&[0xE6, 0x00, 0x1E, 0x00, 0x02])),
+            writes: Vec::new(),
+        });
+        cpu.reset();
+        cpu.bus.ram.0[0x00] = 0x05;
+        cpu.bus.ram.0[0x0200] = 0x41;
+        run(&mut cpu, 2);
+        assert_eq!(cpu.bus.writes, [(0x00, 0x05), (0x00, 0x06), (0x0200, 0x41), (0x0200, 0x82)]);
+    }
+
+    #[test]
+    fn nmi_during_brk_hijacks_the_vector() {
+        // BRK, with NMI asserting one cycle into it (reset ticked 7).
+        let mut cpu = cpu_with_interrupts(&[0x00, 0x00]);
+        cpu.bus.nmi_at = Some(8);
+        cpu.step();
+        assert_eq!(cpu.pc, 0x9000);
+        // The pushed P still has B set: the NMI took over a BRK.
+        assert_ne!(cpu.bus.read(0x01FB) & FLAG_BREAK, 0);
+        assert!(!cpu.interrupt_pending());
+    }
+
+    #[test]
+    fn unofficial_absolute_x_nops_read_and_pay_for_page_crossing() {
+        for opcode in [0x1C, 0x3C, 0x5C, 0x7C, 0xDC, 0xFC] {
+            // LDX #$01; NOP $1200,X; NOP $12FF,X
+            let program = [0xA2, 0x01, opcode, 0x00, 0x12, opcode, 0xFF, 0x12];
+            let mut cpu = CPU::new(ReadLogBus {
+                ram: FlatBus(ram_with(&program)),
+                reads: Vec::new(),
+            });
+            cpu.reset();
+            cpu.step();
+            cpu.bus.reads.clear();
+            assert_eq!(cpu.step(), 4, "${opcode:02X} same page");
+            assert!(cpu.bus.reads.contains(&0x1201));
+            assert_eq!(cpu.step(), 5, "${opcode:02X} page crossed");
+            assert!(cpu.bus.reads.contains(&0x1300));
+        }
+    }
+
+    #[test]
+    fn indexed_stores_always_dummy_read_and_take_fixed_cycles() {
+        // LDX #$01; LDY #$01; STA $02FF,X; STA $0300,Y; STA ($10),Y
+        let program = [0xA2, 0x01, 0xA0, 0x01, 0x9D, 0xFF, 0x02, 0x99, 0x00, 0x03, 0x91, 0x10];
+        let mut cpu = CPU::new(ReadLogBus {
+            ram: FlatBus(ram_with(&program)),
+            reads: Vec::new(),
+        });
+        cpu.reset();
+        cpu.bus.ram.0[0x10..0x12].copy_from_slice(&[0xFF, 0x02]);
+        run(&mut cpu, 2);
+        // (cycles, dummy read at the address before the high byte is fixed up)
+        for (cycles, dummy) in [(5, 0x0200), (5, 0x0301), (6, 0x0200)] {
+            cpu.bus.reads.clear();
+            assert_eq!(cpu.step(), cycles);
+            assert!(cpu.bus.reads.contains(&dummy), "no read of ${dummy:04X}");
+        }
+    }
+
+    #[test]
+    fn nop_and_log_skips_unimplemented_opcode_and_operand() {
+        // XAA #$55; LDA #$42
+        let mut cpu = cpu_with(&[0x8B, 0x55, 0xA9, 0x42]);
+        cpu.on_illegal = IllegalOpcodeAction::NopAndLog;
+        cpu.step();
+        assert_eq!(cpu.pc, 0x8002);
+        cpu.step();
+        assert!(!cpu.halted);
+        assert_eq!(cpu.a, 0x42);
+    }
+
+    #[test]
+    fn step_until_breakpoint_stops_on_hit_cycle_limit_or_jam() {
+        // NOP; NOP; JAM
+        let mut cpu = cpu_with(&[0xEA, 0xEA, 0x02]);
+        let index = cpu.add_breakpoint(Breakpoint::at(0x8001));
+        assert_eq!(cpu.step_until_breakpoint(100), StepOutcome::BreakpointHit(0x8001));
+        assert_eq!(cpu.pc, 0x8001);
+        cpu.remove_breakpoint(index);
+
+        let mut limited = cpu_with(&[0xEA; 8]);
+        assert_eq!(limited.step_until_breakpoint(4), StepOutcome::CycleLimit);
+        assert_eq!(limited.pc, 0x8002);
+
+        assert_eq!(cpu.step_until_breakpoint(100), StepOutcome::Halted);
+        assert!(cpu.halted);
+    }
+
+    #[test]
+    fn conditional_breakpoint_fires_only_when_condition_holds() {
+        use crate::cpu::breakpoint::{Condition, Register};
+
+        // loop: INX; JMP loop
+        let mut cpu = cpu_with(&[0xE8, 0x4C, 0x00, 0x80]);
+        cpu.add_breakpoint(Breakpoint::at(0x8001).and(Condition::reg_eq(Register::X, 3)));
+        assert_eq!(cpu.step_until_breakpoint(1_000), StepOutcome::BreakpointHit(0x8001));
+        assert_eq!(cpu.x, 3);
+        // Resuming passes $8001 with X = 4, 5, … without stopping until X wraps back to 3.
+        assert_eq!(cpu.step_until_breakpoint(1_000), StepOutcome::CycleLimit);
+        assert_ne!(cpu.x, 3);
+    }
+
+    /// Run `LDA #a; LDX #x; CLC/SEC; opcode #operand` and return the CPU.
+    fn run_immediate(a: u8, x: u8, carry: bool, opcode: u8, operand: u8) -> CPU<FlatBus> {
+        let set_carry = if carry { 0x38 } else { 0x18 };
+        let mut cpu = cpu_with(&[0xA9, a, 0xA2, x, set_carry, opcode, operand]);
+        run(&mut cpu, 4);
+        cpu
+    }
+
+    #[test]
+    fn anc_alr_arr_sbx_match_reference_results() {
+        const C: u8 = FLAG_CARRY;
+        const Z: u8 = FLAG_ZERO;
+        const V: u8 = FLAG_OVERFLOW;
+        const N: u8 = FLAG_NEGATIVE;
+        // (A, X, carry in, opcode, operand) -> (A, X, C/Z/V/N)
+        let cases = [
+            ((0xFF, 0x00, false, 0x0B, 0x80), (0x80, 0x00, C | N)), // ANC: C = bit 7
+            ((0x8F, 0x00, true, 0x2B, 0x0F), (0x0F, 0x00, 0)),
+            ((0x03, 0x00, false, 0x4B, 0xFF), (0x01, 0x00, C)), // ALR: AND, then LSR
+            ((0x80, 0x00, false, 0x6B, 0xFF), (0x40, 0x00, C | V)), // ARR: C = bit 6, V = 6 ^ 5
+            ((0xFF, 0x00, true, 0x6B, 0xFF), (0xFF, 0x00, C | N)),
+            ((0x0F, 0xF3, false, 0xCB, 0x02), (0x0F, 0x01, C)), // SBX: X = (A & X) - imm
+            ((0x0F, 0xF3, true, 0xCB, 0x03), (0x0F, 0x00, C | Z)),
+            ((0x0F, 0xF3, true, 0xCB, 0x04), (0x0F, 0xFF, N)),
+        ];
+        for ((a, x, carry, opcode, operand), expected) in cases {
+            let cpu = run_immediate(a, x, carry, opcode, operand);
+            let flags = cpu.status & (C | Z | V | N);
+            assert_eq!((cpu.a, cpu.x, flags), expected, "opcode {opcode:02X} #{operand:02X}");
+            assert_eq!(cpu.cycles, 7 + 2 + 2 + 2 + 2);
+        }
+    }
+
+    #[test]
+    fn unstable_stores_and_las_give_the_stable_results_without_page_crossing() {
+        #[rustfmt::skip]
+        let program = [
+            0xA2, 0xF1, 0xA0, 0x00, 0x9E, 0x00, 0x12, // LDX #$F1; LDY #0; SHX $1200,Y
+            0xA0, 0x06, 0xA2, 0x00, 0x9C, 0x10, 0x12, // LDY #$06; LDX #0; SHY $1210,X
+            0xA9, 0x7F, 0xA2, 0xF7, 0x9F, 0x1A, 0x12, // LDA #$7F; LDX #$F7; AHX $121A,Y
+            0xA9, 0xF0, 0xA2, 0x3F, 0x9B, 0x2A, 0x12, // LDA #$F0; LDX #$3F; TAS $122A,Y
+            0xA0, 0x01, 0xBB, 0x33, 0x12, // LDY #1; LAS $1233,Y
+        ];
+        let mut cpu = cpu_with(&program);
+        cpu.bus.0[0x1234] = 0xF7;
+        run(&mut cpu, 12);
+        // Each store writes register & (high byte of the base address + 1) = & $13.
+        assert_eq!(cpu.bus.0[0x1200], 0xF1 & 0x13);
+        assert_eq!(cpu.bus.0[0x1210], 0x06 & 0x13);
+        assert_eq!(cpu.bus.0[0x1220], 0x7F & 0xF7 & 0x13);
+        assert_eq!(cpu.bus.0[0x1230], 0x30 & 0x13);
+        assert_eq!(cpu.sp, 0x30);
+        let before = cpu.cycles;
+        run(&mut cpu, 2);
+        assert_eq!((cpu.a, cpu.x, cpu.sp), (0x30, 0x30, 0x30));
+        assert_eq!(cpu.cycles - before, 2 + 4);
+    }
+
+    #[test]
+    fn decimal_adc_applies_bcd_correction() {
+        // SED; CLC; LDA #$09; ADC #$01
+        let mut cpu = cpu_with(&[0xF8, 0x18, 0xA9, 0x09, 0x69, 0x01]);
+        cpu.decimal_enabled = true;
+        run(&mut cpu, 4);
+        assert_eq!(cpu.a, 0x10);
+    }
+
+    #[test]
+    fn decimal_rra_and_isc_use_bcd_arithmetic() {
+        // SED; CLC; LDA #$09; RRA $10 ($02 -> $01, A = $09 + $01)
+        let mut cpu = cpu_with(&[0xF8, 0x18, 0xA9, 0x09, 0x67, 0x10]);
+        cpu.decimal_enabled = true;
+        cpu.bus.0[0x10] = 0x02;
+        run(&mut cpu, 4);
+        assert_eq!(cpu.a, 0x10);
+
+        // SED; SEC; LDA #$10; ISC $10 ($00 -> $01, A = $10 - $01)
+        let mut cpu = cpu_with(&[0xF8, 0x38, 0xA9, 0x10, 0xE7, 0x10]);
+        cpu.decimal_enabled = true;
+        run(&mut cpu, 4);
+        assert_eq!(cpu.a, 0x09);
+    }
+
+    #[test]
+    fn nestest_trace_matches_reference_log() {
+        let cart = Cartridge::load("test/nestest.nes").unwrap();
+        let mut cpu = CPU::new(NesBus::new(cart, Region::Ntsc));
+        cpu.reset();
+        cpu.pc = 0xC000;
+
+        let lines = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&lines);
+        cpu.set_tracer(move |entry| sink.borrow_mut().push(entry.line.clone()));
+        while lines.borrow().len() < 50 {
+            cpu.step();
+        }
+
+        let expected = std::fs::read_to_string("test/nestest.log").unwrap();
+        for (i, (got, want)) in lines.borrow().iter().zip(expected.lines()).enumerate() {
+            assert_eq!(got, want, "line {}", i + 1);
+        }
+    }
+}
@@ -0,0 +1,318 @@
+//! 6502 disassembler with optional symbol names.
+//!
+//! Decodes one instruction at a time using the [opcode matrix](https://www.nesdev.org/wiki/CPU_unofficial_opcodes)
+//! (official and unofficial opcodes; unofficial mnemonics are prefixed with `*` as in nestest.log).
+//! Memory is read with [`Bus::peek`] so disassembling never disturbs PPU/APU/controller state.
+//!
+//! A [`SymbolTable`] maps addresses to names. When one is supplied, JSR/JMP/branch targets and
+//! memory operands print as labels instead of raw hex. Tables can be loaded from FCEUX
+//! [`.nl` files](https://fceux.com/web/help/NLFilesFormat.html) (`$C000#Reset#comment`).
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::bus::Bus;
+
+/// Address-to-name map used to label disassembly output.
+#[derive(Clone, Debug, Default)]
+pub struct SymbolTable {
+    labels: HashMap<u16, String>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace the label for `addr`.
+    pub fn insert(&mut self, addr: u16, name: impl Into<String>) {
+        self.labels.insert(addr, name.into());
+    }
+
+    /// Label for `addr`, if any.
+    pub fn get(&self, addr: u16) -> Option<&str> {
+        self.labels.get(&addr).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// Parse FCEUX `.nl` text. Each line is `$ADDR#Name#Comment`; `$ADDR/SIZE#Name#` labels an
+    /// array (only the first byte is named). Lines that don't parse, or have an empty name, are
+    /// skipped. Later lines override earlier ones for the same address.
+    pub fn parse_nl(text: &str) -> Self {
+        let mut table = Self::new();
+        for line in text.lines() {
+            let mut fields = line.trim().splitn(3, '#');
+            let (Some(addr), Some(name)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            let addr = addr.trim_start_matches('$');
+            let addr = addr.split('/').next().unwrap_or(addr);
+            let Ok(addr) = u16::from_str_radix(addr, 16) else {
+                continue;
+            };
+            if !name.is_empty() {
+                table.insert(addr, name);
+            }
+        }
+        table
+    }
+
+    /// Load an FCEUX `.nl` file (e.g. `game.nes.ram.nl` or `game.nes.0.nl`).
+    pub fn load_nl(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::parse_nl(&fs::read_to_string(path)?))
+    }
+
+    /// Merge `other` into this table; `other` wins on conflicts.
+    pub fn extend(&mut self, other: SymbolTable) {
+        self.labels.extend(other.labels);
+    }
+}
+
+impl From<HashMap<u16, String>> for SymbolTable {
+    fn from(labels: HashMap<u16, String>) -> Self {
+        Self { labels }
+    }
+}
+
+/// 6502 addressing mode; decides operand size and how the operand is printed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AddrMode {
+    Implied,
+    Accumulator,
+    Immediate,
+    ZeroPage,
+    ZeroPageX,
+    ZeroPageY,
+    Absolute,
+    AbsoluteX,
+    AbsoluteY,
+    Indirect,
+    IndirectX,
+    IndirectY,
+    Relative,
+}
+
+impl AddrMode {
+    /// Operand bytes following the opcode (0–2).
+    pub fn operand_len(self) -> u16 {
+        match self {
+            AddrMode::Implied | AddrMode::Accumulator => 0,
+            AddrMode::Absolute
+            | AddrMode::AbsoluteX
+            | AddrMode::AbsoluteY
+            | AddrMode::Indirect => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// One decoded instruction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Instruction {
+    pub addr: u16,
+    pub opcode: u8,
+    pub mode: AddrMode,
+    /// Raw bytes: opcode followed by `len - 1` operand bytes (unused bytes are zero).
+    pub bytes: [u8; 3],
+    /// Total length in bytes (1–3).
+    pub len: u16,
+    /// Assembly text, e.g. `JSR Reset` or `LDA ($20),Y`.
+    pub text: String,
+}
+
+impl fmt::Display for Instruction {
+    /// nestest.log-style line: `C000  4C F5 C5  JMP $C5F5`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut hex = String::new();
+        for (i, b) in self.bytes[..self.len as usize].iter().enumerate() {
+            if i > 0 {
+                hex.push(' ');
+            }
+            hex.push_str(&format!("{b:02X}"));
+        }
+        // Unofficial opcodes' `*` takes the second separator space, as in nestest.log.
+        let gap = if self.text.starts_with('*') { " " } else { "  " };
+        write!(f, "{:04X}  {:<8}{}{}", self.addr, hex, gap, self.text)
+    }
+}
+
+/// Disassemble the instruction at `addr`, reading memory with side-effect-free `peek`.
+pub fn disassemble<B: Bus>(bus: &mut B, addr: u16, symbols: Option<&SymbolTable>) -> Instruction {
+    let bytes = [
+        bus.peek(addr),
+        bus.peek(addr.wrapping_add(1)),
+        bus.peek(addr.wrapping_add(2)),
+    ];
+    decode(bytes, addr, symbols)
+}
+
+/// Disassemble `count` consecutive instructions starting at `addr`.
+pub fn disassemble_range<B: Bus>(
+    bus: &mut B,
+    mut addr: u16,
+    count: usize,
+    symbols: Option<&SymbolTable>,
+) -> Vec<Instruction> {
+    let mut out = Vec::with_capacity(count);
+    for _ in 0..count {
+        let ins = disassemble(bus, addr, symbols);
+        addr = addr.wrapping_add(ins.len);
+        out.push(ins);
+    }
+    out
+}
+
+/// Decode an instruction from its raw bytes (`bytes[0]` is the opcode at `addr`).
+pub fn decode(bytes: [u8; 3], addr: u16, symbols: Option<&SymbolTable>) -> Instruction {
+    let opcode = bytes[0];
+    let (mnemonic, mode) = OPCODES[opcode as usize];
+    let len = 1 + mode.operand_len();
+    let mut bytes = bytes;
+    bytes[len as usize..].fill(0);
+
+    let b1 = bytes[1];
+    let word = u16::from_le_bytes([bytes[1], bytes[2]]);
+    let zp = |v: u8| name_or(symbols, v as u16, format!("${v:02X}"));
+    let abs = |v: u16| name_or(symbols, v, format!("${v:04X}"));
+    let operand = match mode {
+        AddrMode::Implied => String::new(),
+        AddrMode::Accumulator => "A".to_string(),
+        AddrMode::Immediate => format!("#${b1:02X}"),
+        AddrMode::ZeroPage => zp(b1),
+        AddrMode::ZeroPageX => format!("{},X", zp(b1)),
+        AddrMode::ZeroPageY => format!("{},Y", zp(b1)),
+        AddrMode::Absolute => abs(word),
+        AddrMode::AbsoluteX => format!("{},X", abs(word)),
+        AddrMode::AbsoluteY => format!("{},Y", abs(word)),
+        AddrMode::Indirect => format!("({})", abs(word)),
+        AddrMode::IndirectX => format!("({},X)", zp(b1)),
+        AddrMode::IndirectY => format!("({}),Y", zp(b1)),
+        // Branch offset is relative to the address after the 2-byte instruction.
+        AddrMode::Relative => abs(addr.wrapping_add(2).wrapping_add(b1 as i8 as u16)),
+    };
+
+    let prefix = if is_official(opcode) { "" } else { "*" };
+    let text = if operand.is_empty() {
+        format!("{prefix}{mnemonic}")
+    } else {
+        format!("{prefix}{mnemonic} {operand}")
+    };
+
+    Instruction {
+        addr,
+        opcode,
+        mode,
+        bytes,
+        len,
+        text,
+    }
+}
+
+fn name_or(symbols: Option<&SymbolTable>, addr: u16, hex: String) -> String {
+    symbols
+        .and_then(|s| s.get(addr))
+        .map_or(hex, str::to_string)
+}
+
+/// Addressing mode of `opcode` (decides how many operand bytes follow it).
+pub fn addr_mode(opcode: u8) -> AddrMode {
+    OPCODES[opcode as usize].1
+}
+
+/// True for the 151 documented opcodes; false for unofficial ones (including JAM, the extra NOPs
+/// and $EB SBC).
+pub fn is_official(opcode: u8) -> bool {
+    let (mnemonic, _) = OPCODES[opcode as usize];
+    match mnemonic {
+        "NOP" => opcode == 0xEA,
+        "SBC" => opcode != 0xEB,
+        "JAM" | "SLO" | "RLA" | "SRE" | "RRA" | "SAX" | "LAX" | "DCP" | "ISB" | "ANC" | "ALR"
+        | "ARR" | "XAA" | "AXS" | "AHX" | "TAS" | "SHY" | "SHX" | "LAS" => false,
+        _ => true,
+    }
+}
+
+use AddrMode::{
+    Absolute as Abs, AbsoluteX as Abx, AbsoluteY as Aby, Accumulator as Acc, Immediate as Imm,
+    Implied as Imp, Indirect as Ind, IndirectX as Izx, IndirectY as Izy, Relative as Rel,
+    ZeroPage as Zp, ZeroPageX as Zpx, ZeroPageY as Zpy,
+};
+
+/// Mnemonic and addressing mode for every opcode, row by high nibble.
+#[rustfmt::skip]
+const OPCODES: [(&str, AddrMode); 256] = [
+    // $00
+    ("BRK", Imp), ("ORA", Izx), ("JAM", Imp), ("SLO", Izx), ("NOP", Zp),  ("ORA", Zp),  ("ASL", Zp),  ("SLO", Zp),
+    ("PHP", Imp), ("ORA", Imm), ("ASL", Acc), ("ANC", Imm), ("NOP", Abs), ("ORA", Abs), ("ASL", Abs), ("SLO", Abs),
+    // $10
+    ("BPL", Rel), ("ORA", Izy), ("JAM", Imp), ("SLO", Izy), ("NOP", Zpx), ("ORA", Zpx), ("ASL", Zpx), ("SLO", Zpx),
+    ("CLC", Imp), ("ORA", Aby), ("NOP", Imp), ("SLO", Aby), ("NOP", Abx), ("ORA", Abx), ("ASL", Abx), ("SLO", Abx),
+    // $20
+    ("JSR", Abs), ("AND", Izx), ("JAM", Imp), ("RLA", Izx), ("BIT", Zp),  ("AND", Zp),  ("ROL", Zp),  ("RLA", Zp),
+    ("PLP", Imp), ("AND", Imm), ("ROL", Acc), ("ANC", Imm), ("BIT", Abs), ("AND", Abs), ("ROL", Abs), ("RLA", Abs),
+    // $30
+    ("BMI", Rel), ("AND", Izy), ("JAM", Imp), ("RLA", Izy), ("NOP", Zpx), ("AND", Zpx), ("ROL", Zpx), ("RLA", Zpx),
+    ("SEC", Imp), ("AND", Aby), ("NOP", Imp), ("RLA", Aby), ("NOP", Abx), ("AND", Abx), ("ROL", Abx), ("RLA", Abx),
+    // $40
+    ("RTI", Imp), ("EOR", Izx), ("JAM", Imp), ("SRE", Izx), ("NOP", Zp),  ("EOR", Zp),  ("LSR", Zp),  ("SRE", Zp),
+    ("PHA", Imp), ("EOR", Imm), ("LSR", Acc), ("ALR", Imm), ("JMP", Abs), ("EOR", Abs), ("LSR", Abs), ("SRE", Abs),
+    // $50
+    ("BVC", Rel), ("EOR", Izy), ("JAM", Imp), ("SRE", Izy), ("NOP", Zpx), ("EOR", Zpx), ("LSR", Zpx), ("SRE", Zpx),
+    ("CLI", Imp), ("EOR", Aby), ("NOP", Imp), ("SRE", Aby), ("NOP", Abx), ("EOR", Abx), ("LSR", Abx), ("SRE", Abx),
+    // $60
+    ("RTS", Imp), ("ADC", Izx), ("JAM", Imp), ("RRA", Izx), ("NOP", Zp),  ("ADC", Zp),  ("ROR", Zp),  ("RRA", Zp),
+    ("PLA", Imp), ("ADC", Imm), ("ROR", Acc), ("ARR", Imm), ("JMP", Ind), ("ADC", Abs), ("ROR", Abs), ("RRA", Abs),
+    // $70
+    ("BVS", Rel), ("ADC", Izy), ("JAM", Imp), ("RRA", Izy), ("NOP", Zpx), ("ADC", Zpx), ("ROR", Zpx), ("RRA", Zpx),
+    ("SEI", Imp), ("ADC", Aby), ("NOP", Imp), ("RRA", Aby), ("NOP", Abx), ("ADC", Abx), ("ROR", Abx), ("RRA", Abx),
+    // $80
+    ("NOP", Imm), ("STA", Izx), ("NOP", Imm), ("SAX", Izx), ("STY", Zp),  ("STA", Zp),  ("STX", Zp),  ("SAX", Zp),
+    ("DEY", Imp), ("NOP", Imm), ("TXA", Imp), ("XAA", Imm), ("STY", Abs), ("STA", Abs), ("STX", Abs), ("SAX", Abs),
+    // $90
+    ("BCC", Rel), ("STA", Izy), ("JAM", Imp), ("AHX", Izy), ("STY", Zpx), ("STA", Zpx), ("STX", Zpy), ("SAX", Zpy),
+    ("TYA", Imp), ("STA", Aby), ("TXS", Imp), ("TAS", Aby), ("SHY", Abx), ("STA", Abx), ("SHX", Aby), ("AHX", Aby),
+    // $A0
+    ("LDY", Imm), ("LDA", Izx), ("LDX", Imm), ("LAX", Izx), ("LDY", Zp),  ("LDA", Zp),  ("LDX", Zp),  ("LAX", Zp),
+    ("TAY", Imp), ("LDA", Imm), ("TAX", Imp), ("LAX", Imm), ("LDY", Abs), ("LDA", Abs), ("LDX", Abs), ("LAX", Abs),
+    // $B0
+    ("BCS", Rel), ("LDA", Izy), ("JAM", Imp), ("LAX", Izy), ("LDY", Zpx), ("LDA", Zpx), ("LDX", Zpy), ("LAX", Zpy),
+    ("CLV", Imp), ("LDA", Aby), ("TSX", Imp), ("LAS", Aby), ("LDY", Abx), ("LDA", Abx), ("LDX", Aby), ("LAX", Aby),
+    // $C0
+    ("CPY", Imm), ("CMP", Izx), ("NOP", Imm), ("DCP", Izx), ("CPY", Zp),  ("CMP", Zp),  ("DEC", Zp),  ("DCP", Zp),
+    ("INY", Imp), ("CMP", Imm), ("DEX", Imp), ("AXS", Imm), ("CPY", Abs), ("CMP", Abs), ("DEC", Abs), ("DCP", Abs),
+    // $D0
+    ("BNE", Rel), ("CMP", Izy), ("JAM", Imp), ("DCP", Izy), ("NOP", Zpx), ("CMP", Zpx), ("DEC", Zpx), ("DCP", Zpx),
+    ("CLD", Imp), ("CMP", Aby), ("NOP", Imp), ("DCP", Aby), ("NOP", Abx), ("CMP", Abx), ("DEC", Abx), ("DCP", Abx),
+    // $E0
+    ("CPX", Imm), ("SBC", Izx), ("NOP", Imm), ("ISB", Izx), ("CPX", Zp),  ("SBC", Zp),  ("INC", Zp),  ("ISB", Zp),
+    ("INX", Imp), ("SBC", Imm), ("NOP", Imp), ("SBC", Imm), ("CPX", Abs), ("SBC", Abs), ("INC", Abs), ("ISB", Abs),
+    // $F0
+    ("BEQ", Rel), ("SBC", Izy), ("JAM", Imp), ("ISB", Izy), ("NOP", Zpx), ("SBC", Zpx), ("INC", Zpx), ("ISB", Zpx),
+    ("SED", Imp), ("SBC", Aby), ("NOP", Imp), ("ISB", Aby), ("NOP", Abx), ("SBC", Abx), ("INC", Abx), ("ISB", Abx),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labeled_address_renders_as_its_name() {
+        let symbols = SymbolTable::parse_nl("$C5F5#Reset#entry point\n$0010#Temp#\n");
+        let jmp = decode([0x4C, 0xF5, 0xC5], 0xC000, Some(&symbols));
+        assert_eq!(jmp.text, "JMP Reset");
+        let lda = decode([0xB5, 0x10, 0x00], 0xC003, Some(&symbols));
+        assert_eq!(lda.text, "LDA Temp,X");
+        let unlabeled = decode([0x4C, 0x00, 0xC0], 0xC005, Some(&symbols));
+        assert_eq!(unlabeled.text, "JMP $C000");
+    }
+}
@@ -2,7 +2,8 @@
 //!
 //! See [Status flags](https://www.nesdev.org/wiki/Status_flags) and
 //! [CPU](https://www.nesdev.org/wiki/CPU). The NES uses the Ricoh 2A03, which omits the 6502's
-//! decimal mode (D flag is stored but not used; ADC/SBC behave as if D=0).
+//! decimal mode (D flag is stored but not used; ADC/SBC behave as if D=0 unless
+//! `CPU::decimal_enabled` is set for generic 6502 use).
 
 pub const FLAG_CARRY: u8 = 1 << 0;       // C: carry from ALU
 pub const FLAG_ZERO: u8 = 1 << 1;       // Z: result zero
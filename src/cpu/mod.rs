@@ -3,7 +3,12 @@
 //! Implements the [CPU](https://www.nesdev.org/wiki/CPU) / [Instruction reference](https://www.nesdev.org/wiki/Instruction_reference):
 //! all official 6502 opcodes plus [unofficial/undocumented opcodes](https://www.nesdev.org/wiki/CPU_unofficial_opcodes)
 //! used by NES software. nestest-compatible. Bus trait abstracts [CPU memory map](https://www.nesdev.org/wiki/CPU_memory_map).
-//! NMI from PPU vblank; reset vector from $FFFC–$FFFD. No DMC/APU IRQ cycle-accurate stall (handled in main loop).
+//! [`breakpoint`] adds conditional debugger breakpoints; [`disasm`] decodes instructions (with optional symbol labels) for debugging.
+//! [`profile`] counts instructions, cycles and (optionally) opcode frequencies.
+//! NMI from PPU vblank; reset vector from $FFFC–$FFFD. DMC and OAM DMA stalls are added by the bus (`Bus::take_stall_cycles`).
 
+pub mod breakpoint;
 pub mod cpu;
+pub mod disasm;
 pub mod flags;
+pub mod profile;
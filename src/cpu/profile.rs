@@ -0,0 +1,51 @@
+//! Execution counters for profiling.
+//!
+//! [`CpuStats`] counts executed instructions and CPU cycles since power-on (or the last
+//! [`CpuStats::reset`]); unlike `CPU::cycles`, which `reset` sets back to 7, these never rewind
+//! on their own. An optional per-opcode histogram shows which instructions are hot; it is off by
+//! default since it costs a table update per instruction.
+
+/// Instruction and cycle totals, plus the opcode histogram when enabled. Read with
+/// `CPU::stats`; enable the histogram with `CPU::set_opcode_histogram`.
+#[derive(Clone, Debug, Default)]
+pub struct CpuStats {
+    /// Instructions executed (interrupt sequences are not counted).
+    pub instructions: u64,
+    /// CPU cycles elapsed, including interrupt sequences and DMA stalls.
+    pub total_cycles: u64,
+    /// Executions per opcode, when enabled.
+    pub opcode_counts: Option<Box<[u64; 256]>>,
+}
+
+impl CpuStats {
+    /// Zero the counters. The histogram stays enabled (or disabled).
+    pub fn reset(&mut self) {
+        self.instructions = 0;
+        self.total_cycles = 0;
+        if let Some(counts) = &mut self.opcode_counts {
+            counts.fill(0);
+        }
+    }
+
+    /// The `n` most executed opcodes as (opcode, count), highest first. Empty if the histogram
+    /// is disabled.
+    pub fn hottest_opcodes(&self, n: usize) -> Vec<(u8, u64)> {
+        let Some(counts) = &self.opcode_counts else {
+            return Vec::new();
+        };
+        let mut ranked: Vec<(u8, u64)> = (0..=255u8)
+            .map(|op| (op, counts[op as usize]))
+            .filter(|&(_, count)| count > 0)
+            .collect();
+        ranked.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+        ranked.truncate(n);
+        ranked
+    }
+
+    pub(crate) fn record_instruction(&mut self, opcode: u8) {
+        self.instructions += 1;
+        if let Some(counts) = &mut self.opcode_counts {
+            counts[opcode as usize] += 1;
+        }
+    }
+}
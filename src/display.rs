@@ -0,0 +1,399 @@
+//! Frame presentation helpers: integer scaling and pixel-aspect correction.
+//!
+//! The PPU produces a 256×240 buffer of square samples, but an NTSC television displays NES pixels
+//! with an 8:7 [pixel aspect ratio](https://www.nesdev.org/wiki/Overscan#Pixel_aspect_ratio), so a
+//! 256-pixel line appears about 292 pixels wide. These helpers scale the framebuffer by a whole
+//! number (nearest neighbour, no blur) and optionally stretch it horizontally to that width.
+//!
+//! [`ColorAdjust`] is an optional picture adjustment (brightness, contrast, hue, saturation)
+//! applied to the 256×240 frame before scaling.
+//!
+//! [`CrtFilter`] is an optional scanline/phosphor look applied to the scaled output.
+//!
+//! [`Overscan`] crops the frame edges a television would hide; presenting and screenshots both
+//! use it so captures match what is on screen.
+//!
+//! [`FrameSkipper`] decides which frames get presented at all, for hosts too slow to present
+//! every frame; the emulation itself still runs every frame.
+
+/// Width of the PPU framebuffer in pixels.
+pub const NES_WIDTH: usize = 256;
+/// Height of the PPU framebuffer in pixels.
+pub const NES_HEIGHT: usize = 240;
+
+/// Smallest and largest integer scale offered by the frontend.
+pub const MIN_SCALE: usize = 1;
+pub const MAX_SCALE: usize = 6;
+
+/// Horizontal pixel shape used when presenting the frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PixelAspect {
+    /// 1:1 pixels; output is 256×240 per scale step.
+    Square,
+    /// NTSC 8:7 pixels; output is ~292×240 per scale step.
+    Ntsc,
+}
+
+/// Most frames auto frame skip drops in a row, so the picture keeps updating on very slow hosts.
+pub const MAX_AUTO_SKIP: u32 = 4;
+
+/// Frame presentation cadence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameSkip {
+    /// Present one frame, then skip the next `n` (0 = present every frame).
+    Fixed(u32),
+    /// Skip presenting after a frame that overran its time budget (up to `MAX_AUTO_SKIP` in a row).
+    Auto,
+}
+
+impl Default for FrameSkip {
+    fn default() -> Self {
+        FrameSkip::Fixed(0)
+    }
+}
+
+/// Tracks skipped frames and decides whether the next emulated frame is presented.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameSkipper {
+    pub mode: FrameSkip,
+    skipped: u32,
+}
+
+impl FrameSkipper {
+    pub fn new(mode: FrameSkip) -> Self {
+        Self { mode, skipped: 0 }
+    }
+
+    /// Call once per emulated frame. `previous_frame_late` is true when the last frame took longer
+    /// than the region's frame duration. Returns true if this frame should be presented.
+    pub fn should_present(&mut self, previous_frame_late: bool) -> bool {
+        let skip = match self.mode {
+            FrameSkip::Fixed(n) => self.skipped < n,
+            FrameSkip::Auto => previous_frame_late && self.skipped < MAX_AUTO_SKIP,
+        };
+        if skip {
+            self.skipped += 1;
+        } else {
+            self.skipped = 0;
+        }
+        !skip
+    }
+}
+
+/// Picture adjustment applied to the RGB frame before presenting. The default leaves colors
+/// untouched.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorAdjust {
+    /// Added to each channel, -1.0–1.0 (0 = unchanged; ±0.1 is a visible but mild change).
+    pub brightness: f32,
+    /// Scale around mid-grey, 0.0–2.0 (1 = unchanged).
+    pub contrast: f32,
+    /// Hue rotation in degrees, -180–180 (0 = unchanged).
+    pub hue: f32,
+    /// Chroma scale, 0.0 (greyscale) – 2.0 (1 = unchanged).
+    pub saturation: f32,
+}
+
+impl Default for ColorAdjust {
+    fn default() -> Self {
+        Self {
+            brightness: 0.0,
+            contrast: 1.0,
+            hue: 0.0,
+            saturation: 1.0,
+        }
+    }
+}
+
+impl ColorAdjust {
+    /// True when applying this adjustment would not change any pixel.
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Adjust one 0xRRGGBB pixel. Hue and saturation act on the chroma (I/Q) of the YIQ color
+    /// space, so rotating hue keeps brightness; then contrast and brightness act per channel.
+    pub fn apply(&self, rgb: u32) -> u32 {
+        let r = ((rgb >> 16) & 0xFF) as f32 / 255.0;
+        let g = ((rgb >> 8) & 0xFF) as f32 / 255.0;
+        let b = (rgb & 0xFF) as f32 / 255.0;
+
+        let y = 0.299 * r + 0.587 * g + 0.114 * b;
+        let i = 0.596 * r - 0.274 * g - 0.322 * b;
+        let q = 0.211 * r - 0.523 * g + 0.312 * b;
+        let (sin, cos) = self.hue.to_radians().sin_cos();
+        let i2 = (i * cos - q * sin) * self.saturation;
+        let q2 = (i * sin + q * cos) * self.saturation;
+
+        let channel = |c: f32| {
+            let c = (c - 0.5) * self.contrast + 0.5 + self.brightness;
+            (c.clamp(0.0, 1.0) * 255.0).round() as u32
+        };
+        let r = channel(y + 0.956 * i2 + 0.621 * q2);
+        let g = channel(y - 0.272 * i2 - 0.647 * q2);
+        let b = channel(y - 1.106 * i2 + 1.703 * q2);
+        (r << 16) | (g << 8) | b
+    }
+}
+
+/// Apply `adjust` to every pixel of `frame` in place. Does nothing for the identity adjustment.
+pub fn adjust_frame(frame: &mut [u32], adjust: &ColorAdjust) {
+    if adjust.is_identity() {
+        return;
+    }
+    for px in frame {
+        *px = adjust.apply(*px);
+    }
+}
+
+/// Scanline/CRT post-process for the scaled output buffer. Off by default.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CrtFilter {
+    pub enabled: bool,
+    /// How much odd output rows are darkened: 0.0 (no scanlines) – 1.0 (black).
+    pub scanline_intensity: f32,
+    /// Soften each row horizontally (1-2-1 blend of neighbouring pixels), like phosphor bleed.
+    pub blur: bool,
+}
+
+impl Default for CrtFilter {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            scanline_intensity: 0.5,
+            blur: false,
+        }
+    }
+}
+
+impl CrtFilter {
+    /// Same settings, switched on or off.
+    pub fn toggle(&self) -> Self {
+        Self {
+            enabled: !self.enabled,
+            ..*self
+        }
+    }
+}
+
+/// Apply `filter` in place to a scaled `width`-pixel-wide buffer (output of `scale_frame`). Does
+/// nothing when the filter is disabled.
+pub fn apply_crt_filter(buf: &mut [u32], width: usize, filter: &CrtFilter) {
+    if !filter.enabled || width == 0 {
+        return;
+    }
+    let keep = ((1.0 - filter.scanline_intensity.clamp(0.0, 1.0)) * 256.0) as u32;
+    for (y, row) in buf.chunks_exact_mut(width).enumerate() {
+        if filter.blur {
+            let mut prev = row[0];
+            for x in 0..width {
+                let cur = row[x];
+                let next = row[(x + 1).min(width - 1)];
+                row[x] = blend_121(prev, cur, next);
+                prev = cur;
+            }
+        }
+        if y % 2 == 1 {
+            for px in row.iter_mut() {
+                *px = scale_rgb(*px, keep);
+            }
+        }
+    }
+}
+
+/// Per-channel (a + 2b + c) / 4.
+fn blend_121(a: u32, b: u32, c: u32) -> u32 {
+    let channel = |shift: u32| {
+        let v = ((a >> shift) & 0xFF) + 2 * ((b >> shift) & 0xFF) + ((c >> shift) & 0xFF);
+        (v / 4) << shift
+    };
+    channel(16) | channel(8) | channel(0)
+}
+
+/// Multiply each channel by `factor / 256`.
+fn scale_rgb(rgb: u32, factor: u32) -> u32 {
+    let channel = |shift: u32| ((((rgb >> shift) & 0xFF) * factor) >> 8) << shift;
+    channel(16) | channel(8) | channel(0)
+}
+
+/// Pixels hidden at each edge of the 256×240 frame, as a TV's
+/// [overscan](https://www.nesdev.org/wiki/Overscan) would. Default: nothing cropped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Overscan {
+    pub top: usize,
+    pub bottom: usize,
+    pub left: usize,
+    pub right: usize,
+}
+
+impl Overscan {
+    /// The same crop on all four edges (e.g. 8 → 240×224 visible).
+    pub fn uniform(px: usize) -> Self {
+        Self {
+            top: px,
+            bottom: px,
+            left: px,
+            right: px,
+        }
+    }
+
+    /// Visible (width, height) after cropping.
+    pub fn visible_size(&self) -> (usize, usize) {
+        (
+            NES_WIDTH.saturating_sub(self.left + self.right).max(1),
+            NES_HEIGHT.saturating_sub(self.top + self.bottom).max(1),
+        )
+    }
+
+    /// Copy the visible part of a 256×240 frame into `dst` (resized to `visible_size`).
+    pub fn crop(&self, src: &[u32], dst: &mut Vec<u32>) {
+        let (width, height) = self.visible_size();
+        dst.clear();
+        for y in self.top..self.top + height {
+            let row = y * NES_WIDTH + self.left;
+            dst.extend_from_slice(&src[row..row + width]);
+        }
+    }
+}
+
+/// Integer scale factor, pixel aspect and overscan crop used to size the window and the
+/// presented buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DisplayConfig {
+    pub scale: usize,
+    pub aspect: PixelAspect,
+    pub overscan: Overscan,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            scale: 3,
+            aspect: PixelAspect::Square,
+            overscan: Overscan::default(),
+        }
+    }
+}
+
+impl DisplayConfig {
+    /// Config with `scale` clamped to `MIN_SCALE..=MAX_SCALE` and no overscan crop.
+    pub fn new(scale: usize, aspect: PixelAspect) -> Self {
+        Self {
+            scale: scale.clamp(MIN_SCALE, MAX_SCALE),
+            aspect,
+            overscan: Overscan::default(),
+        }
+    }
+
+    /// Output size in pixels (width, height) for this scale, aspect and overscan.
+    pub fn output_size(&self) -> (usize, usize) {
+        let (visible_w, visible_h) = self.overscan.visible_size();
+        let width = match self.aspect {
+            PixelAspect::Square => visible_w * self.scale,
+            PixelAspect::Ntsc => visible_w * self.scale * 8 / 7,
+        };
+        (width, visible_h * self.scale)
+    }
+
+    /// This config at the largest integer scale (at least 1×, possibly above `MAX_SCALE`) whose
+    /// output fits in `width`×`height`, e.g. for a window the user has resized.
+    pub fn fit_to(&self, width: usize, height: usize) -> Self {
+        let (unit_w, unit_h) = Self { scale: 1, ..*self }.output_size();
+        let scale = (width / unit_w).min(height / unit_h).max(MIN_SCALE);
+        Self { scale, ..*self }
+    }
+
+    /// Next scale in the 1×–6× cycle (wraps back to 1×).
+    pub fn next_scale(&self) -> Self {
+        let scale = if self.scale >= MAX_SCALE {
+            MIN_SCALE
+        } else {
+            self.scale + 1
+        };
+        Self { scale, ..*self }
+    }
+
+    /// Same scale with the other pixel aspect.
+    pub fn toggle_aspect(&self) -> Self {
+        let aspect = match self.aspect {
+            PixelAspect::Square => PixelAspect::Ntsc,
+            PixelAspect::Ntsc => PixelAspect::Square,
+        };
+        Self { aspect, ..*self }
+    }
+}
+
+/// Scale the visible part (after `config.overscan`) of a 256×240 frame into `dst` using
+/// nearest-neighbour sampling. `dst` is resized to `config.output_size()`; each source row is
+/// repeated `scale` times and each column is repeated `scale` (square) or ~`scale * 8/7` (NTSC
+/// aspect) times.
+pub fn scale_frame(src: &[u32], dst: &mut Vec<u32>, config: &DisplayConfig) {
+    let (out_w, out_h) = config.output_size();
+    let (visible_w, _) = config.overscan.visible_size();
+    dst.resize(out_w * out_h, 0);
+
+    for oy in 0..out_h {
+        let sy = oy / config.scale + config.overscan.top;
+        let row_start = sy * NES_WIDTH + config.overscan.left;
+        let src_row = &src[row_start..row_start + visible_w];
+        let dst_row = &mut dst[oy * out_w..(oy + 1) * out_w];
+        for (ox, px) in dst_row.iter_mut().enumerate() {
+            *px = src_row[ox * visible_w / out_w];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity_color_adjust_leaves_frame_unchanged() {
+        let frame: Vec<u32> = (0..4096).map(|i| i * 0x0FED).collect();
+        let mut adjusted = frame.clone();
+        adjust_frame(&mut adjusted, &ColorAdjust::default());
+        assert_eq!(adjusted, frame);
+        let identity = ColorAdjust::default();
+        assert!(frame.iter().all(|&px| identity.apply(px) == px & 0xFF_FFFF));
+    }
+
+    #[test]
+    fn crt_filter_dims_alternate_rows() {
+        let mut buf = vec![0x80_80_80; 4 * 4];
+        let filter = CrtFilter {
+            enabled: true,
+            ..CrtFilter::default()
+        };
+        apply_crt_filter(&mut buf, 4, &filter);
+        for (y, row) in buf.chunks(4).enumerate() {
+            let expected = if y % 2 == 1 { 0x40_40_40 } else { 0x80_80_80 };
+            assert!(row.iter().all(|&px| px == expected), "row {y}");
+        }
+
+        let mut untouched = vec![0x80_80_80; 4 * 4];
+        apply_crt_filter(&mut untouched, 4, &CrtFilter::default());
+        assert!(untouched.iter().all(|&px| px == 0x80_80_80));
+    }
+
+    #[test]
+    fn fixed_frame_skip_presents_every_n_plus_first_frame() {
+        let mut skipper = FrameSkipper::new(FrameSkip::Fixed(2));
+        let run: Vec<bool> = (0..6).map(|_| skipper.should_present(false)).collect();
+        assert_eq!(run, [false, false, true, false, false, true]);
+
+        // Auto never skips more than MAX_AUTO_SKIP frames in a row, however late.
+        let mut skipper = FrameSkipper::new(FrameSkip::Auto);
+        let run: Vec<bool> = (0..6).map(|_| skipper.should_present(true)).collect();
+        assert_eq!(run, [false, false, false, false, true, false]);
+    }
+
+    #[test]
+    fn fit_to_picks_the_largest_integer_scale_that_fits() {
+        let config = DisplayConfig::new(2, PixelAspect::Square);
+        assert_eq!(config.fit_to(1000, 800).scale, 3);
+        assert_eq!(config.fit_to(600, 1000).scale, 2);
+        assert_eq!(config.fit_to(100, 100).scale, 1);
+        let resized = config.fit_to(1000, 800);
+        assert_eq!((resized.aspect, resized.overscan), (config.aspect, config.overscan));
+    }
+}
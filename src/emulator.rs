@@ -0,0 +1,371 @@
+//! Top-level emulator: the 6502 CPU wired to the NES bus, plus console-level settings.
+//!
+//! Frontends hold an `Emulator` instead of assembling `CPU<NesBus>` by hand. The region decides
+//! frame pacing (see [Cycle reference chart](https://www.nesdev.org/wiki/Cycle_reference_chart)).
+//! [`Emulator::run_frame`] hands finished scanlines and frames to a [`FrameSink`], so a frontend
+//! only has to implement presentation; [`Emulator::next_frame`] instead returns the finished
+//! [`Frame`] with the audio produced during it.
+
+use std::time::Duration;
+
+use crate::bus::{Bus, NesBus};
+use crate::cartridge::cartridge::Cartridge;
+use crate::cpu::cpu::CPU;
+use crate::region::Region;
+
+/// Status and message a test ROM reported through the $6000 protocol used by blargg's tests.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TestResult {
+    /// $6000: $80 = running, $81 = press reset, otherwise the final result (0 = passed).
+    pub status: u8,
+    /// NUL-terminated ASCII text from $6004.
+    pub message: String,
+}
+
+impl TestResult {
+    pub fn is_running(&self) -> bool {
+        self.status == 0x80
+    }
+
+    /// The ROM asks for the reset button to be pressed (after at least 100 ms).
+    pub fn needs_reset(&self) -> bool {
+        self.status == 0x81
+    }
+
+    /// Finished with status 0.
+    pub fn passed(&self) -> bool {
+        self.status == 0
+    }
+}
+
+/// What [`Emulator::step_instruction`] executed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StepResult {
+    /// Opcode of the instruction that ran.
+    pub opcode: u8,
+    /// CPU cycles taken, including any interrupt sequence and DMA stalls.
+    pub cycles: usize,
+    /// PC after the instruction (the next instruction's address).
+    pub pc: u16,
+    /// An NMI or IRQ was serviced first; `opcode` is then the handler's first instruction.
+    pub interrupt: bool,
+}
+
+/// One emulated frame from [`Emulator::next_frame`]: the picture and the audio generated while
+/// it was drawn.
+#[derive(Clone, Copy, Debug)]
+pub struct Frame<'a> {
+    /// 256×240 pixels, 0xRRGGBB, row-major.
+    pub framebuffer: &'a [u32],
+    /// Mono samples in -1..1 at the APU's sample rate (44.1 kHz; ~735 per NTSC frame).
+    pub audio: &'a [f32],
+}
+
+/// Receives video output from [`Emulator::run_frame`].
+pub trait FrameSink {
+    /// A complete 256×240 frame (0xRRGGBB), called once per frame at the start of vblank.
+    fn present(&mut self, framebuffer: &[u32]);
+    /// Visible scanline `line` (0–239) has just been rendered. Called in order, before the
+    /// frame's `present`. Default: ignored.
+    fn scanline(&mut self, _line: u16, _pixels: &[u32; 256]) {}
+}
+
+/// Discards all output (headless runs, tests).
+impl FrameSink for () {
+    fn present(&mut self, _framebuffer: &[u32]) {}
+}
+
+/// A powered-on NES: CPU (which owns the bus, PPU, APU, cartridge, controller) and its region.
+#[derive(Clone)]
+pub struct Emulator {
+    pub cpu: CPU<NesBus>,
+    region: Region,
+    /// Samples handed out by `drain_audio`, converted to -1..1.
+    audio: Vec<f32>,
+}
+
+impl Emulator {
+    /// Power on with `cart` inserted and take the reset vector ($FFFC–$FFFD).
+    pub fn new(cart: Cartridge, region: Region) -> Self {
+        let mut cpu = CPU::new(NesBus::new(cart, region));
+        cpu.reset();
+        Self {
+            cpu,
+            region,
+            audio: Vec::new(),
+        }
+    }
+
+    /// Press the reset button: the APU is silenced and the CPU restarts through the reset vector.
+    /// RAM, the PPU and the cartridge keep their state.
+    pub fn reset(&mut self) {
+        self.cpu.bus.apu.reset();
+        self.cpu.reset();
+    }
+
+    /// Replace the cartridge without rebuilding the emulator (e.g. from a ROM browser): the bus is
+    /// power-cycled and the CPU reset through the new cartridge's vector.
+    pub fn load_cartridge(&mut self, cart: Cartridge) {
+        self.cpu.bus.load_cartridge(cart);
+        self.cpu.reset();
+    }
+
+    /// Run until the PPU enters vblank, passing each scanline rendered along the way and then the
+    /// finished frame to `sink`. Returns false (without presenting) once the CPU halts.
+    ///
+    /// If called mid-frame, only the scanlines still to come are passed; a call starting in
+    /// vblank waits for the next frame's first line.
+    pub fn run_frame(&mut self, sink: &mut dyn FrameSink) -> bool {
+        let ppu = &self.cpu.bus.ppu;
+        let mut waiting_for_frame = ppu.scanline >= 240;
+        let mut next_line = if waiting_for_frame { 0 } else { ppu.scanline.max(0) as usize };
+        loop {
+            self.cpu.step();
+            if self.cpu.halted {
+                return false;
+            }
+            let ppu = &self.cpu.bus.ppu;
+            waiting_for_frame &= ppu.scanline >= 240;
+            if !waiting_for_frame {
+                // Lines above the current one are complete (rendered when they finished).
+                let completed = ppu.scanline.clamp(0, 240) as usize;
+                while next_line < completed {
+                    let row = &ppu.framebuffer[next_line * 256..(next_line + 1) * 256];
+                    sink.scanline(next_line as u16, row.try_into().expect("256-pixel row"));
+                    next_line += 1;
+                }
+            }
+            if ppu.frame_ready {
+                sink.present(&ppu.framebuffer);
+                self.cpu.bus.clear_frame_ready();
+                return true;
+            }
+        }
+    }
+
+    /// Run until the next vblank like `run_frame`, then return the finished frame with the audio
+    /// produced since the last drain. `None` once the CPU halts.
+    pub fn next_frame(&mut self) -> Option<Frame<'_>> {
+        if !self.run_frame(&mut ()) {
+            return None;
+        }
+        self.fill_audio();
+        Some(Frame {
+            framebuffer: &self.cpu.bus.ppu.framebuffer,
+            audio: &self.audio,
+        })
+    }
+
+    /// Take all APU samples generated since the last drain, converted from mixer output 0..1 to
+    /// -1..1 for playback.
+    pub fn drain_audio(&mut self) -> &[f32] {
+        self.fill_audio();
+        &self.audio
+    }
+
+    fn fill_audio(&mut self) {
+        self.audio.clear();
+        let mut chunk = [0.0f32; 1024];
+        loop {
+            let n = self.cpu.bus.apu.drain_samples(&mut chunk);
+            let samples = chunk[..n].iter().map(|s| (s * 2.0 - 1.0).clamp(-1.0, 1.0));
+            self.audio.extend(samples);
+            if n < chunk.len() {
+                break;
+            }
+        }
+    }
+
+    /// Execute one instruction, with the PPU and APU ticked in lockstep, for single-stepping in a
+    /// debugger. A pending interrupt is serviced first, so the step lands inside its handler.
+    pub fn step_instruction(&mut self) -> StepResult {
+        let mut cycles = 0;
+        let mut interrupt = false;
+        while self.cpu.interrupt_pending() && !self.cpu.halted {
+            cycles += self.cpu.step();
+            interrupt = true;
+        }
+        let opcode = self.cpu.bus.peek(self.cpu.pc);
+        cycles += self.cpu.step();
+        StepResult {
+            opcode,
+            cycles,
+            pc: self.cpu.pc,
+            interrupt,
+        }
+    }
+
+    /// Console region used for timing.
+    pub fn region(&self) -> Region {
+        self.region
+    }
+
+    /// PRG bank serving CPU `addr`, in the mapper's bank units (`None` if it doesn't report).
+    pub fn prg_bank_at(&self, addr: u16) -> Option<usize> {
+        self.cpu.bus.cart.prg_bank_at(addr)
+    }
+
+    /// CHR bank serving PPU `addr`, in the mapper's bank units (`None` if it doesn't report).
+    pub fn chr_bank_at(&self, addr: u16) -> Option<usize> {
+        self.cpu.bus.cart.chr_bank_at(addr)
+    }
+
+    /// Test ROM output, if the cartridge's PRG RAM holds the $DE $B0 $61 signature at
+    /// $6001–$6003: status from $6000 and the message from $6004 (read up to the first NUL).
+    pub fn test_output(&self) -> Option<TestResult> {
+        let cart = &self.cpu.bus.cart;
+        if [cart.read(0x6001), cart.read(0x6002), cart.read(0x6003)] != [0xDE, 0xB0, 0x61] {
+            return None;
+        }
+        let message = (0x6004..=0x7FFF)
+            .map(|addr| cart.read(addr))
+            .take_while(|&b| b != 0)
+            .map(char::from)
+            .collect();
+        Some(TestResult {
+            status: cart.read(0x6000),
+            message,
+        })
+    }
+
+    /// Wall-clock time one emulated frame should take: ~16.64 ms (NTSC) or ~20.00 ms (PAL).
+    pub fn target_frame_duration(&self) -> Duration {
+        self.region.frame_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::mapper::Mirroring;
+    use crate::display::{FrameSkip, FrameSkipper};
+
+    /// NROM cartridge with `program` at $8000 and every vector pointing there.
+    fn nrom(program: &[u8]) -> Cartridge {
+        let mut prg = vec![0xEA; 32 * 1024];
+        prg[..program.len()].copy_from_slice(program);
+        prg[0x7FFA..].copy_from_slice(&[0x00, 0x80, 0x00, 0x80, 0x00, 0x80]);
+        Cartridge::from_rom(prg, Vec::new(), 0, Mirroring::Horizontal).unwrap()
+    }
+
+    #[test]
+    fn load_cartridge_takes_the_new_reset_vector() {
+        let mut emu = Emulator::new(nrom(&[]), Region::Ntsc);
+        assert_eq!(emu.cpu.pc, 0x8000);
+        let mut prg = vec![0xEA; 32 * 1024];
+        prg[0x7FFC..0x7FFE].copy_from_slice(&[0x34, 0x92]);
+        emu.load_cartridge(Cartridge::from_rom(prg, Vec::new(), 0, Mirroring::Vertical).unwrap());
+        assert_eq!(emu.cpu.pc, 0x9234);
+    }
+
+    #[test]
+    fn step_instruction_reports_each_opcode_in_order() {
+        // LDA #$01; STA $00; INX; JMP $8000
+        let program = [0xA9, 0x01, 0x85, 0x00, 0xE8, 0x4C, 0x00, 0x80];
+        let mut emu = Emulator::new(nrom(&program), Region::Ntsc);
+        let steps: Vec<_> = (0..5).map(|_| emu.step_instruction()).collect();
+        let opcodes: Vec<u8> = steps.iter().map(|step| step.opcode).collect();
+        assert_eq!(opcodes, [0xA9, 0x85, 0xE8, 0x4C, 0xA9]);
+        let pcs: Vec<u16> = steps.iter().map(|step| step.pc).collect();
+        assert_eq!(pcs, [0x8002, 0x8004, 0x8005, 0x8000, 0x8002]);
+        let cycles: Vec<usize> = steps.iter().map(|step| step.cycles).collect();
+        assert_eq!(cycles, [2, 3, 2, 3, 2]);
+        assert!(steps.iter().all(|step| !step.interrupt));
+    }
+
+    #[test]
+    fn test_output_reads_status_and_message_from_prg_ram() {
+        let prg = vec![0; 32 * 1024];
+        let cart = Cartridge::from_rom(prg, Vec::new(), 1, Mirroring::Vertical).unwrap();
+        let mut emu = Emulator::new(cart, Region::Ntsc);
+        assert_eq!(emu.test_output(), None);
+        let bytes = [&[0x00, 0xDE, 0xB0, 0x61][..], b"Passed\n\0"].concat();
+        for (i, &b) in bytes.iter().enumerate() {
+            emu.cpu.bus.write(0x6000 + i as u16, b);
+        }
+        let result = emu.test_output().unwrap();
+        assert!(result.passed());
+        assert_eq!(result.message, "Passed\n");
+    }
+
+    #[test]
+    fn next_frame_returns_one_frame_of_audio() {
+        let mut emu = Emulator::new(nrom(&[]), Region::Ntsc);
+        emu.next_frame().unwrap();
+        let frame = emu.next_frame().unwrap();
+        assert_eq!(frame.framebuffer.len(), 256 * 240);
+        // 29780.5 CPU cycles at 1.789773 MHz is ~733.8 samples at 44.1 kHz.
+        assert!((730..=740).contains(&frame.audio.len()), "{} samples", frame.audio.len());
+    }
+
+    #[test]
+    fn pal_paces_frames_at_50_hz() {
+        let emu = Emulator::new(nrom(&[]), Region::Pal);
+        assert!((emu.region().frame_rate() - 50.007).abs() < 0.001);
+        let frame_ms = emu.target_frame_duration().as_secs_f64() * 1000.0;
+        assert!((frame_ms - 20.0).abs() < 0.01);
+    }
+
+    /// Records the scanlines and frames it is given.
+    #[derive(Default)]
+    struct RecordingSink {
+        lines: Vec<u16>,
+        frames: Vec<Vec<u32>>,
+    }
+
+    impl FrameSink for RecordingSink {
+        fn present(&mut self, framebuffer: &[u32]) {
+            self.frames.push(framebuffer.to_vec());
+        }
+
+        fn scanline(&mut self, line: u16, _pixels: &[u32; 256]) {
+            self.lines.push(line);
+        }
+    }
+
+    #[test]
+    fn run_frame_passes_each_scanline_then_presents_the_frame() {
+        let mut emu = Emulator::new(nrom(&[]), Region::Ntsc);
+        let mut sink = RecordingSink::default();
+        assert!(emu.run_frame(&mut sink));
+        assert!(emu.run_frame(&mut sink));
+        assert_eq!(sink.frames.len(), 2);
+        assert!(sink.frames.iter().all(|frame| frame.len() == 256 * 240));
+        let expected: Vec<u16> = (0..240).chain(0..240).collect();
+        assert_eq!(sink.lines, expected);
+    }
+
+    /// Counts the frames a `FrameSkipper` lets through, like the windowed frontend's sink.
+    struct SkippingSink {
+        skipper: FrameSkipper,
+        presented: usize,
+    }
+
+    impl FrameSink for SkippingSink {
+        fn present(&mut self, _framebuffer: &[u32]) {
+            if self.skipper.should_present(false) {
+                self.presented += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn frame_skip_still_emulates_every_frame() {
+        let mut emu = Emulator::new(nrom(&[]), Region::Ntsc);
+        let skipper = FrameSkipper::new(FrameSkip::Fixed(2));
+        let mut sink = SkippingSink { skipper, presented: 0 };
+        emu.run_frame(&mut sink);
+        emu.drain_audio();
+        for frame in 1..=9 {
+            let cycles = emu.cpu.cycles;
+            assert!(emu.run_frame(&mut sink));
+            // A whole NTSC frame of CPU time (~29780.5 cycles, give or take the instruction that
+            // crosses vblank) and audio (~734 samples) each call, whether or not it was presented.
+            assert!((29_776..=29_785).contains(&(emu.cpu.cycles - cycles)), "frame {frame}");
+            assert_eq!(emu.cpu.bus.ppu.scanline, 241);
+            assert!((730..=740).contains(&emu.drain_audio().len()), "frame {frame}");
+        }
+        // Every third frame: 2, 5 and 8 of the ten.
+        assert_eq!(sink.presented, 3);
+    }
+}
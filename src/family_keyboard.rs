@@ -0,0 +1,195 @@
+//! Family BASIC keyboard (HVC-007) on the Famicom expansion port.
+//!
+//! Implements the [Family BASIC Keyboard](https://www.nesdev.org/wiki/Family_BASIC_Keyboard) scan
+//! protocol. The 72 keys form a 9-row × 2-column matrix of 4 keys each:
+//!
+//! - **$4016 write**: bit 0 = reset to row 0, bit 1 = column select, bit 2 = enable the matrix.
+//!   Each time the column select goes from 1 to 0, the keyboard advances to the next row.
+//! - **$4017 read**: bits 1–4 = the four keys of the selected row/column, 0 = pressed.
+//!   Reads after the last row, or while the matrix is disabled, return no keys.
+
+use crate::controller::InputDevice;
+
+/// Keys of the Family BASIC keyboard, named after their legends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FamilyKey {
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    Key1,
+    Key2,
+    Key3,
+    Key4,
+    Key5,
+    Key6,
+    Key7,
+    Key8,
+    Key9,
+    Key0,
+    Minus,
+    Caret,
+    Yen,
+    Stop,
+    Escape,
+    Q,
+    W,
+    E,
+    R,
+    T,
+    Y,
+    U,
+    I,
+    O,
+    P,
+    At,
+    LeftBracket,
+    Return,
+    Ctr,
+    A,
+    S,
+    D,
+    F,
+    G,
+    H,
+    J,
+    K,
+    L,
+    Semicolon,
+    Colon,
+    RightBracket,
+    Kana,
+    LeftShift,
+    Z,
+    X,
+    C,
+    V,
+    B,
+    N,
+    M,
+    Comma,
+    Period,
+    Slash,
+    Underscore,
+    RightShift,
+    Grph,
+    Space,
+    ClrHome,
+    Ins,
+    Del,
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+use FamilyKey::*;
+
+/// Key matrix: `MATRIX[row][column][n]` is read on $4017 bit `n + 1`.
+#[rustfmt::skip]
+const MATRIX: [[[FamilyKey; 4]; 2]; 9] = [
+    [[RightBracket, LeftBracket, Return, F8], [Stop, Yen, RightShift, Kana]],
+    [[Semicolon, Colon, At, F7], [Caret, Minus, Slash, Underscore]],
+    [[K, L, O, F6], [Key0, P, Comma, Period]],
+    [[J, U, I, F5], [Key8, Key9, N, M]],
+    [[H, G, Y, F4], [Key6, Key7, V, B]],
+    [[D, R, T, F3], [Key4, Key5, C, F]],
+    [[A, S, W, F2], [Key3, E, Z, X]],
+    [[Ctr, Q, Escape, F1], [Key2, Key1, Grph, LeftShift]],
+    [[Left, Right, Up, ClrHome], [Ins, Del, Space, Down]],
+];
+
+impl FamilyKey {
+    /// (row, column, bit index 0–3) of this key in the scan matrix.
+    pub fn position(self) -> (usize, usize, usize) {
+        for (row, columns) in MATRIX.iter().enumerate() {
+            for (column, keys) in columns.iter().enumerate() {
+                if let Some(bit) = keys.iter().position(|&k| k == self) {
+                    return (row, column, bit);
+                }
+            }
+        }
+        unreachable!("every key is in the matrix")
+    }
+}
+
+/// Family BASIC keyboard state: pressed keys plus the scan position set by $4016 writes.
+#[derive(Clone, Debug, Default)]
+pub struct FamilyKeyboard {
+    /// Pressed keys as bits 0–3 per row and column (1 = pressed; inverted when read).
+    pressed: [[u8; 2]; 9],
+    row: usize,
+    column: usize,
+    enabled: bool,
+}
+
+impl FamilyKeyboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Press or release `key` (set by the frontend from host keys).
+    pub fn set_key(&mut self, key: FamilyKey, down: bool) {
+        let (row, column, bit) = key.position();
+        if down {
+            self.pressed[row][column] |= 1 << bit;
+        } else {
+            self.pressed[row][column] &= !(1 << bit);
+        }
+    }
+
+    /// Release every key.
+    pub fn release_all(&mut self) {
+        self.pressed = [[0; 2]; 9];
+    }
+}
+
+impl InputDevice for FamilyKeyboard {
+    fn write(&mut self, data: u8) {
+        let column = ((data >> 1) & 1) as usize;
+        if data & 1 != 0 {
+            self.row = 0;
+        } else if self.column == 1 && column == 0 {
+            self.row += 1;
+        }
+        self.column = column;
+        self.enabled = data & 4 != 0;
+    }
+
+    fn read(&mut self) -> u8 {
+        match self.pressed.get(self.row) {
+            Some(row) if self.enabled => (!row[self.column] & 0x0F) << 1,
+            // Past the last row: nothing pressed.
+            None if self.enabled => 0x1E,
+            _ => 0,
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn InputDevice> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn column_scan_reads_back_a_pressed_key() {
+        let mut kb = FamilyKeyboard::new();
+        kb.set_key(A, true);
+        kb.write(0x05); // reset to row 0, enable
+        for row in 0..9 {
+            let column0 = kb.read();
+            kb.write(0x06);
+            let column1 = kb.read();
+            kb.write(0x04); // column 1 -> 0: next row
+            let expected = if row == 6 { 0x1C } else { 0x1E };
+            assert_eq!((column0, column1), (expected, 0x1E), "row {row}");
+        }
+    }
+}
@@ -13,11 +13,28 @@
 //! - **cartridge** – [iNES](https://www.nesdev.org/wiki/INES) loading; [Mapper](https://www.nesdev.org/wiki/Mapper) NROM (0), MMC1 (1)
 //! - **controller** – [Controller reading](https://www.nesdev.org/wiki/Controller_reading): $4016 latch, shift-out
 //! - **cpu** – [6502](https://www.nesdev.org/wiki/CPU) / 2A03: full + undocumented opcodes, [NMI](https://www.nesdev.org/wiki/NMI)
+//! - **family_keyboard** – [Family BASIC keyboard](https://www.nesdev.org/wiki/Family_BASIC_Keyboard) matrix on the expansion port
+//! - **emulator** – CPU + bus wired together with console-level settings (region, frame pacing)
+//! - **display** – Integer scaling and 8:7 [pixel aspect](https://www.nesdev.org/wiki/Overscan#Pixel_aspect_ratio) for presenting frames
+//! - **paddle** – [Arkanoid controller](https://www.nesdev.org/wiki/Arkanoid_controller) on port 2
+//! - **power_pad** – [Power Pad](https://www.nesdev.org/wiki/Power_Pad) mat on port 2
 //! - **ppu** – [PPU](https://www.nesdev.org/wiki/PPU), [PPU registers](https://www.nesdev.org/wiki/PPU_registers), OAM, nametables, 256×240
+//! - **region** – NTSC/PAL [timing](https://www.nesdev.org/wiki/Cycle_reference_chart) (CPU clock, frame rate)
+//! - **screenshot** – PNG encoding for screenshots
+
+// Each chip lives in `<chip>/<chip>.rs` (e.g. `cpu::cpu::CPU`).
+#![allow(clippy::module_inception)]
 
 pub mod apu;
 pub mod bus;
 pub mod cartridge;
 pub mod controller;
 pub mod cpu;
-pub mod ppu;
\ No newline at end of file
+pub mod display;
+pub mod emulator;
+pub mod family_keyboard;
+pub mod paddle;
+pub mod power_pad;
+pub mod ppu;
+pub mod region;
+pub mod screenshot;
\ No newline at end of file
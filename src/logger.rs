@@ -0,0 +1,50 @@
+//! Level-filtered stderr logger for the `elaris` binary.
+//!
+//! The library only emits records through the `log` facade; this installs the sink. Warnings and
+//! errors are shown by default; `--verbose` lowers the filter to debug, and `ELARIS_LOG=trace`
+//! (or any level name) overrides it, e.g. to see the nestest trace lines.
+
+use ansi_term::Colour::{Blue, Cyan, Purple, Red, Yellow};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let level = match record.level() {
+            Level::Error => Red.bold().paint("error"),
+            Level::Warn => Yellow.bold().paint("warning"),
+            Level::Info => Cyan.paint("info"),
+            Level::Debug => Blue.paint("debug"),
+            Level::Trace => Purple.paint("trace"),
+        };
+        eprintln!("{level}: {}", record.args());
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: StderrLogger = StderrLogger;
+
+/// Install the logger. Call once, before anything logs.
+pub fn init(verbose: bool) {
+    let default = if verbose {
+        LevelFilter::Debug
+    } else {
+        LevelFilter::Warn
+    };
+    let filter = std::env::var("ELARIS_LOG")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default);
+    // Only fails if a logger is already set, in which case that one stays in charge.
+    let _ = log::set_logger(&LOGGER);
+    log::set_max_level(filter);
+}
@@ -1,33 +1,47 @@
 //! NES emulator entry point.
 //!
 //! Loads a cartridge and runs the CPU with a display window and audio output.
-//! Usage: `elaris [path/to/game.nes]`
+//! Usage: `elaris [OPTIONS] [path/to/game.nes]` (see `elaris --help` / `cli::USAGE`).
 //!
 //! ## NESdev references
 //!
 //! - [Cycle reference chart](https://www.nesdev.org/wiki/Cycle_reference_chart): NTSC frame rate
-//!   (~60.0988 Hz), CPU/PPU cycle relationship (3 PPU cycles per CPU cycle).
+//!   (~60.0988 Hz), PAL (~50.007 Hz), CPU/PPU cycle relationship (3 PPU cycles per CPU cycle).
 //! - [NMI](https://www.nesdev.org/wiki/NMI): VBlank NMI triggers at scanline 241; games sync to
 //!   this for one logic frame per display frame.
 //! - [APU](https://www.nesdev.org/wiki/APU): Audio sampled at 44.1 kHz; DMC can stall CPU for
 //!   sample fetches (4 cycles per byte from PRG).
 
+mod cli;
+mod logger;
+
 use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
 use std::path::Path;
+use std::process;
 use std::time::{Duration, Instant};
 
-use elaris::{bus::Bus, bus::NesBus, cartridge::cartridge::Cartridge, cpu::cpu::CPU};
-use minifb::{Key, Window, WindowOptions};
+use cli::{Options, Pacing, Port2Choice};
+use elaris::cartridge::cartridge::Cartridge;
+use elaris::display::{self, ColorAdjust, CrtFilter, DisplayConfig, FrameSkipper, Overscan};
+use elaris::emulator::{Emulator, FrameSink};
+use elaris::family_keyboard::{FamilyKey, FamilyKeyboard};
+use elaris::paddle::Paddle;
+use elaris::power_pad::PowerPad;
+use elaris::ppu::palette::load_pal_file;
+use elaris::screenshot;
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window, WindowOptions};
 use rodio::OutputStream;
 
-/// NES NTSC frame rate is ~60.0988 Hz. We target 16.67 ms per frame for ~60 fps display.
-/// See: NESdev wiki "Cycle reference chart" (frame = 29780.5 CPU cycles at 1.789773 MHz).
-const FRAME_DURATION: Duration = Duration::from_nanos(16_666_667);
-
 /// Audio output sample rate (Hz). Matches APU sample generation rate.
 /// APU mixer runs at CPU clock; we resample to 44.1 kHz for output (see APU_Mixer).
 const SAMPLE_RATE: u32 = 44_100;
 
+/// With `--pacing audio`, emulate another frame once fewer than this many frames of audio are
+/// queued in the sink (~50 ms at 60 Hz): enough slack to ride out a slow frame.
+const AUDIO_QUEUE_FRAMES: usize = 3;
+
 /// Build controller port 1 ($4016) button state from keyboard.
 /// Bit order matches [Standard controller](https://www.nesdev.org/wiki/Standard_controller):
 /// 0=A, 1=B, 2=Select, 3=Start, 4=Up, 5=Down, 6=Left, 7=Right.
@@ -61,121 +75,522 @@ fn controller_state_from_keys(window: &Window) -> u8 {
     state
 }
 
-fn main() {
-    // Load ROM from path or default to nestest for CPU verification (nestest: CPU test ROM).
-    let path = env::args()
-        .nth(1)
-        .unwrap_or_else(|| "test/nestest.nes".to_string());
-
-    let cart = Cartridge::load(&path);
-    let bus = NesBus::new(cart);
-    // CPU initial state: A,X,Y=0, SP=$FD, P=$24 (I=1, U=1), PC set by reset vector.
-    // See: NESdev "CPU power up state" / "Reset vector" ($FFFC–$FFFD).
-    let mut cpu = CPU {
-        a: 0,
-        x: 0,
-        y: 0,
-        sp: 0xFD,
-        pc: 0,
-        status: 0x24,
-        cycles: 0,
-        bus,
-        halted: false,
-    };
+/// Host key → Family BASIC key. Keys without a close host equivalent use nearby ones: Kana =
+/// Right Alt, Grph = Left Alt, Ctr = Ctrl, Stop = End, Yen = Backslash, Underscore = Apostrophe,
+/// Caret = Equal, At = Backquote.
+///
+/// While the keyboard is plugged in it owns these host keys, so `run_windowed` turns off
+/// everything else they'd trigger: Escape no longer exits, the F2–F5 hotkeys and the M microphone
+/// are ignored, and controller port 1 reads no buttons (Enter, Shift and the arrows are keys
+/// here). F12 (screenshot) and closing the window still work.
+const FAMILY_KEYBOARD_KEYS: &[(Key, FamilyKey)] = &[
+    (Key::F1, FamilyKey::F1),
+    (Key::F2, FamilyKey::F2),
+    (Key::F3, FamilyKey::F3),
+    (Key::F4, FamilyKey::F4),
+    (Key::F5, FamilyKey::F5),
+    (Key::F6, FamilyKey::F6),
+    (Key::F7, FamilyKey::F7),
+    (Key::F8, FamilyKey::F8),
+    (Key::Key1, FamilyKey::Key1),
+    (Key::Key2, FamilyKey::Key2),
+    (Key::Key3, FamilyKey::Key3),
+    (Key::Key4, FamilyKey::Key4),
+    (Key::Key5, FamilyKey::Key5),
+    (Key::Key6, FamilyKey::Key6),
+    (Key::Key7, FamilyKey::Key7),
+    (Key::Key8, FamilyKey::Key8),
+    (Key::Key9, FamilyKey::Key9),
+    (Key::Key0, FamilyKey::Key0),
+    (Key::Minus, FamilyKey::Minus),
+    (Key::Equal, FamilyKey::Caret),
+    (Key::Backslash, FamilyKey::Yen),
+    (Key::End, FamilyKey::Stop),
+    (Key::Escape, FamilyKey::Escape),
+    (Key::Q, FamilyKey::Q),
+    (Key::W, FamilyKey::W),
+    (Key::E, FamilyKey::E),
+    (Key::R, FamilyKey::R),
+    (Key::T, FamilyKey::T),
+    (Key::Y, FamilyKey::Y),
+    (Key::U, FamilyKey::U),
+    (Key::I, FamilyKey::I),
+    (Key::O, FamilyKey::O),
+    (Key::P, FamilyKey::P),
+    (Key::Backquote, FamilyKey::At),
+    (Key::LeftBracket, FamilyKey::LeftBracket),
+    (Key::Enter, FamilyKey::Return),
+    (Key::LeftCtrl, FamilyKey::Ctr),
+    (Key::RightCtrl, FamilyKey::Ctr),
+    (Key::A, FamilyKey::A),
+    (Key::S, FamilyKey::S),
+    (Key::D, FamilyKey::D),
+    (Key::F, FamilyKey::F),
+    (Key::G, FamilyKey::G),
+    (Key::H, FamilyKey::H),
+    (Key::J, FamilyKey::J),
+    (Key::K, FamilyKey::K),
+    (Key::L, FamilyKey::L),
+    (Key::Semicolon, FamilyKey::Semicolon),
+    (Key::Apostrophe, FamilyKey::Underscore),
+    (Key::RightBracket, FamilyKey::RightBracket),
+    (Key::RightAlt, FamilyKey::Kana),
+    (Key::LeftShift, FamilyKey::LeftShift),
+    (Key::Z, FamilyKey::Z),
+    (Key::X, FamilyKey::X),
+    (Key::C, FamilyKey::C),
+    (Key::V, FamilyKey::V),
+    (Key::B, FamilyKey::B),
+    (Key::N, FamilyKey::N),
+    (Key::M, FamilyKey::M),
+    (Key::Comma, FamilyKey::Comma),
+    (Key::Period, FamilyKey::Period),
+    (Key::Slash, FamilyKey::Slash),
+    (Key::RightShift, FamilyKey::RightShift),
+    (Key::LeftAlt, FamilyKey::Grph),
+    (Key::Space, FamilyKey::Space),
+    (Key::Home, FamilyKey::ClrHome),
+    (Key::Insert, FamilyKey::Ins),
+    (Key::Delete, FamilyKey::Del),
+    (Key::Backspace, FamilyKey::Del),
+    (Key::Up, FamilyKey::Up),
+    (Key::Down, FamilyKey::Down),
+    (Key::Left, FamilyKey::Left),
+    (Key::Right, FamilyKey::Right),
+];
 
-    // Reset: PC from $FFFC/$FFFD (cartridge supplies vector). Nestest.nes expects entry at $C000.
-    if path.contains("nestest") {
-        cpu.pc = 0xC000;
-        cpu.cycles = 7;
-    } else {
-        cpu.reset();
+/// Update the Family BASIC keyboard matrix from the host keyboard.
+fn family_keyboard_from_keys(window: &Window, keyboard: &mut FamilyKeyboard) {
+    keyboard.release_all();
+    for &(key, family_key) in FAMILY_KEYBOARD_KEYS {
+        if window.is_key_down(key) {
+            keyboard.set_key(family_key, true);
+        }
     }
+}
 
-    // NES PPU output is 256×240 pixels (8×8 tiles: 32×30 visible). See PPU_registers / PPU_rendering.
+/// Host keys for Power Pad buttons 1–12, laid out like the mat: 1–4 / Q–R / A–F.
+const POWER_PAD_KEYS: [Key; 12] = [
+    Key::Key1,
+    Key::Key2,
+    Key::Key3,
+    Key::Key4,
+    Key::Q,
+    Key::W,
+    Key::E,
+    Key::R,
+    Key::A,
+    Key::S,
+    Key::D,
+    Key::F,
+];
+
+/// Keyboard → Power Pad buttons.
+fn power_pad_from_keys(window: &Window, pad: &mut PowerPad) {
+    for (i, &key) in POWER_PAD_KEYS.iter().enumerate() {
+        pad.set_button(i as u8 + 1, window.is_key_down(key));
+    }
+}
+
+/// Mouse → Arkanoid paddle: X across the window turns the knob, left button fires.
+fn paddle_from_mouse(window: &Window, paddle: &mut Paddle) {
+    let (width, _) = window.get_size();
+    if let Some((x, _)) = window.get_mouse_pos(MouseMode::Clamp) {
+        paddle.set_from_fraction(x / width.max(1) as f32);
+    }
+    paddle.fire = window.get_mouse_down(MouseButton::Left);
+}
+
+/// Create the window at the exact output size for `config`. minifb scaling is left at 1× so the
+/// integer/aspect scaling in `display::scale_frame` is what the user sees (no blurry fit-to-screen).
+/// The window can be resized; the frame is then re-scaled to the largest integer scale that fits
+/// and centred. With `fullscreen`, minifb stretches the scaled output to fill the screen instead.
+fn create_window(title: &str, config: &DisplayConfig, fullscreen: bool, fps: usize) -> Window {
+    let (width, height) = config.output_size();
     let mut window = Window::new(
-        format!(
-            "{} - Elaris",
-            Path::new(&path)
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("ROM")
-        )
-        .as_str(),
-        256,
-        240,
+        title,
+        width,
+        height,
         WindowOptions {
             borderless: true,
-            resize: true,
-            scale: minifb::Scale::FitScreen,
-            scale_mode: minifb::ScaleMode::AspectRatioStretch,
+            resize: !fullscreen,
+            scale: if fullscreen {
+                minifb::Scale::FitScreen
+            } else {
+                minifb::Scale::X1
+            },
+            scale_mode: if fullscreen {
+                minifb::ScaleMode::AspectRatioStretch
+            } else {
+                minifb::ScaleMode::Center
+            },
             topmost: true,
-            title: true,
+            title: !fullscreen,
             transparency: false,
             none: false,
         },
     )
     .expect("Failed to create window");
+    window.set_target_fps(fps);
+    window
+}
+
+/// Presents frames in a minifb window: color adjustment, scaling and the CRT filter, with frame
+/// skip deciding which frames are drawn (skipped frames still pump window events).
+struct WindowSink {
+    window: Window,
+    title: String,
+    fullscreen: bool,
+    fps: usize,
+    display_config: DisplayConfig,
+    color: ColorAdjust,
+    crt: CrtFilter,
+    skipper: FrameSkipper,
+    /// Set by the main loop when the last frame overran its time budget.
+    previous_frame_late: bool,
+    scaled_frame: Vec<u32>,
+    adjusted_frame: Vec<u32>,
+}
+
+impl WindowSink {
+    /// Switch to `config`, recreating the window at the new output size if it changed.
+    fn set_display_config(&mut self, config: DisplayConfig) {
+        if config != self.display_config {
+            self.display_config = config;
+            self.window = create_window(&self.title, &config, self.fullscreen, self.fps);
+        }
+    }
+
+    /// Config to present with: `display_config`, or after the user resized the window, the same
+    /// aspect and overscan at the largest integer scale that fits it.
+    fn presented_config(&self) -> DisplayConfig {
+        if self.fullscreen {
+            return self.display_config;
+        }
+        let (width, height) = self.window.get_size();
+        if (width, height) == self.display_config.output_size() {
+            self.display_config
+        } else {
+            self.display_config.fit_to(width, height)
+        }
+    }
+}
+
+impl FrameSink for WindowSink {
+    fn present(&mut self, framebuffer: &[u32]) {
+        if !self.skipper.should_present(self.previous_frame_late) {
+            self.window.update();
+            return;
+        }
+        let config = self.presented_config();
+        if self.color.is_identity() {
+            display::scale_frame(framebuffer, &mut self.scaled_frame, &config);
+        } else {
+            let adjusted = &mut self.adjusted_frame;
+            adjusted.copy_from_slice(framebuffer);
+            display::adjust_frame(adjusted, &self.color);
+            display::scale_frame(adjusted, &mut self.scaled_frame, &config);
+        }
+        let (width, height) = config.output_size();
+        display::apply_crt_filter(&mut self.scaled_frame, width, &self.crt);
+        self.window
+            .update_with_buffer(&self.scaled_frame, width, height)
+            .expect("Failed to update window");
+    }
+}
+
+/// Write mono samples (-1..1) as a 16-bit PCM WAV file.
+fn write_wav(path: &Path, sample_rate: u32, samples: &[f32]) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    let data_len = (samples.len() * 2) as u32;
+    out.write_all(b"RIFF")?;
+    out.write_all(&(36 + data_len).to_le_bytes())?;
+    out.write_all(b"WAVEfmt ")?;
+    out.write_all(&16u32.to_le_bytes())?; // fmt chunk size
+    out.write_all(&1u16.to_le_bytes())?; // PCM
+    out.write_all(&1u16.to_le_bytes())?; // mono
+    out.write_all(&sample_rate.to_le_bytes())?;
+    out.write_all(&(sample_rate * 2).to_le_bytes())?; // byte rate
+    out.write_all(&2u16.to_le_bytes())?; // block align
+    out.write_all(&16u16.to_le_bytes())?; // bits per sample
+    out.write_all(b"data")?;
+    out.write_all(&data_len.to_le_bytes())?;
+    for s in samples {
+        out.write_all(&((s * i16::MAX as f32) as i16).to_le_bytes())?;
+    }
+    out.flush()
+}
+
+/// Save `framebuffer` as `<save dir>/<ROM name>-NNN.png` (first unused number), cropped to
+/// `overscan` unless `--screenshot-full`, so it matches what the window shows.
+fn save_screenshot(framebuffer: &[u32], overscan: Overscan, opts: &Options) {
+    let overscan = if opts.screenshot_full {
+        Overscan::default()
+    } else {
+        overscan
+    };
+    let mut pixels = Vec::new();
+    overscan.crop(framebuffer, &mut pixels);
+    let (width, height) = overscan.visible_size();
+
+    let stem = Path::new(&opts.rom)
+        .file_stem()
+        .and_then(|n| n.to_str())
+        .unwrap_or("screenshot");
+    let Some(path) = (1..1000)
+        .map(|n| opts.save_dir.join(format!("{stem}-{n:03}.png")))
+        .find(|path| !path.exists())
+    else {
+        log::error!("no free screenshot name in {}", opts.save_dir.display());
+        return;
+    };
+    match screenshot::write_png(&path, &pixels, width, height) {
+        Ok(()) => log::info!("saved screenshot {}", path.display()),
+        Err(err) => log::error!("failed to write {}: {err}", path.display()),
+    }
+}
+
+/// Headless mode: no window or audio device; runs as fast as possible and reports throughput.
+fn run_headless(emu: &mut Emulator, opts: &Options, recording: &mut Option<Vec<f32>>) {
+    let start = Instant::now();
+    let mut frames = 0u64;
+    while opts.frames.is_none_or(|limit| frames < limit) {
+        let Some(frame) = emu.next_frame() else {
+            break;
+        };
+        if let Some(rec) = recording {
+            rec.extend_from_slice(frame.audio);
+        }
+        frames += 1;
+    }
+    let secs = start.elapsed().as_secs_f64();
+    println!(
+        "{} frames in {:.2} s ({:.1} fps)",
+        frames,
+        secs,
+        frames as f64 / secs.max(f64::EPSILON)
+    );
+    // Test ROMs (blargg) leave a status byte and message in PRG RAM.
+    if let Some(result) = emu.test_output() {
+        println!("test status ${:02X}: {}", result.status, result.message.trim_end());
+    }
+}
+
+/// Windowed mode: present each frame, play audio, and pace to the region's frame rate.
+fn run_windowed(emu: &mut Emulator, opts: &Options, recording: &mut Option<Vec<f32>>) {
+    // ~16.64 ms (60 Hz) for NTSC, ~20 ms (50 Hz) for PAL. See Cycle_reference_chart.
+    let frame_duration = emu.target_frame_duration();
+    let mut pacing = opts.pacing;
+    if pacing == Pacing::Audio && opts.mute {
+        log::warn!("--pacing audio needs audio output; falling back to sleep pacing");
+        pacing = Pacing::Sleep;
+    }
+    // The window's own refresh limiter would fight audio-driven pacing.
+    let fps = match pacing {
+        Pacing::Audio => 0,
+        Pacing::Sleep | Pacing::Window => emu.region().frame_rate().round() as usize,
+    };
 
-    window.set_target_fps(60);
+    // NES PPU output is 256×240 pixels (8×8 tiles: 32×30 visible). See PPU_registers / PPU_rendering.
+    // The window is sized to the scaled output; F2 cycles 1×–6×, F3 toggles square/8:7 pixels.
+    // F4 logs the mapper's current PRG/CHR banks; F5 toggles the CRT filter; F12 saves a
+    // screenshot.
+    let title = format!(
+        "{} - Elaris",
+        Path::new(&opts.rom)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("ROM")
+    );
+    let mut sink = WindowSink {
+        window: create_window(&title, &opts.display, opts.fullscreen, fps),
+        title,
+        fullscreen: opts.fullscreen,
+        fps,
+        display_config: opts.display,
+        color: opts.color,
+        crt: opts.crt,
+        skipper: FrameSkipper::new(opts.frame_skip),
+        previous_frame_late: false,
+        scaled_frame: Vec::new(),
+        adjusted_frame: vec![0u32; display::NES_WIDTH * display::NES_HEIGHT],
+    };
 
-    // Audio: default device, sink for queueing APU samples each frame
-    let (_stream, stream_handle) = OutputStream::try_default().expect("No default audio device");
-    let sink = rodio::Sink::try_new(&stream_handle).expect("Failed to create audio sink");
-    let mut audio_buf = vec![0.0f32; 1024];
+    // Audio: default device, sink for queueing APU samples each frame. `--mute` never opens the
+    // device, so it also works on machines without one. The stream must outlive the sink.
+    let audio: Option<(OutputStream, rodio::Sink)> = (!opts.mute).then(|| {
+        let (stream, stream_handle) =
+            OutputStream::try_default().expect("No default audio device");
+        let sink = rodio::Sink::try_new(&stream_handle).expect("Failed to create audio sink");
+        sink.set_volume(opts.volume);
+        (stream, sink)
+    });
+    let audio_sink = audio.as_ref().map(|(_, sink)| sink);
+    let mut frames = 0u64;
+    let mut underruns = 0u64;
+    // The Family BASIC keyboard reserves Escape, F1–F8, M and port 1's keys (see
+    // `FAMILY_KEYBOARD_KEYS`).
+    let keyboard_attached = opts.port2 == Port2Choice::Keyboard;
 
-    // Main loop: run one frame of emulation, then present and pace to 60 fps
-    while window.is_open() && !window.is_key_down(Key::Escape) {
+    // Main loop: run one frame of emulation, then present and pace to the region's frame rate
+    while sink.window.is_open() && (keyboard_attached || !sink.window.is_key_down(Key::Escape)) {
+        if opts.frames.is_some_and(|limit| frames >= limit) {
+            break;
+        }
         let frame_start = Instant::now();
+        let window = &sink.window;
+        let hotkey = |key| !keyboard_attached && window.is_key_pressed(key, KeyRepeat::No);
+
+        // Scale hotkeys: recreate the window at the new output size.
+        let new_config = if hotkey(Key::F2) {
+            sink.display_config.next_scale()
+        } else if hotkey(Key::F3) {
+            sink.display_config.toggle_aspect()
+        } else {
+            sink.display_config
+        };
+        if hotkey(Key::F5) {
+            sink.crt = sink.crt.toggle();
+        }
+        let cpu = &mut emu.cpu;
+        if hotkey(Key::F4) {
+            log::info!("{}", cpu.bus.cart.bank_summary());
+        }
+
+        if !keyboard_attached {
+            // Keyboard → controller port 1. Game latches by writing 1 then 0 to $4016
+            // (Controller_reading).
+            cpu.bus.controller.state = controller_state_from_keys(window);
+            // M = blow into the Famicom controller 2 microphone ($4016 bit 2).
+            cpu.bus.microphone = window.is_key_down(Key::M);
+        }
+        if let Some(keyboard) = cpu.bus.port2_mut::<FamilyKeyboard>() {
+            family_keyboard_from_keys(window, keyboard);
+        }
+        if let Some(paddle) = cpu.bus.port2_mut::<Paddle>() {
+            paddle_from_mouse(window, paddle);
+        }
+        if let Some(pad) = cpu.bus.port2_mut::<PowerPad>() {
+            power_pad_from_keys(window, pad);
+        }
+        let take_screenshot = window.is_key_pressed(Key::F12, KeyRepeat::No);
+        sink.set_display_config(new_config);
+
+        // Framebuffer is filled as each visible scanline (0–239) completes; at vblank (scanline
+        // 241) the sink presents it (unless frame skip drops it; the window still pumps input).
+        if !emu.run_frame(&mut sink) {
+            break;
+        }
+        frames += 1;
+        if take_screenshot {
+            let overscan = sink.display_config.overscan;
+            save_screenshot(&emu.cpu.bus.ppu.framebuffer, overscan, opts);
+        }
+
+        let samples = emu.drain_audio();
+        if let Some(rec) = recording {
+            rec.extend_from_slice(samples);
+        }
+        if let Some(audio_sink) = audio_sink.as_ref().filter(|_| !samples.is_empty()) {
+            // Playback ran dry before this frame's samples arrived (skip the first frame).
+            if frames > 1 && audio_sink.empty() {
+                underruns += 1;
+            }
+            // rodio takes ownership of each queued chunk, so this copy is the one allocation left.
+            audio_sink.append(rodio::buffer::SamplesBuffer::new(1, SAMPLE_RATE, samples.to_vec()));
+        }
 
-        // Keyboard → controller port 1. Game latches by writing 1 then 0 to $4016 (Controller_reading).
-        cpu.bus.controller.state = controller_state_from_keys(&window);
-
-        // Run one frame: CPU runs until PPU signals vblank (scanline 241, cycle 1). Each CPU
-        // instruction calls bus.tick(cycles), advancing PPU by 3× cycles and APU by cycles.
-        while !cpu.bus.frame_ready() {
-            // DMC sample fetch: when buffer empty, APU requests a byte. CPU is stalled 4 cycles
-            // while the DMC reads from PRG ($8000–$FFFF). See APU_DMC "Memory reader".
-            while let Some(addr) = cpu.bus.apu.dmc_wants_fetch() {
-                cpu.cycles += 4;
-                for _ in 0..4 {
-                    cpu.bus.tick(1);
+        // Pace to the frame rate so we don't burn CPU (emulation is far faster than real NES)
+        let elapsed = frame_start.elapsed();
+        sink.previous_frame_late = elapsed > frame_duration;
+        match (pacing, &audio_sink) {
+            (Pacing::Audio, Some(audio_sink)) => {
+                // If playback stalls, stop waiting after a queue's worth of frames so the window
+                // keeps pumping events (and Escape still works).
+                let deadline = frame_start + frame_duration * AUDIO_QUEUE_FRAMES as u32;
+                while audio_sink.len() >= AUDIO_QUEUE_FRAMES && Instant::now() < deadline {
+                    std::thread::sleep(Duration::from_millis(1));
                 }
-                let byte = cpu.bus.read(addr);
-                cpu.bus.apu.dmc_feed_byte(byte);
             }
-            cpu.step();
-            if cpu.halted {
-                break;
+            // `update_with_buffer` already blocked in minifb's frame limiter.
+            (Pacing::Window, _) => {}
+            _ => {
+                if elapsed < frame_duration {
+                    std::thread::sleep(frame_duration - elapsed);
+                }
             }
         }
-        if cpu.halted {
-            break;
+    }
+    log::info!("{frames} frames, {underruns} audio underruns");
+}
+
+fn main() {
+    let opts = match cli::parse(env::args().skip(1)) {
+        Ok(cli::Command::Run(opts)) => *opts,
+        Ok(cli::Command::Help) => {
+            println!("{}", cli::USAGE);
+            return;
+        }
+        Err(err) => {
+            eprintln!("error: {err}\n\n{}", cli::USAGE);
+            process::exit(2);
         }
+    };
+    logger::init(opts.verbose);
 
-        if cpu.bus.frame_ready() {
-            // Framebuffer was filled as each visible scanline (0–239) completed; vblank flag set
-            // at scanline 241. We present the buffer and clear frame_ready for next frame.
-            window
-                .update_with_buffer(&cpu.bus.ppu.framebuffer, 256, 240)
-                .expect("Failed to update window");
-            cpu.bus.clear_frame_ready();
-
-            // APU samples are 0..1 (mixer output); convert to -1..1 for rodio playback.
-            let n = cpu.bus.apu.drain_samples(&mut audio_buf);
-            if n > 0 {
-                let samples: Vec<f32> = audio_buf[..n]
-                    .iter()
-                    .map(|s| (s * 2.0 - 1.0).clamp(-1.0, 1.0))
-                    .collect();
-                let source = rodio::buffer::SamplesBuffer::new(1, SAMPLE_RATE, samples);
-                sink.append(source);
+    // Load ROM from path or default to nestest for CPU verification (nestest: CPU test ROM).
+    // Power-on takes the reset vector from $FFFC/$FFFD; nestest.nes expects entry at $C000.
+    let cart = match Cartridge::load(&opts.rom) {
+        Ok(cart) => cart,
+        Err(err) => {
+            log::error!("failed to load {}: {err}", opts.rom);
+            process::exit(1);
+        }
+    };
+    log::info!(
+        "mapper {} ({}), {} KiB PRG, {} KiB CHR{}",
+        cart.mapper_id(),
+        cart.mapper_name(),
+        cart.prg_rom_size() / 1024,
+        cart.chr_rom_size() / 1024,
+        if cart.has_battery() { ", battery" } else { "" }
+    );
+
+    let region = cart.resolve_region(opts.region);
+    log::info!("region: {region:?}");
+    let mut emu = Emulator::new(cart, region);
+    emu.cpu.bus.apu.set_sample_rate(SAMPLE_RATE);
+    if let Some(path) = &opts.palette {
+        match load_pal_file(path) {
+            Ok(palette) => emu.cpu.bus.ppu.set_palette(&palette),
+            Err(err) => {
+                log::error!("failed to load palette {path}: {err}");
+                process::exit(1);
             }
         }
+    }
+    match opts.port2 {
+        Port2Choice::None => {}
+        Port2Choice::Keyboard => emu.cpu.bus.port2 = Some(Box::new(FamilyKeyboard::new())),
+        Port2Choice::Paddle => emu.cpu.bus.port2 = Some(Box::new(Paddle::new())),
+        Port2Choice::PowerPad => emu.cpu.bus.port2 = Some(Box::new(PowerPad::new())),
+    }
+    if opts.rom.contains("nestest") {
+        emu.cpu.pc = 0xC000;
+    }
 
-        // Pace to ~60 fps so we don't burn CPU (emulation is far faster than real NES)
-        let elapsed = frame_start.elapsed();
-        if elapsed < FRAME_DURATION {
-            std::thread::sleep(FRAME_DURATION - elapsed);
+    let mut recording = opts.record_audio.as_ref().map(|_| Vec::new());
+    if opts.headless {
+        run_headless(&mut emu, &opts, &mut recording);
+    } else {
+        run_windowed(&mut emu, &opts, &mut recording);
+    }
+
+    if let (Some(file), Some(samples)) = (&opts.record_audio, &recording) {
+        let path = opts.save_dir.join(file);
+        if let Err(err) = write_wav(&path, SAMPLE_RATE, samples) {
+            log::error!("failed to write {}: {err}", path.display());
+            process::exit(1);
         }
     }
 }
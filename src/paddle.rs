@@ -0,0 +1,95 @@
+//! Arkanoid "Vaus" paddle controller on port 2.
+//!
+//! Implements the NES [Arkanoid controller](https://www.nesdev.org/wiki/Arkanoid_controller):
+//!
+//! - **$4016 write**: bit 0 = strobe. While high, the potentiometer value is latched into the
+//!   shift register.
+//! - **$4017 read**: D3 = next potentiometer bit, MSB first and inverted (0 = bit set); D4 = fire
+//!   button (1 = pressed). After 8 reads the register is empty and D3 stays 0.
+//!
+//! Arkanoid expects values of roughly 98 (left) to 242 (right); [`Paddle::set_from_fraction`]
+//! maps a pointer position onto that range.
+
+use crate::controller::InputDevice;
+
+/// Leftmost potentiometer value of a typical Vaus controller.
+pub const PADDLE_MIN: u8 = 98;
+/// Rightmost potentiometer value of a typical Vaus controller.
+pub const PADDLE_MAX: u8 = 242;
+
+/// Paddle state: knob position, fire button and the serial shift register.
+#[derive(Clone, Debug)]
+pub struct Paddle {
+    /// Potentiometer value (higher = further right).
+    pub position: u8,
+    pub fire: bool,
+    /// Inverted position, shifted out MSB first on D3.
+    shift: u8,
+    strobe: bool,
+}
+
+impl Default for Paddle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Paddle {
+    /// Paddle centred, fire released.
+    pub fn new() -> Self {
+        Paddle {
+            position: PADDLE_MIN + (PADDLE_MAX - PADDLE_MIN) / 2,
+            fire: false,
+            shift: 0,
+            strobe: false,
+        }
+    }
+
+    /// Set the knob from a 0.0 (left) – 1.0 (right) fraction, e.g. mouse X over window width.
+    pub fn set_from_fraction(&mut self, fraction: f32) {
+        let span = (PADDLE_MAX - PADDLE_MIN) as f32;
+        self.position = PADDLE_MIN + (fraction.clamp(0.0, 1.0) * span).round() as u8;
+    }
+}
+
+impl InputDevice for Paddle {
+    fn write(&mut self, data: u8) {
+        self.strobe = data & 1 != 0;
+        if self.strobe {
+            self.shift = !self.position;
+        }
+    }
+
+    fn read(&mut self) -> u8 {
+        if self.strobe {
+            self.shift = !self.position;
+        }
+        let data = (self.shift >> 7) & 1;
+        self.shift <<= 1;
+        let fire = if self.fire { 0x10 } else { 0 };
+        (data << 3) | fire
+    }
+
+    fn clone_box(&self) -> Box<dyn InputDevice> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_shifts_out_inverted_msb_first() {
+        let mut paddle = Paddle::new();
+        paddle.position = 0b1010_0110;
+        paddle.fire = true;
+        paddle.write(1);
+        paddle.write(0);
+        let bits: Vec<u8> = (0..8).map(|_| paddle.read()).collect();
+        let d3: Vec<u8> = bits.iter().map(|b| (b >> 3) & 1).collect();
+        assert_eq!(d3, [0, 1, 0, 1, 1, 0, 0, 1]);
+        assert!(bits.iter().all(|b| b & 0x10 != 0));
+        assert_eq!(paddle.read() & 0x08, 0);
+    }
+}
@@ -0,0 +1,116 @@
+//! Power Pad (Family Trainer mat) on port 2.
+//!
+//! Implements the [Power Pad](https://www.nesdev.org/wiki/Power_Pad): 12 buttons in three rows of
+//! four, numbered 1–12 left to right, top to bottom (side B). Like the standard controller, a
+//! strobe on $4016 bit 0 latches the buttons into shift registers, and each $4017 read shifts out
+//! one bit on two data lines at once:
+//!
+//! - **D3**: buttons 2, 1, 5, 9, 6, 10, 11, 7
+//! - **D4**: buttons 4, 3, 12, 8, then 1s
+//!
+//! 1 = pressed. After the 8th read both lines read 1.
+
+use crate::controller::InputDevice;
+
+/// Button numbers shifted out on D3, first read first.
+const D3_ORDER: [u8; 8] = [2, 1, 5, 9, 6, 10, 11, 7];
+/// Button numbers shifted out on D4; the remaining 4 reads return 1.
+const D4_ORDER: [u8; 4] = [4, 3, 12, 8];
+
+/// Power Pad state: pressed buttons and the two shift registers.
+#[derive(Clone, Debug, Default)]
+pub struct PowerPad {
+    /// Bit `n - 1` set = button `n` (1–12) pressed.
+    pub buttons: u16,
+    shift_d3: u8,
+    shift_d4: u8,
+    strobe: bool,
+}
+
+impl PowerPad {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Press or release button `n` (1–12). Out-of-range numbers are ignored.
+    pub fn set_button(&mut self, n: u8, down: bool) {
+        if !(1..=12).contains(&n) {
+            return;
+        }
+        if down {
+            self.buttons |= 1 << (n - 1);
+        } else {
+            self.buttons &= !(1 << (n - 1));
+        }
+    }
+
+    fn pressed(&self, n: u8) -> bool {
+        self.buttons & (1 << (n - 1)) != 0
+    }
+
+    /// Load both shift registers from the current buttons (LSB is read first). D4's unused
+    /// low bits read as 1, and 1s shift in behind both registers.
+    fn latch(&mut self) {
+        self.shift_d3 = D3_ORDER
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (i, &n)| acc | ((self.pressed(n) as u8) << i));
+        self.shift_d4 = D4_ORDER
+            .iter()
+            .enumerate()
+            .fold(0xF0, |acc, (i, &n)| acc | ((self.pressed(n) as u8) << i));
+    }
+}
+
+impl InputDevice for PowerPad {
+    fn write(&mut self, data: u8) {
+        self.strobe = data & 1 != 0;
+        if self.strobe {
+            self.latch();
+        }
+    }
+
+    fn read(&mut self) -> u8 {
+        if self.strobe {
+            self.latch();
+        }
+        let d3 = self.shift_d3 & 1;
+        let d4 = self.shift_d4 & 1;
+        self.shift_d3 = (self.shift_d3 >> 1) | 0x80;
+        self.shift_d4 = (self.shift_d4 >> 1) | 0x80;
+        (d3 << 3) | (d4 << 4)
+    }
+
+    fn clone_box(&self) -> Box<dyn InputDevice> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// (D3, D4) bits of 8 reads after a strobe.
+    fn read_all(pad: &mut PowerPad) -> Vec<(u8, u8)> {
+        pad.write(1);
+        pad.write(0);
+        (0..8).map(|_| pad.read()).map(|b| ((b >> 3) & 1, (b >> 4) & 1)).collect()
+    }
+
+    #[test]
+    fn buttons_shift_out_in_order() {
+        let expected_d3 = [2, 1, 5, 9, 6, 10, 11, 7];
+        let expected_d4 = [4, 3, 12, 8];
+        for n in 1..=12 {
+            let mut pad = PowerPad::new();
+            pad.set_button(n, true);
+            let reads = read_all(&mut pad);
+            for (i, &(d3, d4)) in reads.iter().enumerate() {
+                assert_eq!(d3 == 1, expected_d3[i] == n, "button {n}, D3 read {i}");
+                let d4_pressed = expected_d4.get(i).is_none_or(|&b| b == n);
+                assert_eq!(d4 == 1, d4_pressed, "button {n}, D4 read {i}");
+            }
+            assert_eq!(pad.read(), 0x18);
+        }
+    }
+}
@@ -3,5 +3,7 @@
 //! See [PPU](https://www.nesdev.org/wiki/PPU), [PPU registers](https://www.nesdev.org/wiki/PPU_registers),
 //! [PPU memory map](https://www.nesdev.org/wiki/PPU_memory_map). Handles 341-dot scanlines, 262
 //! scanlines per frame, vblank NMI, background and sprite rendering, OAM, nametables, and palette.
+//! [`palette`] loads `.pal` files to replace the built-in color table.
 
+pub mod palette;
 pub mod ppu;
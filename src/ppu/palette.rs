@@ -0,0 +1,52 @@
+//! External `.pal` palette files.
+//!
+//! A `.pal` file is 64 RGB triples (192 bytes), one per PPU color index $00–$3F. Some tools save
+//! a 512-entry variant (1536 bytes) with a full palette for each of the 8 PPUMASK emphasis
+//! combinations, entry `index | emphasis << 6`. Install either with `PPU::set_palette`. See
+//! [PPU palettes](https://www.nesdev.org/wiki/PPU_palettes).
+
+use std::fs;
+use std::io;
+
+/// Size of a plain 64-color `.pal` file.
+const PAL_LEN: usize = 64 * 3;
+
+/// Size of a `.pal` file with all 8 emphasis variants.
+const PAL_EMPHASIS_LEN: usize = PAL_LEN * 8;
+
+/// Read a `.pal` file into a color index → 0xRRGGBB table of 64 or 512 entries.
+pub fn load_pal_file(path: &str) -> io::Result<Vec<u32>> {
+    let data = fs::read(path)?;
+    if data.len() != PAL_LEN && data.len() != PAL_EMPHASIS_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("expected {PAL_LEN} or {PAL_EMPHASIS_LEN} bytes, got {}", data.len()),
+        ));
+    }
+    Ok(data
+        .chunks_exact(3)
+        .map(|rgb| (rgb[0] as u32) << 16 | (rgb[1] as u32) << 8 | rgb[2] as u32)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_a_192_byte_pal_file() {
+        let path = std::env::temp_dir().join(format!("elaris-{}.pal", std::process::id()));
+        let mut data: Vec<u8> = (0..PAL_LEN).map(|i| i as u8).collect();
+        data[3..6].copy_from_slice(&[0xAB, 0xCD, 0xEF]);
+        fs::write(&path, &data).unwrap();
+        let colors = load_pal_file(path.to_str().unwrap());
+        fs::write(&path, &data[..100]).unwrap();
+        let short = load_pal_file(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+
+        let colors = colors.unwrap();
+        assert_eq!(colors.len(), 64);
+        assert_eq!((colors[0], colors[1], colors[63]), (0x000102, 0xABCDEF, 0xBDBEBF));
+        assert_eq!(short.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
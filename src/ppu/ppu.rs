@@ -7,7 +7,12 @@
 //! ## Timing
 //!
 //! 341 PPU cycles per scanline; 262 scanlines per frame (0–239 visible, 240 post-render, 241 vblank,
-//! 261 pre-render). VBlank NMI at scanline 241, cycle 1. PPU runs at 3× CPU clock.
+//! 261 pre-render). VBlank NMI at scanline 241, cycle 1. PPU runs at 3× CPU clock. With rendering
+//! enabled, odd frames skip the last pre-render dot (89341 dots instead of 89342).
+//!
+//! The PAL 2C07 has 312 scanlines (vblank from 241 through 310, pre-render 311), no skipped dot,
+//! and runs at 3.2× the CPU clock. See
+//! [Cycle reference chart](https://www.nesdev.org/wiki/Cycle_reference_chart).
 //!
 //! ## References
 //!
@@ -16,6 +21,7 @@
 //! - [Sprite 0 hit](https://www.nesdev.org/wiki/PPU_OAM#Sprite_zero_hits), [Sprite overflow](https://www.nesdev.org/wiki/PPU_sprite_evaluation#Sprite_overflow_bug)
 
 use crate::cartridge::{cartridge::Cartridge, mapper::Mirroring};
+use crate::region::Region;
 
 /// NES 2C02 64-color RGB palette (0xRRGGBB). Used to convert 6-bit palette indices to display.
 /// Index 0 is the backdrop color ($3F00). See PPU_palettes.
@@ -30,90 +36,207 @@ pub const NES_PALETTE_RGB: [u32; 64] = [
     0x000000,
 ];
 
+/// Pre-render scanline in NESdev numbering; `tick` reports it so the renderer can issue the
+/// sprite fetches a mapper like MMC3 counts.
+pub const PRE_RENDER_SCANLINE: u16 = 261;
+
+/// PPU dots after which an unrefreshed open-bus value has decayed to 0 (~600 ms on NTSC).
+const OPEN_BUS_DECAY_DOTS: u64 = 3_200_000;
+
 /// OAM (Object Attribute Memory): 64 sprites × 4 bytes. Each entry: Y, tile index, attributes, X.
 /// See PPU_OAM (byte 0=Y, 1=tile, 2=attr, 3=X).
 pub const OAM_LEN: usize = 256;
 
+/// One decoded OAM entry, for sprite viewers. See PPU_OAM.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SpriteEntry {
+    /// Top edge minus one (the sprite appears on scanline `y + 1`).
+    pub y: u8,
+    /// Tile index (in 8×16 mode, bit 0 selects the pattern table).
+    pub tile: u8,
+    /// Attributes: palette (bits 0–1), behind background (5), flip horizontal (6), vertical (7).
+    pub attr: u8,
+    pub x: u8,
+}
+
+impl SpriteEntry {
+    /// Sprite palette 0–3 (palette RAM $3F10 + 4 × palette).
+    pub fn palette(&self) -> u8 {
+        self.attr & 3
+    }
+
+    pub fn behind_background(&self) -> bool {
+        self.attr & 0x20 != 0
+    }
+
+    pub fn flip_horizontal(&self) -> bool {
+        self.attr & 0x40 != 0
+    }
+
+    pub fn flip_vertical(&self) -> bool {
+        self.attr & 0x80 != 0
+    }
+}
+
+/// A sprite selected by evaluation for the current scanline.
+#[derive(Clone, Copy)]
+struct SpriteSlot {
+    oam_index: u8,
+    /// Row within the sprite (0–7 or 0–15), before vertical flip.
+    y_offset: u8,
+    tile: u8,
+    attr: u8,
+    x: u8,
+}
+
 /// PPU state: cycle (0–340) and scanline (-1=pre-render, 0–239=visible, 241=vblank start), internal
 /// registers, nametable RAM (2 KiB), palette (32 bytes $3F00–$3F1F), OAM, and framebuffer.
+#[derive(Clone)]
 pub struct PPU {
+    /// Total PPU dots since power-on. Passed to `on_chr_access` so mappers can time A12 edges.
+    pub clock: u64,
     pub cycle: u16,
     pub scanline: i16,
+    /// NMI edge for the CPU: the PPU's NMI output (vblank flag AND PPUCTRL bit 7) went from low to
+    /// high. Stays set until the bus polls it, even if the output drops again meanwhile.
     pub nmi: bool,
     pub vblank: bool,
-    /// True when we've entered vblank (scanline 241); host clears after presenting frame.
+    /// Set at scanline 241, dot 1 (the same dot as the vblank flag and NMI). Visible lines are
+    /// rendered as each one completes (dot 340), so by then lines 0–239 of `framebuffer` hold the
+    /// finished frame, and stay untouched until scanline 0 of the next frame completes. The host
+    /// clears it after presenting.
     pub frame_ready: bool,
+    /// Toggled at the end of every pre-render line. Odd frames with rendering enabled skip the
+    /// last dot of the pre-render line, so they are one dot shorter.
+    pub frame_odd: bool,
     /// PPUCTRL ($2000): NMI enable, sprite size, bg/sprite pattern table, increment, nametable.
     pub ctrl: u8,
     /// PPUMASK ($2001): grayscale (0), show bg/sprite left 8 (1,2), show bg/sprite (3,4), emphasis (5–7).
     pub mask: u8,
-    /// Current VRAM address (PPUADDR $2006; 14-bit). Shared with scroll internally.
-    pub addr: u16,
-    pub addr_latch: bool,
-    pub scroll_x: u8,
-    pub scroll_y: u8,
-    pub scroll_latch: bool,
-    /// Nametable RAM: 2 KiB for $2000–$2FFF (mirroring applied per cartridge). PPU_nametables.
-    pub nametable: [u8; 0x800],
+    /// Current VRAM address (15-bit loopy `v`): PPUDATA address, and the scroll position while
+    /// rendering. Bits: `yyy NN YYYYY XXXXX` (fine Y, nametable, coarse Y, coarse X).
+    pub v: u16,
+    /// Temporary VRAM address (`t`), same layout as `v`. PPUCTRL, PPUSCROLL and PPUADDR write it;
+    /// it is copied into `v` by the second PPUADDR write and during rendering.
+    pub t: u16,
+    /// Fine X scroll (3 bits), set by the first PPUSCROLL write.
+    pub x: u8,
+    /// Write toggle shared by PPUSCROLL and PPUADDR (false = first write). Cleared by $2002 reads.
+    pub w: bool,
+    /// PPUDATA read buffer: reads below $3F00 return this and refill it, so each read sees the
+    /// byte fetched by the previous one.
+    pub read_buffer: u8,
+    /// `v` and `x` as the current scanline's background fetches saw them, latched at dot 256
+    /// before the Y increment. The line is drawn at dot 340, after later writes may have moved
+    /// them, so `render_scanline` draws from these.
+    line_v: u16,
+    line_x: u8,
+    /// Nametable RAM: the console's 2 KiB for $2000–$2FFF (mirroring applied per cartridge), then
+    /// 2 KiB standing in for four-screen cartridge VRAM. PPU_nametables.
+    pub nametable: [u8; 0x1000],
     /// Palette RAM: 32 bytes ($3F00–$3F1F); $3F10/14/18/1C mirror $3F00. PPU_palettes.
     pub palette: [u8; 32],
     /// OAM: 256 bytes. Filled via OAMDATA ($2003/$2004) or OAMDMA ($4014).
     pub oam: [u8; OAM_LEN],
     pub oam_addr: u8,
+    /// Last value on the PPU's internal data bus (the "decay register"): reads of write-only
+    /// registers and unused bits return it. Refreshed by register writes and reads.
+    pub open_bus: u8,
+    /// `clock` at the last refresh of `open_bus`; the value reads as 0 once it has decayed.
+    open_bus_refreshed: u64,
     /// Sprite 0 hit flag (PPUSTATUS bit 6). Set when sprite 0 overlaps background; clear on $2002 read.
     pub sprite_0_hit: bool,
     /// Sprite overflow flag (PPUSTATUS bit 5). Buggy on hardware; clear on $2002 read.
     pub sprite_overflow: bool,
     /// 256×240 framebuffer (one u32 0xRRGGBB per pixel). Row-major, scanline 0 = top.
     pub framebuffer: [u32; 256 * 240],
+    region: Region,
+    /// Color index → 0xRRGGBB table used for output; `NES_PALETTE_RGB` unless replaced with
+    /// `set_palette`. 64 entries, or 512 with one set per emphasis combination.
+    colors: Vec<u32>,
+}
+
+impl Default for PPU {
+    fn default() -> Self {
+        Self::new(Region::default())
+    }
 }
 
 impl PPU {
-    /// Create PPU in initial state (pre-render scanline -1, cycle 0).
-    pub fn new() -> Self {
+    /// Create PPU in initial state (scanline 0, cycle 0, as in Nintendulator's nestest.log) with
+    /// `region`'s frame length.
+    pub fn new(region: Region) -> Self {
         Self {
+            clock: 0,
             cycle: 0,
-            scanline: -1,
+            scanline: 0,
             nmi: false,
             vblank: false,
             frame_ready: false,
+            frame_odd: false,
             ctrl: 0,
             mask: 0,
-            addr: 0,
-            addr_latch: false,
-            scroll_x: 0,
-            scroll_y: 0,
-            scroll_latch: false,
-            nametable: [0; 0x800],
+            v: 0,
+            t: 0,
+            x: 0,
+            w: false,
+            read_buffer: 0,
+            line_v: 0,
+            line_x: 0,
+            nametable: [0; 0x1000],
             palette: [0; 32],
             oam: [0; OAM_LEN],
             oam_addr: 0,
+            open_bus: 0,
+            open_bus_refreshed: 0,
             sprite_0_hit: false,
             sprite_overflow: false,
             framebuffer: [0; 256 * 240],
+            region,
+            colors: NES_PALETTE_RGB.to_vec(),
         }
     }
 
-    /// Apply PPUMASK: grayscale (bit 0) and color emphasis (bits 5–7). See PPUMASK "Color control".
-    fn apply_display_mask(&self, rgb: u32) -> u32 {
-        let r = ((rgb >> 16) & 0xFF) as u32;
-        let g = ((rgb >> 8) & 0xFF) as u32;
-        let b = (rgb & 0xFF) as u32;
-        let rgb = if self.mask & 0x01 != 0 {
-            let gray = (r + g + b) / 3;
-            (gray << 16) | (gray << 8) | gray
+    /// Replace the color table (e.g. from `palette::load_pal_file`). Takes effect from the next
+    /// rendered pixel. With 64 entries emphasis is approximated by dimming; with 512, entry
+    /// `index | emphasis << 6` (PPUMASK bits 5–7) is used as is.
+    ///
+    /// Panics if `palette` has neither 64 nor 512 entries.
+    pub fn set_palette(&mut self, palette: &[u32]) {
+        assert!(
+            palette.len() == 64 || palette.len() == 512,
+            "palette must have 64 or 512 entries"
+        );
+        self.colors = palette.to_vec();
+    }
+
+    /// RGB for a 6-bit color index with PPUMASK applied: grayscale (bit 0) keeps only the
+    /// brightness column (index & $30), then color emphasis (bits 5–7), looked up in a 512-color
+    /// table or approximated. See PPUMASK "Color control".
+    fn apply_display_mask(&self, color_idx: u8) -> u32 {
+        let index = self.grayscale(color_idx) as usize;
+        if self.colors.len() == 512 {
+            self.colors[index | (self.mask as usize >> 5) << 6]
         } else {
-            rgb
-        };
-        self.apply_emphasis(rgb)
+            self.apply_emphasis(self.colors[index])
+        }
+    }
+
+    /// Color index as the PPU outputs it: & $30 under grayscale, else the low 6 bits.
+    fn grayscale(&self, color_idx: u8) -> u8 {
+        if self.mask & 0x01 != 0 {
+            color_idx & 0x30
+        } else {
+            color_idx & 0x3F
+        }
     }
 
     /// Dim channels not emphasized by PPUMASK bits 5 (R), 6 (G), 7 (B).
     fn apply_emphasis(&self, rgb: u32) -> u32 {
         let m = self.mask;
-        let mut r = ((rgb >> 16) & 0xFF) as u32;
-        let mut g = ((rgb >> 8) & 0xFF) as u32;
-        let mut b = (rgb & 0xFF) as u32;
+        let mut r = (rgb >> 16) & 0xFF;
+        let mut g = (rgb >> 8) & 0xFF;
+        let mut b = rgb & 0xFF;
         if m & 0x20 == 0 {
             r = r * 2 / 3;
         }
@@ -129,18 +252,25 @@ impl PPU {
     /// Render one visible scanline (0–239) into the framebuffer: background from nametable + scroll,
     /// then up to 8 sprites (priority: lower OAM index on top). Sprite 0 hit and overflow set here.
     /// Called when the PPU cycle counter has just completed that scanline (341 cycles). PPU_rendering.
+    ///
+    /// Pattern fetches are reported to the mapper with the dot they occur at on hardware
+    /// (background during dots 1–256, sprites during 257–320), and only while rendering is
+    /// enabled. For `PRE_RENDER_SCANLINE` only the sprite fetches are issued.
     pub fn render_scanline(&mut self, cart: &mut Cartridge, scanline: u16) {
-        let fine_x = self.scroll_x & 7;
-        let fine_y = self.scroll_y & 7;
-        let coarse_x = self.scroll_x >> 3;
-        let coarse_y = self.scroll_y >> 3;
-        let nametable_base = (self.ctrl & 3) as u16;
-        let bg_pattern_base = if self.ctrl & 0x10 != 0 {
-            0x1000u16
-        } else {
-            0x0000
-        };
-        let mirroring = cart.mapper.mirroring();
+        let line_start = self.clock.saturating_sub(341);
+        let rendering = self.mask & 0x18 != 0;
+        if scanline == PRE_RENDER_SCANLINE {
+            if rendering {
+                self.notify_sprite_fetches(cart, line_start, [None; 8]);
+            }
+            return;
+        }
+
+        let fine_x = self.line_x;
+        let coarse_x = self.line_v & 0x1F;
+        let tile_y_in_nt = (self.line_v >> 5) & 0x1F;
+        let py_in_tile = (self.line_v >> 12) & 7;
+        let nametable_base = (self.line_v >> 10) & 3;
         let y = scanline;
 
         let show_bg = self.mask & 0x08 != 0;
@@ -148,53 +278,51 @@ impl PPU {
         let show_bg_left = self.mask & 0x02 != 0;
         let show_sprites_left = self.mask & 0x04 != 0;
 
-        let backdrop_idx = self.palette[0] as usize & 0x3F;
-        let backdrop_rgb = NES_PALETTE_RGB[backdrop_idx];
+        let backdrop_rgb = self.apply_display_mask(self.palette[0]);
 
         // Background pixel values (0-3) per x for sprite 0 hit and priority. 0 = transparent.
         let mut bg_pixel: [u8; 256] = [0; 256];
 
         let base_nt_x = nametable_base & 1;
         let base_nt_y = (nametable_base >> 1) & 1;
-        // PPUCTRL bits 0–1 are the 9th bit of X/Y scroll (nametable base). NESdev PPU_scrolling.
+        // Nametable bit 0 of `v` is the 9th bit of the X scroll. NESdev PPU_scrolling. The row
+        // (coarse Y, fine Y, nametable bit 1) is the same for the whole scanline; coarse Y 30 and
+        // 31 fetch the attribute table as tiles, as on hardware.
         let base_x: u32 = (base_nt_x as u32) * 256;
-        let base_y: u32 = (base_nt_y as u32) * 240;
+        // Current tile's column, palette and pattern bytes; fetched once per 8 pixels (up to 33
+        // tiles per line with fine X scroll) rather than per pixel.
+        let mut cached_tile_x = None;
+        let (mut palette_bank, mut row_lo, mut row_hi) = (0u8, 0u8, 0u8);
         for x in 0..256u16 {
             let total_x = (base_x + x as u32 + fine_x as u32 + (coarse_x as u32) * 8) % 512;
-            let total_y = (base_y + y as u32 + fine_y as u32 + (coarse_y as u32) * 8) % 480;
             let tile_x = (total_x / 8) as u16;
-            let tile_y = (total_y / 8) as u16;
 
-            let nt_x = tile_x / 32;
-            let nt_y = tile_y / 30;
+            if cached_tile_x != Some(tile_x) {
+                cached_tile_x = Some(tile_x);
+                let nt_x = tile_x / 32;
+                let logical_nt = (base_nt_x ^ nt_x) + (base_nt_y << 1);
+                let tile_x_in_nt = tile_x % 32;
+
+                let nt_addr = 0x2000 + logical_nt * 0x400;
+                let (bank, lo, hi, row_addr) = self.fetch_background_tile(
+                    cart,
+                    nt_addr,
+                    tile_x_in_nt,
+                    tile_y_in_nt,
+                    py_in_tile,
+                );
+                (palette_bank, row_lo, row_hi) = (bank, lo, hi);
+                // Notify after reading: a bank switch triggered by this fetch (MMC2/MMC4 latch)
+                // applies from the next tile on.
+                if rendering {
+                    let dot = line_start + 1 + x as u64;
+                    cart.on_chr_access(row_addr, dot);
+                    cart.on_chr_access(row_addr + 8, dot);
+                }
+            }
 
-            let logical_nt = (base_nt_x ^ nt_x) + ((base_nt_y ^ nt_y) << 1);
-            let nt_phys = match mirroring {
-                Mirroring::Vertical => logical_nt & 1,
-                Mirroring::Horizontal => logical_nt >> 1,
-                Mirroring::OneScreenLower => 0,
-                Mirroring::OneScreenUpper => 1,
-            };
-            let tile_x_in_nt = tile_x % 32;
-            let tile_y_in_nt = tile_y % 30;
-
-            let nt_index = (nt_phys * 0x400) + tile_y_in_nt * 32 + tile_x_in_nt;
-            let tile_id = self.nametable[nt_index as usize];
-
-            let attr_index =
-                (nt_phys * 0x400) + 0x3C0 + (tile_y_in_nt / 4) * 8 + (tile_x_in_nt / 4);
-            let attr_byte = self.nametable[attr_index as usize];
-            let shift = ((tile_y_in_nt & 2) << 1) | (tile_x_in_nt & 2);
-            let palette_bank = (attr_byte >> shift) & 3;
-
-            let px_in_tile = (total_x % 8) as u16;
-            let py_in_tile = (total_y % 8) as u16;
-            let tile_addr = bg_pattern_base + (tile_id as u16) * 16;
-            cart.on_chr_access(tile_addr + py_in_tile);
-            let row_lo = cart.read(tile_addr + py_in_tile);
-            cart.on_chr_access(tile_addr + py_in_tile + 8);
-            let row_hi = cart.read(tile_addr + py_in_tile + 8);
-            let bit = 7 - (px_in_tile % 8);
+            let px_in_tile = total_x % 8;
+            let bit = 7 - px_in_tile;
             let low = (row_lo >> bit) & 1;
             let high = (row_hi >> bit) & 1;
             let pixel_value = (high << 1) | low;
@@ -203,95 +331,65 @@ impl PPU {
 
             let rgb = if show_bg && (x >= 8 || show_bg_left) && pixel_value != 0 {
                 let palette_idx = 0x3F00 + (palette_bank as u16) * 4 + (pixel_value as u16);
-                let color_idx = self.palette[Self::palette_index(palette_idx)] as usize;
-                NES_PALETTE_RGB[color_idx & 0x3F]
+                self.apply_display_mask(self.palette[Self::palette_index(palette_idx)])
             } else {
                 backdrop_rgb
             };
-            self.framebuffer[(y as usize) * 256 + (x as usize)] = self.apply_display_mask(rgb);
+            self.framebuffer[(y as usize) * 256 + (x as usize)] = rgb;
         }
 
         // Sprite evaluation: find up to 8 sprites on this scanline (lower OAM index = higher priority).
         let sprite_height = if self.ctrl & 0x20 != 0 { 16 } else { 8 };
-        let sprite_pattern_base = if self.ctrl & 0x08 != 0 {
-            0x1000u16
-        } else {
-            0x0000
-        };
-
-        #[derive(Clone, Copy)]
-        struct SpriteSlot {
-            oam_index: u8,
-            y_offset: u8,
-            y: u8,
-            tile: u8,
-            attr: u8,
-            x: u8,
-        }
 
         let mut slots: [Option<SpriteSlot>; 8] = [None; 8];
         let mut slot_count = 0u8;
 
-        for i in 0..64u8 {
-            let base = (i as usize) * 4;
+        let in_range = |oam_y: u8| (oam_y as u16..oam_y as u16 + sprite_height).contains(&scanline);
+        let mut n = 0usize;
+        while n < 64 && slot_count < 8 {
+            let base = n * 4;
             let oam_y = self.oam[base];
-            let oam_tile = self.oam[base + 1];
-            let oam_attr = self.oam[base + 2];
-            let oam_x = self.oam[base + 3];
-
-            let y_lo = oam_y as u16;
-            let y_hi = y_lo + sprite_height;
-            if scanline >= y_lo && scanline < y_hi {
-                if slot_count < 8 {
-                    let y_offset = (scanline - y_lo) as u8;
-                    slots[slot_count as usize] = Some(SpriteSlot {
-                        oam_index: i,
-                        y_offset,
-                        y: oam_y,
-                        tile: oam_tile,
-                        attr: oam_attr,
-                        x: oam_x,
-                    });
-                    slot_count += 1;
-                } else {
-                    self.sprite_overflow = true;
-                }
+            if in_range(oam_y) {
+                slots[slot_count as usize] = Some(SpriteSlot {
+                    oam_index: n as u8,
+                    y_offset: (scanline - oam_y as u16) as u8,
+                    tile: self.oam[base + 1],
+                    attr: self.oam[base + 2],
+                    x: self.oam[base + 3],
+                });
+                slot_count += 1;
+            }
+            n += 1;
+        }
+
+        // Overflow search with the hardware bug: once 8 sprites are found, the PPU advances the
+        // byte index m along with the sprite index n, so it compares tile, attribute and X bytes
+        // as if they were Y. It can both miss a 9th sprite and report one that isn't there.
+        let mut m = 0;
+        while n < 64 {
+            if in_range(self.oam[n * 4 + m]) {
+                self.sprite_overflow = true;
+                break;
             }
+            n += 1;
+            m = (m + 1) & 3;
+        }
+
+        if rendering {
+            self.notify_sprite_fetches(cart, line_start, slots);
         }
 
         // Draw sprites back-to-front (highest OAM index first) so lower-index sprites appear on top.
         for s in (0..slot_count).rev() {
             let slot = slots[s as usize].unwrap();
-            let flip_v = slot.attr & 0x80 != 0;
             let flip_h = slot.attr & 0x40 != 0;
             let behind_bg = slot.attr & 0x20 != 0;
             let palette_bank = (slot.attr & 3) as u16;
             let palette_base = 0x3F10 + palette_bank * 4;
 
-            let row_in_sprite = if flip_v {
-                (sprite_height - 1) as u8 - slot.y_offset
-            } else {
-                slot.y_offset
-            };
-
-            let (tile_addr, row_in_tile) = if sprite_height == 8 {
-                let addr = sprite_pattern_base + (slot.tile as u16) * 16;
-                (addr, row_in_sprite)
-            } else {
-                let table = (slot.tile & 1) as u16 * 0x1000;
-                let tile_8 = (slot.tile & 0xFE) as u16;
-                let (tile_idx, row) = if row_in_sprite < 8 {
-                    (tile_8, row_in_sprite)
-                } else {
-                    (tile_8 + 1, row_in_sprite - 8)
-                };
-                (table + tile_idx * 16, row)
-            };
-
-            cart.on_chr_access(tile_addr + row_in_tile as u16);
-            let row_lo = cart.read(tile_addr + row_in_tile as u16);
-            cart.on_chr_access(tile_addr + row_in_tile as u16 + 8);
-            let row_hi = cart.read(tile_addr + row_in_tile as u16 + 8);
+            let row_addr = self.sprite_row_addr(&slot);
+            let row_lo = cart.read(row_addr);
+            let row_hi = cart.read(row_addr + 8);
 
             if !show_sprites {
                 continue;
@@ -324,13 +422,68 @@ impl PPU {
                 }
 
                 let palette_idx = palette_base + pixel_value as u16;
-                let color_idx = self.palette[Self::palette_index(palette_idx)] as usize;
-                let rgb = NES_PALETTE_RGB[color_idx & 0x3F];
-                self.framebuffer[idx] = self.apply_display_mask(rgb);
+                let color_idx = self.palette[Self::palette_index(palette_idx)];
+                self.framebuffer[idx] = self.apply_display_mask(color_idx);
             }
         }
     }
 
+    /// Address of the low pattern plane for this sprite's row on the current scanline (the high
+    /// plane is 8 bytes later). Vertical flip mirrors the row across the whole sprite, so a
+    /// flipped 8×16 sprite draws its bottom tile in the top half.
+    fn sprite_row_addr(&self, slot: &SpriteSlot) -> u16 {
+        let tall = self.ctrl & 0x20 != 0;
+        let last_row = if tall { 15 } else { 7 };
+        let row = if slot.attr & 0x80 != 0 {
+            last_row - slot.y_offset
+        } else {
+            slot.y_offset
+        };
+        let table_8x8 = if self.ctrl & 0x08 != 0 {
+            0x1000u16
+        } else {
+            0x0000
+        };
+        Self::sprite_pattern_addr(slot.tile, row, tall, table_8x8)
+    }
+
+    /// Pattern address of `row` (0–7, or 0–15 for 8×16) of sprite `tile`. 8×8 sprites use
+    /// `table_8x8` (PPUCTRL bit 3); 8×16 sprites take the table from tile bit 0 and stack tiles
+    /// `tile & $FE` (rows 0–7) and `tile | 1` (rows 8–15). See PPU OAM, byte 1.
+    fn sprite_pattern_addr(tile: u8, row: u8, tall: bool, table_8x8: u16) -> u16 {
+        if tall {
+            let table = (tile & 1) as u16 * 0x1000;
+            let tile = (tile & 0xFE) as u16 + (row >= 8) as u16;
+            table + tile * 16 + (row & 7) as u16
+        } else {
+            table_8x8 + tile as u16 * 16 + row as u16
+        }
+    }
+
+    /// Report the eight sprite pattern fetches of dots 257–320 (two per slot, at the slot's 5th
+    /// and 7th dot). Empty slots fetch tile $FF, as the PPU does, so A12 still rises with 8×16
+    /// sprites or the $1000 sprite table even when no sprite is on the line.
+    fn notify_sprite_fetches(
+        &self,
+        cart: &mut Cartridge,
+        line_start: u64,
+        slots: [Option<SpriteSlot>; 8],
+    ) {
+        for (i, slot) in slots.iter().enumerate() {
+            let dummy = SpriteSlot {
+                oam_index: 0xFF,
+                y_offset: 0,
+                tile: 0xFF,
+                attr: 0,
+                x: 0xFF,
+            };
+            let addr = self.sprite_row_addr(slot.as_ref().unwrap_or(&dummy));
+            let dot = line_start + 257 + i as u64 * 8;
+            cart.on_chr_access(addr, dot + 4);
+            cart.on_chr_access(addr + 8, dot + 6);
+        }
+    }
+
     /// Resolve PPU palette address $3F00–$3F1F (and $3F20–$3FFF mirrors) to 32-byte index.
     /// Addresses $3F10, $3F14, $3F18, $3F1C mirror $3F00 (background color).
     fn palette_index(addr: u16) -> usize {
@@ -343,18 +496,20 @@ impl PPU {
     }
 
     /// Advance PPU by one cycle. 341 cycles per scanline; at cycle 1 of scanline 241 set vblank and
-    /// optionally NMI. Returns Some(scanline) when a visible scanline (0–239) has just completed
-    /// (cycle 341), so the bus can render it. See PPU_rendering, Cycle_reference_chart.
+    /// optionally NMI. Returns Some(scanline) when a visible scanline (0–239) or the pre-render
+    /// line (`PRE_RENDER_SCANLINE`) has just completed (cycle 341), so the bus can render it.
+    /// See PPU_rendering, Cycle_reference_chart.
     pub fn tick(&mut self) -> Option<u16> {
+        self.clock += 1;
         self.cycle += 1;
 
-        // Start of vblank (scanline 241, cycle 1)
+        // Start of vblank (scanline 241, cycle 1). NMI fires only if PPUCTRL bit 7 is set now, so
+        // clearing it before this dot suppresses this frame's NMI.
         if self.scanline == 241 && self.cycle == 1 {
+            let was_high = self.nmi_output();
             self.vblank = true;
             self.frame_ready = true;
-            if self.ctrl & 0x80 != 0 {
-                self.nmi = true;
-            }
+            self.nmi |= !was_high && self.nmi_output();
         }
 
         // Clear vblank at end of pre-render
@@ -362,17 +517,44 @@ impl PPU {
             self.vblank = false;
         }
 
-        // End of scanline (341 cycles per scanline)
+        // Scroll updates on the visible and pre-render lines, so PPUSCROLL/PPUADDR writes made
+        // mid-frame (e.g. after sprite 0 hit) take effect from the next line. PPU_scrolling.
+        if self.scanline < 240 {
+            let rendering = self.mask & 0x18 != 0;
+            match self.cycle {
+                256 => {
+                    self.line_v = self.v;
+                    self.line_x = self.x;
+                    if rendering {
+                        self.increment_y();
+                    }
+                }
+                257 if rendering => self.copy_horizontal(),
+                280..=304 if rendering && self.scanline == -1 => self.copy_vertical(),
+                _ => {}
+            }
+        }
+
+        // End of scanline (341 cycles per scanline). On odd frames with rendering enabled the
+        // pre-render line jumps from dot 339 straight to dot 0 of line 0. PPU_frame_timing.
+        let skip_dot = self.scanline == -1
+            && self.frame_odd
+            && self.mask & 0x18 != 0
+            && self.region == Region::Ntsc;
+        let line_dots = if skip_dot { 340 } else { 341 };
         let mut completed_scanline = None;
-        if self.cycle == 341 {
-            // Just finished this scanline; if visible, caller should render it
+        if self.cycle == line_dots {
+            // Just finished this scanline; if visible (or pre-render), caller should render it
             if self.scanline >= 0 && self.scanline < 240 {
                 completed_scanline = Some(self.scanline as u16);
+            } else if self.scanline == -1 {
+                completed_scanline = Some(PRE_RENDER_SCANLINE);
+                self.frame_odd = !self.frame_odd;
             }
             self.cycle = 0;
             self.scanline += 1;
 
-            if self.scanline == 261 {
+            if self.scanline == self.region.scanlines_per_frame() as i16 - 1 {
                 self.scanline = -1;
             }
         }
@@ -382,7 +564,7 @@ impl PPU {
     /// Read PPUSTATUS ($2002): bits 7=vblank, 6=sprite 0 hit, 5=sprite overflow; lower bits open bus.
     /// Side effect: clears vblank/sprite flags and the w (write latch) for PPUSCROLL/PPUADDR.
     pub fn read_status(&mut self) -> u8 {
-        let mut status = 0u8;
+        let mut status = self.read_open_bus() & 0x1F;
 
         if self.vblank {
             status |= 0x80;
@@ -394,16 +576,136 @@ impl PPU {
             status |= 0x20;
         }
 
+        // Drops the NMI output; an edge already latched in `nmi` is still delivered.
         self.vblank = false;
-        self.nmi = false;
         self.sprite_0_hit = false;
         self.sprite_overflow = false;
-        self.addr_latch = false;
-        self.scroll_latch = false;
+        self.w = false;
 
+        self.refresh_open_bus(status, 0xE0);
         status
     }
 
+    /// The PPU data bus as seen by a read of a write-only register.
+    pub fn read_open_bus(&self) -> u8 {
+        if self.clock - self.open_bus_refreshed > OPEN_BUS_DECAY_DOTS {
+            0
+        } else {
+            self.open_bus
+        }
+    }
+
+    /// Drive the bits of `mask` onto the PPU data bus. Every register write drives all 8 bits;
+    /// reads drive only the bits the register actually outputs. See Open_bus_behavior "PPU open
+    /// bus". Decay is modeled for the byte as a whole rather than bit by bit.
+    pub fn refresh_open_bus(&mut self, data: u8, mask: u8) {
+        self.open_bus = (self.read_open_bus() & !mask) | (data & mask);
+        self.open_bus_refreshed = self.clock;
+    }
+
+    /// All 64 OAM entries, decoded.
+    pub fn sprites(&self) -> [SpriteEntry; 64] {
+        std::array::from_fn(|i| SpriteEntry {
+            y: self.oam[i * 4],
+            tile: self.oam[i * 4 + 1],
+            attr: self.oam[i * 4 + 2],
+            x: self.oam[i * 4 + 3],
+        })
+    }
+
+    /// RGB of each palette slot $3F00–$3F1F as drawn now: $3F10/$3F14/$3F18/$3F1C resolve to
+    /// $3F00, and PPUMASK grayscale and emphasis are applied.
+    pub fn palette_rgb(&self) -> [u32; 32] {
+        std::array::from_fn(|i| {
+            self.apply_display_mask(self.palette[Self::palette_index(0x3F00 + i as u16)])
+        })
+    }
+
+    /// Pattern table 0 or 1 ($0000 / $1000) as a 128×128 image for a tile viewer: 16×16 tiles,
+    /// tile 0 top left, colored with background palette `palette_row` (0–3). Reads go through
+    /// the current CHR banks but do not notify the mapper, so MMC2/MMC4 latches and the MMC3 IRQ
+    /// counter are left alone.
+    pub fn render_pattern_table(
+        &self,
+        cart: &mut Cartridge,
+        table: u8,
+        palette_row: u8,
+    ) -> [u32; 128 * 128] {
+        let mut image = [0; 128 * 128];
+        let base = (table as u16 & 1) * 0x1000;
+        for tile in 0..256u16 {
+            let (tile_x, tile_y) = ((tile % 16) as usize * 8, (tile / 16) as usize * 8);
+            for row in 0..8u16 {
+                let addr = base + tile * 16 + row;
+                let lo = cart.read(addr);
+                let hi = cart.read(addr + 8);
+                for col in 0..8 {
+                    let pixel = ((hi >> (7 - col)) & 1) << 1 | (lo >> (7 - col)) & 1;
+                    image[(tile_y + row as usize) * 128 + tile_x + col] =
+                        self.background_rgb(palette_row & 3, pixel);
+                }
+            }
+        }
+        image
+    }
+
+    /// One of the four logical nametables (0–3 for $2000, $2400, $2800, $2C00, resolved through
+    /// the current mirroring) as a 256×240 background image, ignoring scroll, sprites and the
+    /// PPUMASK show bits. Uses the renderer's tile and attribute fetches, but without notifying
+    /// the mapper, so CHR latches and the MMC3 IRQ counter are left alone.
+    pub fn render_nametable(&self, cart: &mut Cartridge, which: u8) -> [u32; 256 * 240] {
+        let mut image = [0; 256 * 240];
+        let nt_addr = 0x2000 + (which as u16 & 3) * 0x400;
+        for tile_y in 0..30u16 {
+            for tile_x in 0..32u16 {
+                for fine_y in 0..8u16 {
+                    let (bank, lo, hi, _) =
+                        self.fetch_background_tile(cart, nt_addr, tile_x, tile_y, fine_y);
+                    let row_start = (tile_y * 8 + fine_y) as usize * 256 + tile_x as usize * 8;
+                    for col in 0..8 {
+                        let pixel = ((hi >> (7 - col)) & 1) << 1 | (lo >> (7 - col)) & 1;
+                        image[row_start + col] = self.background_rgb(bank, pixel);
+                    }
+                }
+            }
+        }
+        image
+    }
+
+    /// Fetch row `fine_y` of background tile (`tile_x`, `tile_y`) in the nametable at `nt_addr`:
+    /// returns the attribute palette bank, the low and high pattern bytes, and the pattern
+    /// address of the row (PPUCTRL bit 4 picks the table).
+    fn fetch_background_tile(
+        &self,
+        cart: &mut Cartridge,
+        nt_addr: u16,
+        tile_x: u16,
+        tile_y: u16,
+        fine_y: u16,
+    ) -> (u8, u8, u8, u16) {
+        let tile_id = self.read_nametable(cart, nt_addr + tile_y * 32 + tile_x);
+        let attr_byte = self.read_nametable(cart, nt_addr + 0x3C0 + (tile_y / 4) * 8 + tile_x / 4);
+        let shift = ((tile_y & 2) << 1) | (tile_x & 2);
+        let pattern_base = if self.ctrl & 0x10 != 0 { 0x1000 } else { 0x0000 };
+        let row_addr = pattern_base + tile_id as u16 * 16 + fine_y;
+        (
+            (attr_byte >> shift) & 3,
+            cart.read(row_addr),
+            cart.read(row_addr + 8),
+            row_addr,
+        )
+    }
+
+    /// RGB of background pixel value `pixel` (0–3) in palette `bank`; 0 is the backdrop color.
+    fn background_rgb(&self, bank: u8, pixel: u8) -> u32 {
+        let addr = if pixel == 0 {
+            0x3F00
+        } else {
+            0x3F00 + bank as u16 * 4 + pixel as u16
+        };
+        self.apply_display_mask(self.palette[Self::palette_index(addr)])
+    }
+
     /// Write OAMADDR ($2003).
     pub fn write_oam_addr(&mut self, data: u8) {
         self.oam_addr = data;
@@ -411,7 +713,9 @@ impl PPU {
 
     /// Read OAMDATA ($2004); returns OAM byte at current OAMADDR (read does not increment on real NES).
     pub fn read_oam_data(&mut self) -> u8 {
-        self.oam[self.oam_addr as usize]
+        let data = self.oam[self.oam_addr as usize];
+        self.refresh_open_bus(data, 0xFF);
+        data
     }
 
     /// Write OAMDATA ($2004); writes OAM and increments OAMADDR.
@@ -420,18 +724,20 @@ impl PPU {
         self.oam_addr = self.oam_addr.wrapping_add(1);
     }
 
-    /// OAMDMA ($4014): copy 256 bytes from CPU RAM page `page` (high byte of address, e.g. $02 →
-    /// $0200–$02FF) into OAM. Takes 513–514 cycles; we copy in one go. OAMADDR should be 0 first.
-    pub fn oam_dma(&mut self, ram: &[u8; 2048], page: u8) {
-        let start = ((page as u16) << 8) as usize % 2048;
-        for i in 0..256 {
-            self.oam[i] = ram[(start + i) % 2048];
-        }
-    }
-
     /// Write PPUCTRL ($2000).
     pub fn write_ctrl(&mut self, data: u8) {
+        // Enabling NMI (bit 7: 0→1) while the vblank flag is still set raises NMI at once, and
+        // toggling bit 7 off and on again during vblank raises another (multiple NMIs per frame).
+        let was_high = self.nmi_output();
         self.ctrl = data;
+        self.t = (self.t & !0x0C00) | ((data as u16 & 0x03) << 10);
+        self.nmi |= !was_high && self.nmi_output();
+    }
+
+    /// The PPU's /NMI output, active while the vblank flag and PPUCTRL bit 7 are both set. The CPU
+    /// reacts to its rising edge. See [NMI](https://www.nesdev.org/wiki/NMI) "Operation".
+    fn nmi_output(&self) -> bool {
+        self.vblank && self.ctrl & 0x80 != 0
     }
 
     /// Write PPUMASK ($2001).
@@ -439,58 +745,58 @@ impl PPU {
         self.mask = data;
     }
 
-    /// Write PPUADDR ($2006): two-byte write for 16-bit VRAM address (high then low).
+    /// Write PPUADDR ($2006): two-byte write into `t` (high 6 bits, then low byte); the second
+    /// write copies `t` to `v`. The first write also clears bit 14 of `t`.
     pub fn write_addr(&mut self, data: u8) {
-        if !self.addr_latch {
-            self.addr = (data as u16) << 8;
-            self.addr_latch = true;
+        if !self.w {
+            self.t = (self.t & 0x00FF) | ((data as u16 & 0x3F) << 8);
         } else {
-            self.addr |= data as u16;
-            self.addr_latch = false;
+            self.t = (self.t & 0xFF00) | data as u16;
+            self.v = self.t;
         }
+        self.w = !self.w;
     }
 
-    /// Read PPUDATA ($2007): return byte at current VRAM address; then increment addr by 1 or 32
-    /// (PPUCTRL bit 2). Palette $3F00–$3F1F returns immediately; other reads use internal buffer.
+    /// Read PPUDATA ($2007), then increment the address by 1 or 32 (PPUCTRL bit 2). Reads below
+    /// $3F00 return the read buffer and refill it, so the first read after setting PPUADDR is
+    /// stale. Palette reads return immediately but still refill the buffer with the nametable
+    /// byte underneath ($2F00–$2FFF), and reflect grayscale. See PPUDATA "The PPUDATA read
+    /// buffer".
     pub fn read_data(&mut self, cart: &mut Cartridge) -> u8 {
-        let addr = self.addr & 0x3FFF;
+        let addr = self.v & 0x3FFF;
 
         let data = match addr {
             // CHR: pattern tables (notify mapper for e.g. MMC3 IRQ A12 tracking)
             0x0000..=0x1FFF => {
-                cart.on_chr_access(addr);
-                cart.read(addr)
+                cart.on_chr_access(addr, self.clock);
+                std::mem::replace(&mut self.read_buffer, cart.read(addr))
             }
 
-            // Nametables (with mirroring)
-            0x2000..=0x2FFF => {
-                let mirroring = cart.mapper.mirroring();
-                let index = Self::map_nametable_addr(addr, mirroring);
-                self.nametable[index as usize]
+            // Nametables (with mirroring) and their $3000–$3EFF mirror
+            0x2000..=0x3EFF => {
+                let fetched = self.read_nametable(cart, addr);
+                std::mem::replace(&mut self.read_buffer, fetched)
             }
 
-            // Mirrors of $2000–$2EFF
-            0x3000..=0x3EFF => {
-                let mirrored = addr - 0x1000;
-                let mirroring = cart.mapper.mirroring();
-                let index = Self::map_nametable_addr(mirrored, mirroring);
-                self.nametable[index as usize]
+            // Palette RAM $3F00-$3F1F and $3F20-$3FFF mirrors; bits 6–7 are open bus
+            _ => {
+                self.read_buffer = self.read_nametable(cart, addr);
+                let color = self.grayscale(self.palette[Self::palette_index(addr)]);
+                (self.read_open_bus() & 0xC0) | color
             }
-
-            // Palette RAM $3F00-$3F1F and $3F20-$3FFF mirrors
-            0x3F00..=0x3FFF => self.palette[Self::palette_index(addr)],
-            _ => 0,
         };
+        let driven = if addr >= 0x3F00 { 0x3F } else { 0xFF };
+        self.refresh_open_bus(data, driven);
 
         // Increment by 32 if PPUCTRL bit 2 set, else 1
         let inc = if self.ctrl & 0x04 != 0 { 32 } else { 1 };
-        self.addr = self.addr.wrapping_add(inc);
+        self.v = self.v.wrapping_add(inc) & 0x7FFF;
         data
     }
 
     /// Write PPUDATA ($2007): writes VRAM at current address, then increments (by 1 or 32 per PPUCTRL).
     pub fn write_data(&mut self, cart: &mut Cartridge, data: u8) {
-        let addr = self.addr & 0x3FFF;
+        let addr = self.v & 0x3FFF;
 
         match addr {
             // CHR RAM only (writes to ROM ignored by mapper)
@@ -498,20 +804,8 @@ impl PPU {
                 cart.write(addr, data);
             }
 
-            // Nametables
-            0x2000..=0x2FFF => {
-                let mirroring = cart.mapper.mirroring();
-                let index = Self::map_nametable_addr(addr, mirroring);
-                self.nametable[index as usize] = data;
-            }
-
-            // Mirrors
-            0x3000..=0x3EFF => {
-                let mirrored = addr - 0x1000;
-                let mirroring = cart.mapper.mirroring();
-                let index = Self::map_nametable_addr(mirrored, mirroring);
-                self.nametable[index as usize] = data;
-            }
+            // Nametables and their $3000–$3EFF mirror
+            0x2000..=0x3EFF => self.write_nametable(cart, addr, data),
 
             // Palette RAM $3F00-$3F1F and $3F20-$3FFF mirrors (upper 2 bits of data ignored on real NES)
             0x3F00..=0x3FFF => self.palette[Self::palette_index(addr)] = data & 0x3F,
@@ -520,22 +814,78 @@ impl PPU {
 
         // Increment by 32 if PPUCTRL bit 2 set, else 1
         let inc = if self.ctrl & 0x04 != 0 { 32 } else { 1 };
-        self.addr = self.addr.wrapping_add(inc);
+        self.v = self.v.wrapping_add(inc) & 0x7FFF;
     }
 
-    /// Write PPUSCROLL ($2005): two-byte write. First = X scroll (fine + coarse low 8); second = Y.
-    /// Bit 8 of X/Y come from PPUCTRL bits 0–1. Shared w register with PPUADDR; read $2002 to reset.
+    /// Write PPUSCROLL ($2005): two-byte write. First = X scroll (coarse X into `t`, fine X into
+    /// `x`); second = Y (coarse and fine Y into `t`). Bit 8 of X/Y come from PPUCTRL bits 0–1.
+    /// Shares `w` with PPUADDR; read $2002 to reset.
     pub fn write_scroll(&mut self, data: u8) {
-        if !self.scroll_latch {
-            self.scroll_x = data;
-            self.scroll_latch = true;
+        if !self.w {
+            self.t = (self.t & !0x001F) | (data as u16 >> 3);
+            self.x = data & 7;
         } else {
-            self.scroll_y = data;
-            self.scroll_latch = false;
+            self.t =
+                (self.t & !0x73E0) | ((data as u16 & 0x07) << 12) | ((data as u16 & 0xF8) << 2);
+        }
+        self.w = !self.w;
+    }
+
+    /// Move `v` down one pixel row: fine Y, then coarse Y, wrapping from row 29 into the other
+    /// vertical nametable (row 31 wraps without switching). PPU_scrolling "Y increment".
+    fn increment_y(&mut self) {
+        if self.v & 0x7000 != 0x7000 {
+            self.v += 0x1000;
+            return;
+        }
+        self.v &= !0x7000;
+        let coarse_y = match (self.v >> 5) & 0x1F {
+            29 => {
+                self.v ^= 0x0800;
+                0
+            }
+            31 => 0,
+            y => y + 1,
+        };
+        self.v = (self.v & !0x03E0) | (coarse_y << 5);
+    }
+
+    /// Copy coarse X and the horizontal nametable bit from `t` to `v` (dot 257).
+    fn copy_horizontal(&mut self) {
+        self.v = (self.v & !0x041F) | (self.t & 0x041F);
+    }
+
+    /// Copy fine Y, coarse Y and the vertical nametable bit from `t` to `v` (pre-render dots
+    /// 280–304).
+    fn copy_vertical(&mut self) {
+        self.v = (self.v & !0x7BE0) | (self.t & 0x7BE0);
+    }
+
+    /// Read a nametable byte ($2000–$3EFF; $3000–$3EFF folds onto $2000–$2EFF). The single
+    /// nametable fetch path for PPUDATA and the renderer, so the cartridge hooks and mirroring
+    /// see the same address either way. The cartridge may supply the byte (fill mode, cartridge
+    /// VRAM); otherwise it comes from internal RAM via the current mirroring.
+    fn read_nametable(&self, cart: &mut Cartridge, addr: u16) -> u8 {
+        let addr = 0x2000 | (addr & 0x0FFF);
+        if let Some(data) = cart.ppu_nametable_read(addr) {
+            return data;
+        }
+        let index = Self::map_nametable_addr(addr, cart.mapper.mirroring());
+        self.nametable[index as usize]
+    }
+
+    /// Write a nametable byte ($2000–$3EFF, folded like `read_nametable`) to the cartridge if it
+    /// claims it, else internal RAM.
+    fn write_nametable(&mut self, cart: &mut Cartridge, addr: u16, data: u8) {
+        let addr = 0x2000 | (addr & 0x0FFF);
+        if !cart.ppu_nametable_write(addr, data) {
+            let index = Self::map_nametable_addr(addr, cart.mapper.mirroring());
+            self.nametable[index as usize] = data;
         }
     }
 
-    /// Map PPU nametable VRAM address ($2000–$2FFF) to internal 2 KiB index using mirroring.
+    /// Map PPU nametable VRAM address ($2000–$2FFF) to an index into `nametable` using mirroring.
+    /// Only four-screen mirroring reaches past the console's 2 KiB.
     pub fn map_nametable_addr(addr: u16, mirroring: Mirroring) -> u16 {
         let addr = (addr - 0x2000) & 0xfff;
         let table = addr / 0x400;
@@ -554,6 +904,394 @@ impl PPU {
             },
             Mirroring::OneScreenLower => offset,
             Mirroring::OneScreenUpper => offset + 0x400,
+            Mirroring::FourScreen => addr,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::mapper::mapper::Mapper;
+
+    /// NROM cartridge with CHR RAM and vertical mirroring.
+    fn nrom() -> Cartridge {
+        Cartridge::from_rom(vec![0; 32 * 1024], Vec::new(), 0, Mirroring::Vertical).unwrap()
+    }
+
+    /// MMC2 cartridge whose 4 KiB CHR banks are filled with their bank number, with the $0000
+    /// window mapped to bank 1 on latch $FD and bank 2 on latch $FE, and the latch set to $FD.
+    fn mmc2_latched_fd() -> Cartridge {
+        let chr = (0..4u8).flat_map(|bank| [bank; 0x1000]).collect();
+        let mut cart =
+            Cartridge::from_rom(vec![0; 32 * 1024], chr, 9, Mirroring::Vertical).unwrap();
+        cart.write(0xB000, 1);
+        cart.write(0xC000, 2);
+        cart.on_chr_access(0x0FD8, 0);
+        assert_eq!(cart.read(0x0000), 1);
+        cart
+    }
+
+    /// Board whose nametable hook answers tile $42 everywhere (attributes 0); tile $42 is solid
+    /// color 1 and every other tile is blank.
+    #[derive(Clone)]
+    struct FixedTileMapper {
+        chr: Vec<u8>,
+    }
+
+    impl FixedTileMapper {
+        fn cartridge() -> Cartridge {
+            let mut chr = vec![0; 0x2000];
+            chr[0x420..0x428].fill(0xFF);
+            Cartridge::from_parts(Box::new(Self { chr }))
+        }
+    }
+
+    impl Mapper for FixedTileMapper {
+        fn read(&self, addr: u16) -> u8 {
+            self.chr.get(addr as usize).copied().unwrap_or(0)
+        }
+
+        fn write(&mut self, _addr: u16, _data: u8) {}
+
+        fn mirroring(&mut self) -> Mirroring {
+            Mirroring::Vertical
+        }
+
+        fn ppu_nametable_read(&mut self, addr: u16) -> Option<u8> {
+            Some(if addr & 0x3FF < 0x3C0 { 0x42 } else { 0 })
+        }
+
+        fn clone_box(&self) -> Box<dyn Mapper> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn clone_copies_mutated_state() {
+        let mut ppu = PPU::new(Region::Pal);
+        ppu.write_ctrl(0x90);
+        ppu.write_mask(0x1E);
+        ppu.write_addr(0x21);
+        ppu.write_addr(0x08);
+        ppu.write_oam_data(0x42);
+        ppu.scanline = 100;
+        ppu.cycle = 200;
+        ppu.framebuffer[7] = 0x123456;
+
+        let copy = ppu.clone();
+        assert_eq!((copy.ctrl, copy.mask, copy.v, copy.w), (0x90, 0x1E, 0x2108, false));
+        assert_eq!((copy.scanline, copy.cycle, copy.region), (100, 200, Region::Pal));
+        assert_eq!(copy.oam, ppu.oam);
+        assert_eq!(copy.framebuffer, ppu.framebuffer);
+    }
+
+    #[test]
+    fn enabling_nmi_during_vblank_raises_it_at_once() {
+        let mut ppu = PPU::new(Region::Ntsc);
+        ppu.vblank = true;
+        ppu.write_ctrl(0x00);
+        assert!(!ppu.nmi);
+        ppu.write_ctrl(0x80);
+        assert!(ppu.nmi);
+        // Already enabled: rewriting bit 7 is not a new edge.
+        ppu.nmi = false;
+        ppu.write_ctrl(0x80);
+        assert!(!ppu.nmi);
+    }
+
+    #[test]
+    fn toggling_nmi_enable_in_vblank_raises_another_nmi() {
+        let mut ppu = PPU::new(Region::Ntsc);
+        ppu.vblank = true;
+        ppu.write_ctrl(0x80);
+        ppu.nmi = false; // taken by the CPU
+        ppu.write_ctrl(0x00);
+        assert!(!ppu.nmi);
+        ppu.write_ctrl(0x80);
+        assert!(ppu.nmi);
+    }
+
+    #[test]
+    fn nmi_at_vblank_start_follows_ppuctrl_bit_7() {
+        for (ctrl, expected) in [(0x80, true), (0x00, false)] {
+            let mut ppu = PPU::new(Region::Ntsc);
+            ppu.write_ctrl(ctrl);
+            (ppu.scanline, ppu.cycle) = (241, 0);
+            ppu.tick();
+            assert!(ppu.vblank);
+            assert_eq!(ppu.nmi, expected, "PPUCTRL {ctrl:#04X}");
+        }
+    }
+
+    #[test]
+    fn scroll_and_address_writes_fill_loopy_registers() {
+        // The register walkthrough from PPU_scrolling "Summary".
+        let mut ppu = PPU::new(Region::Ntsc);
+        ppu.write_ctrl(0x03);
+        assert_eq!(ppu.t, 0x0C00);
+        ppu.w = true;
+        ppu.read_status();
+        assert!(!ppu.w);
+        ppu.write_scroll(0x7D); // coarse X 15, fine X 5
+        assert_eq!((ppu.t, ppu.x, ppu.w), (0x0C0F, 5, true));
+        ppu.write_scroll(0x5E); // coarse Y 11, fine Y 6
+        assert_eq!((ppu.t, ppu.w), (0x6D6F, false));
+        ppu.write_addr(0x3D);
+        assert_eq!((ppu.t, ppu.w), (0x3D6F, true));
+        ppu.write_addr(0xF0);
+        assert_eq!((ppu.t, ppu.v, ppu.w), (0x3DF0, 0x3DF0, false));
+    }
+
+    #[test]
+    fn pattern_table_viewer_leaves_chr_latches_alone() {
+        let mut cart = mmc2_latched_fd();
+        PPU::new(Region::Ntsc).render_pattern_table(&mut cart, 0, 0);
+        assert_eq!(cart.read(0x0000), 1);
+    }
+
+    #[test]
+    fn pattern_table_viewer_decodes_tiles_in_a_16x16_grid() {
+        let mut cart = nrom();
+        cart.write(0x0000, 0xF0); // table 0, tile 0, row 0: low plane
+        cart.write(0x0008, 0xCC); // high plane
+        cart.write(0x1113, 0x01); // table 1, tile $11 (second row, second column), row 3
+        let mut ppu = PPU::new(Region::Ntsc);
+        ppu.palette[..12].copy_from_slice(&[0x0F, 0, 0, 0, 0, 0, 0, 0, 0, 0x16, 0x2A, 0x12]);
+        let rgb = ppu.palette_rgb();
+
+        let image = ppu.render_pattern_table(&mut cart, 0, 2);
+        // Bit 7 is the leftmost pixel; palette row 2 is $3F09–$3F0B.
+        let row: Vec<u32> = [11, 11, 9, 9, 10, 10, 0, 0].iter().map(|&i| rgb[i]).collect();
+        assert_eq!(image[..8], row[..]);
+        assert_eq!(image[128], rgb[0]);
+
+        let image = ppu.render_pattern_table(&mut cart, 1, 2);
+        let row_start = (8 + 3) * 128 + 8;
+        assert_eq!(image[row_start + 7], rgb[9]);
+        assert!(image[row_start..row_start + 7].iter().all(|&pixel| pixel == rgb[0]));
+    }
+
+    #[test]
+    fn nametable_viewer_leaves_chr_latches_alone() {
+        let mut cart = mmc2_latched_fd();
+        let mut ppu = PPU::new(Region::Ntsc);
+        ppu.nametable[0] = 0xFE;
+        ppu.render_nametable(&mut cart, 0);
+        assert_eq!(cart.read(0x0000), 1);
+    }
+
+    #[test]
+    fn nametable_viewer_draws_tiles_with_their_attribute_palette() {
+        let mut cart = nrom(); // vertical mirroring: $2C00 shows $2400
+        for row in 0..8 {
+            cart.write(0x0050 + row, 0xFF); // tile 5: solid color 1
+        }
+        let mut ppu = PPU::new(Region::Ntsc);
+        ppu.palette[0x00] = 0x0F;
+        ppu.palette[0x0D] = 0x27;
+        ppu.nametable[0x400 + 32 + 2] = 0x05; // $2400 table, tile (2, 1)
+        ppu.nametable[0x400 + 0x3C0] = 0x0C; // top-right quadrant of the first block: palette 3
+        let rgb = ppu.palette_rgb();
+
+        // The tile covers pixels (16..24, 8..16).
+        let block = |image: &[u32; 256 * 240], color: u32| {
+            (8..16).all(|y| (16..24).all(|x| image[y * 256 + x] == color))
+        };
+        for which in [1, 3] {
+            let image = ppu.render_nametable(&mut cart, which);
+            assert!(block(&image, rgb[0x0D]), "nametable {which}");
+            assert_eq!((image[8 * 256 + 15], image[7 * 256 + 16]), (rgb[0], rgb[0]));
+        }
+        assert!(block(&ppu.render_nametable(&mut cart, 0), rgb[0]));
+    }
+
+    #[test]
+    fn sprites_decode_oam_and_palette_resolves_mirrors() {
+        let mut ppu = PPU::new(Region::Ntsc);
+        ppu.oam[8..12].copy_from_slice(&[0x40, 0x21, 0xE2, 0x80]);
+        let sprite = ppu.sprites()[2];
+        assert_eq!((sprite.y, sprite.tile, sprite.attr, sprite.x), (0x40, 0x21, 0xE2, 0x80));
+        assert_eq!(sprite.palette(), 2);
+        assert!(sprite.behind_background() && sprite.flip_horizontal() && sprite.flip_vertical());
+        ppu.palette[0] = 0x21;
+        ppu.palette[0x10] = 0x0F;
+        let rgb = ppu.palette_rgb();
+        assert_eq!(rgb[0x10], rgb[0]);
+    }
+
+    #[test]
+    fn nametable_mirror_at_3000_reads_the_same_byte_as_2000() {
+        let mut cart = nrom();
+        let mut ppu = PPU::new(Region::Ntsc);
+        let mut read_at = |ppu: &mut PPU, addr: u16| {
+            ppu.write_addr((addr >> 8) as u8);
+            ppu.write_addr(addr as u8);
+            ppu.read_data(&mut cart); // stale buffer
+            ppu.read_data(&mut cart)
+        };
+        ppu.nametable[0x123] = 0x5A;
+        assert_eq!(read_at(&mut ppu, 0x2123), 0x5A);
+        assert_eq!(read_at(&mut ppu, 0x3123), 0x5A);
+    }
+
+    #[test]
+    fn flipped_8x16_sprite_draws_its_bottom_tile_on_top() {
+        let mut ppu = PPU::new(Region::Ntsc);
+        ppu.ctrl = 0x20; // 8×16 sprites
+        // Odd tile: table $1000, tiles $24 (top) and $25 (bottom).
+        let slot = |y_offset, attr| SpriteSlot { oam_index: 0, y_offset, tile: 0x25, attr, x: 0 };
+        assert_eq!(ppu.sprite_row_addr(&slot(0, 0x00)), 0x1240);
+        assert_eq!(ppu.sprite_row_addr(&slot(8, 0x00)), 0x1250);
+        assert_eq!(ppu.sprite_row_addr(&slot(0, 0x80)), 0x1257);
+        assert_eq!(ppu.sprite_row_addr(&slot(15, 0x80)), 0x1240);
+        ppu.ctrl = 0x08; // 8×8 sprites from $1000
+        assert_eq!(ppu.sprite_row_addr(&slot(0, 0x80)), 0x1257);
+    }
+
+    #[test]
+    fn ppudata_reads_lag_one_read_except_for_palette() {
+        let mut cart = nrom();
+        let mut ppu = PPU::new(Region::Ntsc);
+        ppu.nametable[0x005] = 0xAB;
+        ppu.nametable[0x701] = 0xCD; // $2F01, under $3F01
+        ppu.palette[1] = 0x21;
+        ppu.write_addr(0x20);
+        ppu.write_addr(0x05);
+        assert_eq!(ppu.read_data(&mut cart), 0x00);
+        assert_eq!(ppu.read_data(&mut cart), 0xAB);
+
+        ppu.write_addr(0x3F);
+        ppu.write_addr(0x01);
+        assert_eq!(ppu.read_data(&mut cart) & 0x3F, 0x21);
+        assert_eq!(ppu.read_buffer, 0xCD);
+    }
+
+    #[test]
+    fn grayscale_masks_the_palette_index_for_background_and_sprites() {
+        let mut cart = nrom();
+        cart.write(0x0000, 0xFF); // tile 0, row 0: color 1
+        // Line 1: background color 1 from palette `bg`, sprite 0 at x = 100 from `sprite`.
+        let mut render = |mask: u8, bg: u8, sprite: u8| {
+            let mut ppu = PPU::new(Region::Ntsc);
+            ppu.palette[0x01] = bg;
+            ppu.palette[0x11] = sprite;
+            ppu.oam[..4].copy_from_slice(&[0, 0, 0, 100]);
+            ppu.mask = mask;
+            ppu.render_scanline(&mut cart, 1);
+            (ppu.framebuffer[256], ppu.framebuffer[256 + 100])
+        };
+        // Grayscale, background and sprites everywhere: $26 draws as $20 and $16 as $10.
+        let gray = render(0x1F, 0x26, 0x16);
+        assert_eq!(gray, render(0x1E, 0x20, 0x10));
+        assert_ne!(gray, render(0x1E, 0x26, 0x16));
+    }
+
+    #[test]
+    fn overflow_search_reads_diagonally_after_eight_sprites() {
+        let mut cart = nrom();
+        let mut ppu = PPU::new(Region::Ntsc);
+        ppu.mask = 0x18;
+        ppu.oam.fill(0xFF);
+        for sprite in 0..8 {
+            ppu.oam[sprite * 4] = 0;
+        }
+        // A 9th sprite on the line, but the search is at byte 1 (tile) by sprite 9.
+        ppu.oam[9 * 4] = 0;
+        ppu.render_scanline(&mut cart, 1);
+        assert!(!ppu.sprite_overflow);
+        // A tile number that looks like an in-range Y sets the flag instead.
+        ppu.oam[9 * 4 + 1] = 0;
+        ppu.render_scanline(&mut cart, 1);
+        assert!(ppu.sprite_overflow);
+    }
+
+    #[test]
+    fn emphasis_uses_the_512_color_table_when_loaded() {
+        let mut ppu = PPU::new(Region::Ntsc);
+        ppu.palette[0] = 0x16;
+        let plain = ppu.palette_rgb()[0];
+        ppu.mask = 0x20; // emphasize red
+        let approximated = ppu.palette_rgb()[0];
+        assert_ne!(approximated, plain);
+
+        let mut table = vec![0; 512];
+        table[0x16 | 1 << 6] = 0x123456;
+        ppu.set_palette(&table);
+        assert_eq!(ppu.palette_rgb()[0], 0x123456);
+    }
+
+    /// Dots from one vblank start to the next.
+    fn frame_dots(ppu: &mut PPU) -> usize {
+        let mut dots = 0;
+        ppu.frame_ready = false;
+        while !ppu.frame_ready {
+            ppu.tick();
+            dots += 1;
+        }
+        dots
+    }
+
+    #[test]
+    fn odd_frames_skip_a_dot_while_rendering() {
+        let mut ppu = PPU::new(Region::Ntsc);
+        ppu.mask = 0x08;
+        frame_dots(&mut ppu);
+        let (a, b) = (frame_dots(&mut ppu), frame_dots(&mut ppu));
+        assert_eq!(a.max(b), 341 * 262);
+        assert_eq!(a.min(b), 341 * 262 - 1);
+    }
+
+    #[test]
+    fn pal_frames_have_312_scanlines() {
+        let mut ppu = PPU::new(Region::Pal);
+        ppu.mask = 0x08;
+        frame_dots(&mut ppu);
+        assert_eq!(frame_dots(&mut ppu), 341 * 312);
+        assert_eq!(frame_dots(&mut ppu), 341 * 312);
+        assert_eq!(Region::Pal.scanlines_per_frame(), 312);
+    }
+
+    #[test]
+    fn mapper_nametable_hook_feeds_rendering_and_ppudata() {
+        let mut cart = FixedTileMapper::cartridge();
+        let mut ppu = PPU::new(Region::Ntsc);
+        ppu.palette[0x00] = 0x0F;
+        ppu.palette[0x01] = 0x21;
+        ppu.mask = 0x0A; // background, including the left 8 pixels
+        ppu.render_scanline(&mut cart, 0);
+        let color = ppu.palette_rgb()[1];
+        assert!(ppu.framebuffer[..256].iter().all(|&pixel| pixel == color));
+
+        // Internal nametable RAM stays blank; PPUDATA sees the cartridge's byte.
+        assert_eq!(ppu.nametable[0x005], 0);
+        ppu.write_addr(0x20);
+        ppu.write_addr(0x05);
+        ppu.read_data(&mut cart); // stale buffer
+        assert_eq!(ppu.read_data(&mut cart), 0x42);
+    }
+
+    #[test]
+    fn four_screen_nametables_do_not_alias() {
+        // iNES NROM with flags 6 bit 3 set: four-screen VRAM.
+        let mut data = b"NES\x1A".to_vec();
+        data.extend([2, 1, 0x08, 0]);
+        data.resize(16 + 48 * 1024, 0);
+        let mut cart = Cartridge::from_bytes(&data).unwrap();
+        assert_eq!(cart.mapper.mirroring(), Mirroring::FourScreen);
+
+        let mut ppu = PPU::new(Region::Ntsc);
+        let tables = [0x2000u16, 0x2400, 0x2800, 0x2C00];
+        for (i, &addr) in tables.iter().enumerate() {
+            ppu.write_addr((addr >> 8) as u8);
+            ppu.write_addr(addr as u8);
+            ppu.write_data(&mut cart, 0x10 + i as u8);
+        }
+        for (i, &addr) in tables.iter().enumerate() {
+            ppu.write_addr((addr >> 8) as u8);
+            ppu.write_addr(addr as u8);
+            ppu.read_data(&mut cart); // stale buffer
+            assert_eq!(ppu.read_data(&mut cart), 0x10 + i as u8, "${addr:04X}");
         }
     }
 }
@@ -0,0 +1,59 @@
+//! Console region (TV system) and its timing constants.
+//!
+//! See [Cycle reference chart](https://www.nesdev.org/wiki/Cycle_reference_chart): NTSC runs the
+//! 2A03 at 1.789773 MHz with 29780.5 CPU cycles per frame (~60.0988 Hz); PAL runs the 2A07 at
+//! 1.662607 MHz with 33247.5 CPU cycles per frame (~50.0070 Hz).
+
+use std::time::Duration;
+
+/// NTSC (North America, Japan) or PAL (Europe, Australia) console timing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Region {
+    #[default]
+    Ntsc,
+    Pal,
+}
+
+impl Region {
+    /// CPU clock in Hz (master clock ÷ 12 for NTSC, ÷ 16 for PAL).
+    pub fn cpu_clock_hz(self) -> f64 {
+        match self {
+            Region::Ntsc => 1_789_773.0,
+            Region::Pal => 1_662_607.0,
+        }
+    }
+
+    /// Average CPU cycles per video frame.
+    pub fn cpu_cycles_per_frame(self) -> f64 {
+        match self {
+            Region::Ntsc => 29_780.5,
+            Region::Pal => 33_247.5,
+        }
+    }
+
+    /// PPU scanlines per frame, including vblank and the pre-render line.
+    pub fn scanlines_per_frame(self) -> u16 {
+        match self {
+            Region::Ntsc => 262,
+            Region::Pal => 312,
+        }
+    }
+
+    /// PPU dots per CPU cycle as a ratio (dots, CPU cycles): 3 for NTSC, 3.2 (16/5) for PAL.
+    pub fn ppu_dots_per_cpu_cycle(self) -> (u32, u32) {
+        match self {
+            Region::Ntsc => (3, 1),
+            Region::Pal => (16, 5),
+        }
+    }
+
+    /// Video frame rate in Hz (~60.0988 NTSC, ~50.0070 PAL).
+    pub fn frame_rate(self) -> f64 {
+        self.cpu_clock_hz() / self.cpu_cycles_per_frame()
+    }
+
+    /// Wall-clock duration of one frame (~16.64 ms NTSC, ~20.00 ms PAL).
+    pub fn frame_duration(self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.frame_rate())
+    }
+}
@@ -0,0 +1,112 @@
+//! Screenshots: encode an RGB frame as a PNG file.
+//!
+//! A minimal [PNG](https://www.w3.org/TR/png/) writer with no dependencies: 8-bit RGB, no
+//! interlacing, and image data in uncompressed ("stored") deflate blocks. Files are larger than a
+//! real compressor would make them (~180 KiB for a full 256×240 frame) but open everywhere.
+//! Crop to the displayed area first with [`Overscan::crop`](crate::display::Overscan::crop).
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Largest payload of one stored deflate block.
+const STORED_BLOCK_MAX: usize = 0xFFFF;
+
+/// Encode `width`×`height` pixels (0xRRGGBB, row-major) as a PNG.
+pub fn encode_png(pixels: &[u32], width: usize, height: usize) -> Vec<u8> {
+    assert_eq!(pixels.len(), width * height, "pixel count must match the image size");
+
+    // Raw image data: each row is filter type 0 (none) followed by R, G, B per pixel.
+    let mut raw = Vec::with_capacity(height * (1 + width * 3));
+    for row in pixels.chunks_exact(width.max(1)) {
+        raw.push(0);
+        for &rgb in row {
+            raw.extend_from_slice(&[(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8]);
+        }
+    }
+
+    // zlib stream: header (deflate, no preset dictionary), stored blocks, Adler-32.
+    let mut zlib = vec![0x78, 0x01];
+    let mut blocks = raw.chunks(STORED_BLOCK_MAX).peekable();
+    if blocks.peek().is_none() {
+        zlib.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        zlib.push(last as u8);
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&(width as u32).to_be_bytes());
+    ihdr.extend_from_slice(&(height as u32).to_be_bytes());
+    // Bit depth 8, color type 2 (RGB), deflate, adaptive filtering, no interlace.
+    ihdr.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    write_chunk(&mut png, b"IHDR", &ihdr);
+    write_chunk(&mut png, b"IDAT", &zlib);
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Encode and write a PNG to `path`.
+pub fn write_png(path: &Path, pixels: &[u32], width: usize, height: usize) -> io::Result<()> {
+    fs::write(path, encode_png(pixels, width, height))
+}
+
+/// Append a chunk: length, type, data, CRC-32 of type and data.
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// CRC-32 (IEEE, reflected), bit by bit; screenshots are rare enough not to need a table.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::Overscan;
+
+    #[test]
+    fn cropped_8px_overscan_encodes_240x224() {
+        let mut visible = Vec::new();
+        Overscan::uniform(8).crop(&vec![0x123456; 256 * 240], &mut visible);
+        let png = encode_png(&visible, 240, 224);
+        // IHDR data follows the 8-byte signature, the chunk length and "IHDR".
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(png[16..20], 240u32.to_be_bytes());
+        assert_eq!(png[20..24], 224u32.to_be_bytes());
+    }
+}